@@ -0,0 +1,191 @@
+// Albums whose cover.jpg is missing or smaller than the preset, and filling
+// them from the album's own artwork, the tags of its tracks or the Cover
+// Art Archive, through the same conversion as everything else.
+
+use crate::library::{self, Album};
+use crate::pipeline::{self, CancelToken};
+use crate::preset::{OutputFormat, Preset};
+use crate::{ArtCoverError, ProcessOptions, coverart, embedded, lock};
+use lofty::file::TaggedFileExt;
+use lofty::tag::ItemKey;
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Finding {
+    Missing,
+    // A cover.jpg that does not decode
+    Unreadable,
+    // Width and height of a cover below the preset's size
+    TooSmall(u32, u32),
+}
+
+// An album that needs a cover and why
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Gap {
+    pub album: Album,
+    pub finding: Finding,
+}
+
+// Where the new cover came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Found {
+    Artwork(PathBuf),
+    // The track whose tags held the picture
+    Embedded(PathBuf),
+    // MusicBrainz release id
+    CoverArtArchive(String),
+}
+
+// Albums of a scan lacking a cover at least as large as `preset` asks for
+pub fn audit(albums: &[Album], preset: &Preset) -> Vec<Gap> {
+    let (min_width, min_height) = preset.max_size();
+    albums
+        .iter()
+        .filter_map(|album| {
+            let cover = album.destination();
+            let finding = if !cover.is_file() {
+                Finding::Missing
+            } else {
+                match image::image_dimensions(&cover) {
+                    Err(_) => Finding::Unreadable,
+                    Ok((width, height)) if width < min_width || height < min_height => {
+                        Finding::TooSmall(width, height)
+                    }
+                    Ok(_) => return None,
+                }
+            };
+            Some(Gap {
+                album: album.clone(),
+                finding,
+            })
+        })
+        .collect()
+}
+
+// Write a cover.jpg for the album of `gap` from the first source that is
+// better than what it has: its artwork files, the pictures in the tags of
+// its tracks, then, when `online`, the Cover Art Archive. Ok(None) when
+// none is.
+pub fn fix(
+    gap: &Gap,
+    options: &ProcessOptions,
+    online: bool,
+    cancel: &CancelToken,
+) -> Result<Option<Found>, ArtCoverError> {
+    let album = &gap.album;
+    if let Some(source) = &album.source
+        && improves(gap.finding, image::image_dimensions(source).ok())
+    {
+        // The cover it has is the one found lacking
+        library::convert_album(album, options, true, cancel)?;
+        return Ok(Some(Found::Artwork(source.clone())));
+    }
+
+    let tracks = tracks(&album.folder).map_err(|e| ArtCoverError::io(&album.folder, e))?;
+    for track in &tracks {
+        if cancel.is_cancelled() {
+            return Err(ArtCoverError::Cancelled);
+        }
+        if let Ok(artwork) = embedded::read_artwork(track)
+            && improves(gap.finding, dimensions(&artwork))
+        {
+            embedded::extract_cover(track, options, true, &mut |_| {}, cancel)?;
+            return Ok(Some(Found::Embedded(track.clone())));
+        }
+    }
+
+    if !online {
+        return Ok(None);
+    }
+    let Some(release_id) = release_id(album, &tracks)? else {
+        return Ok(None);
+    };
+    if cancel.is_cancelled() {
+        return Err(ArtCoverError::Cancelled);
+    }
+    let Some(artwork) = coverart::front_cover(&release_id)? else {
+        return Ok(None);
+    };
+    if !improves(gap.finding, dimensions(&artwork)) {
+        return Ok(None);
+    }
+
+    let mut options = options.clone();
+    options.preset.format = OutputFormat::Jpeg;
+    let source = format!("Cover Art Archive {}", release_id);
+    let encoded = pipeline::convert_bytes_from(&artwork, &source, &options)?;
+    let destination = album.destination();
+    let _lock = lock::acquire(&destination, cancel)?;
+    fs::write(&destination, encoded.bytes).map_err(|e| ArtCoverError::io(&destination, e))?;
+    tracing::info!(output = %destination.display(), release_id, "cover downloaded");
+    Ok(Some(Found::CoverArtArchive(release_id)))
+}
+
+// Any readable image replaces a missing one; a small one only gives way
+// to a larger one
+fn improves(finding: Finding, candidate: Option<(u32, u32)>) -> bool {
+    match (finding, candidate) {
+        (_, None) => false,
+        (Finding::Missing | Finding::Unreadable, Some(_)) => true,
+        (Finding::TooSmall(width, height), Some((new_width, new_height))) => {
+            new_width as u64 * new_height as u64 > width as u64 * height as u64
+        }
+    }
+}
+
+fn dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    image::ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()
+}
+
+// The album's audio files, sorted so the first track is asked first
+fn tracks(folder: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut tracks = Vec::new();
+    for entry in fs::read_dir(folder)? {
+        let path = entry?.path();
+        if path.is_file() && library::is_audio(&path) {
+            tracks.push(path);
+        }
+    }
+    tracks.sort();
+    Ok(tracks)
+}
+
+// The MusicBrainz release in the tags, or else the one found by the album
+// and artist in the tags or, lacking those, the folder names
+fn release_id(album: &Album, tracks: &[PathBuf]) -> Result<Option<String>, ArtCoverError> {
+    let tagged = tracks
+        .first()
+        .and_then(|track| lofty::read_from_path(track).ok());
+    let tag = tagged
+        .as_ref()
+        .and_then(|tagged| tagged.primary_tag().or_else(|| tagged.first_tag()));
+    let text = |key: ItemKey| {
+        tag.and_then(|tag| tag.get_string(key))
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    };
+
+    if let Some(release_id) = text(ItemKey::MusicBrainzReleaseId) {
+        return Ok(Some(release_id));
+    }
+
+    let folder_name = |path: Option<&Path>| {
+        path.and_then(Path::file_name)
+            .map(|name| name.to_string_lossy().into_owned())
+    };
+    let title = text(ItemKey::AlbumTitle).or_else(|| folder_name(Some(&album.folder)));
+    let artist = text(ItemKey::AlbumArtist)
+        .or_else(|| text(ItemKey::TrackArtist))
+        .or_else(|| folder_name(album.folder.parent()));
+    match (artist, title) {
+        (Some(artist), Some(title)) => coverart::search(&artist, &title),
+        _ => Ok(None),
+    }
+}
@@ -0,0 +1,90 @@
+use crate::ArtCoverError;
+use crate::preset::Preset;
+use crate::resize::{self, ResizeMode};
+use image::DynamicImage;
+use image::error::{DecodingError, ImageFormatHint};
+use pdfium_render::prelude::{PdfRenderConfig, Pdfium, PdfiumError};
+use std::path::Path;
+use std::sync::OnceLock;
+
+// PDFium can only be bound once per process
+static PDFIUM: OnceLock<Result<Pdfium, String>> = OnceLock::new();
+
+pub fn is_pdf(path: &Path) -> bool {
+    path.extension()
+        .and_then(|s| s.to_str())
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("pdf"))
+}
+
+// Render page `page` of a booklet (the last one if there are fewer) at the
+// preset resolution, along with the page count
+pub fn render_page(
+    path: &Path,
+    page: usize,
+    preset: &Preset,
+    mode: ResizeMode,
+) -> Result<(DynamicImage, usize), ArtCoverError> {
+    let pdfium = pdfium()?;
+    let document = pdfium
+        .load_pdf_from_file(path, None)
+        .map_err(|e| invalid(path, e))?;
+
+    let pages = document.pages();
+    let count = pages.len().max(0) as usize;
+    if count == 0 {
+        return Err(ArtCoverError::UnsupportedFormat(
+            "PDF without pages".to_string(),
+        ));
+    }
+
+    let index = page.min(count - 1);
+    let page = pages.get(index as _).map_err(|e| invalid(path, e))?;
+    let (width, height) =
+        resize::render_size(page.width().value, page.height().value, preset, mode);
+
+    let config = PdfRenderConfig::new().set_target_size(width as i32, height as i32);
+    let img = page
+        .render_with_config(&config)
+        .and_then(|bitmap| bitmap.as_image())
+        .map_err(|e| invalid(path, e))?;
+
+    Ok((img, count))
+}
+
+// Whether PDFium could be loaded
+pub fn available() -> Result<(), ArtCoverError> {
+    pdfium().map(|_| ())
+}
+
+// The library shipped next to the program wins over a system-wide one
+fn pdfium() -> Result<&'static Pdfium, ArtCoverError> {
+    PDFIUM
+        .get_or_init(|| {
+            let bundled = std::env::current_exe()
+                .ok()
+                .and_then(|exe| {
+                    exe.parent()
+                        .map(Pdfium::pdfium_platform_library_name_at_path)
+                })
+                .map(Pdfium::bind_to_library);
+
+            match bundled {
+                Some(Ok(bindings)) => Ok(bindings),
+                _ => Pdfium::bind_to_system_library(),
+            }
+            .map(Pdfium::new)
+            .map_err(|e| e.to_string())
+        })
+        .as_ref()
+        .map_err(|message| ArtCoverError::PdfUnavailable(message.clone()))
+}
+
+fn invalid(path: &Path, error: PdfiumError) -> ArtCoverError {
+    ArtCoverError::Decode {
+        path: path.to_path_buf(),
+        source: image::ImageError::Decoding(DecodingError::new(
+            ImageFormatHint::Name("PDF".to_string()),
+            error,
+        )),
+    }
+}
@@ -0,0 +1,379 @@
+use artcover_image_conversor::settings::{self, Bundle};
+use artcover_image_conversor::{
+    ArtCoverError, CancelToken, ProcessOptions, ProcessedImage, convert_bytes, i18n, overrides,
+    process_image_with,
+};
+use serde::Serialize;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+// Exit statuses besides success; 2 is invalid usage
+const ALL_FAILED: u8 = 1;
+const SOME_FAILED: u8 = 3;
+
+const USAGE: &str = "\
+Usage: artcover_image_conversor convert [options] <files>...
+       artcover_image_conversor convert --stdin [settings] < image > cover
+       artcover_image_conversor --daemon [socket] [settings]
+
+Options:
+  --progress json-lines    Print progress events as JSON, one per line
+  --json                   Print a JSON report of each file, one per line
+  --stdin                  Convert the image read from stdin, write it to stdout
+  -h, --help               Show this help
+
+Exit status: 0 when every file was converted, 1 when none was, 3 when
+only some were, 2 for invalid arguments or settings.
+
+Settings, over the saved ones. Each can also be set with the environment
+variable shown; flags win over variables, variables over the saved file.
+A profile, from a flag or a variable, is applied first and the other
+settings over it.
+";
+
+// USAGE followed by every overridable setting
+pub fn usage() -> String {
+    let mut usage = USAGE.to_string();
+    for (setting, values) in overrides::SETTINGS {
+        let flag = format!("--{} {}", setting, values);
+        usage += &format!("  {:<40} {}\n", flag, overrides::env_name(setting));
+    }
+    usage
+}
+
+// How progress is reported while converting
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Progress {
+    // One line per converted or failed file, for people
+    #[default]
+    Text,
+    // Events on stdout for wrappers drawing their own progress
+    JsonLines,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Args {
+    pub files: Vec<PathBuf>,
+    // Settings flags in the order given, by name
+    pub overrides: Vec<(String, String)>,
+    pub progress: Progress,
+    // A report line per file instead of the text lines
+    pub json: bool,
+    // One image from stdin to stdout, for shell pipelines
+    pub stdin: bool,
+    pub help: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DaemonArgs {
+    // Socket path, or port where Unix sockets are not available
+    pub address: Option<String>,
+    pub overrides: Vec<(String, String)>,
+}
+
+// One line of `--progress json-lines`
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum Event<'a> {
+    Started {
+        file: &'a Path,
+        index: usize,
+        total: usize,
+    },
+    Progress {
+        file: &'a Path,
+        percent: u8,
+    },
+    Finished {
+        file: &'a Path,
+        output: &'a Path,
+        warnings: &'a [String],
+    },
+    Failed {
+        file: &'a Path,
+        error: String,
+    },
+}
+
+// One line of `--json`
+#[derive(Debug, Serialize)]
+struct Report<'a> {
+    input: &'a Path,
+    output: Option<&'a Path>,
+    // None when the file is not a bitmap (drawings, booklets, audio)
+    original_size: Option<Size>,
+    final_size: Option<Size>,
+    // Negative when the output is larger
+    bytes_saved: Option<i64>,
+    warnings: &'a [String],
+    error: Option<String>,
+}
+
+impl<'a> Report<'a> {
+    fn new(
+        input: &'a Path,
+        original_size: Option<Size>,
+        input_bytes: Option<u64>,
+        result: &'a Result<ProcessedImage, ArtCoverError>,
+    ) -> Self {
+        match result {
+            Ok(processed) => Report {
+                input,
+                output: Some(&processed.path),
+                original_size,
+                final_size: size(&processed.path),
+                bytes_saved: input_bytes
+                    .zip(file_size(&processed.path))
+                    .map(|(input, output)| input as i64 - output as i64),
+                warnings: &processed.warnings,
+                error: None,
+            },
+            Err(e) => Report {
+                input,
+                output: None,
+                original_size,
+                final_size: None,
+                bytes_saved: None,
+                warnings: &[],
+                error: Some(e.user_message()),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Size {
+    width: u32,
+    height: u32,
+}
+
+fn size(path: &Path) -> Option<Size> {
+    image::image_dimensions(path)
+        .ok()
+        .map(|(width, height)| Size { width, height })
+}
+
+fn file_size(path: &Path) -> Option<u64> {
+    std::fs::metadata(path).map(|m| m.len()).ok()
+}
+
+// Arguments after "convert"
+pub fn parse(args: &[String]) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--progress" => {
+                parsed.progress = match args.next().map(String::as_str) {
+                    Some("json-lines") => Progress::JsonLines,
+                    Some("text") => Progress::Text,
+                    _ => return Err("--progress takes json-lines or text".to_string()),
+                };
+            }
+            "--json" => parsed.json = true,
+            "--stdin" => parsed.stdin = true,
+            "-h" | "--help" => parsed.help = true,
+            option if option.starts_with("--") => {
+                parsed.overrides.push(setting(option, &mut args)?);
+            }
+            file => parsed.files.push(PathBuf::from(file)),
+        }
+    }
+
+    // Only one thing can be written to stdout
+    if parsed.json && parsed.progress == Progress::JsonLines {
+        return Err("--json cannot be used with --progress json-lines".to_string());
+    }
+    if parsed.stdin {
+        if !parsed.files.is_empty() {
+            return Err("--stdin converts stdin, not files".to_string());
+        }
+        if parsed.progress == Progress::JsonLines || parsed.json {
+            return Err("--stdin writes the image to stdout, not JSON".to_string());
+        }
+    } else if parsed.files.is_empty() && !parsed.help {
+        return Err("no files to convert".to_string());
+    }
+    Ok(parsed)
+}
+
+// Arguments after "--daemon": an optional socket and settings flags
+pub fn parse_daemon(args: &[String]) -> Result<DaemonArgs, String> {
+    let mut parsed = DaemonArgs::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg.starts_with("--") {
+            parsed.overrides.push(setting(arg, &mut args)?);
+        } else if parsed.address.is_none() {
+            parsed.address = Some(arg.clone());
+        } else {
+            return Err(format!("unexpected argument {}", arg));
+        }
+    }
+    Ok(parsed)
+}
+
+// "--jpeg-quality 85", or "--jpeg-quality=85"
+fn setting<'a>(
+    option: &str,
+    args: &mut impl Iterator<Item = &'a String>,
+) -> Result<(String, String), String> {
+    let option = &option[2..];
+    let (name, value) = match option.split_once('=') {
+        Some((name, value)) => (name, Some(value.to_string())),
+        None => (option, args.next().cloned()),
+    };
+    if !overrides::is_setting(name) {
+        return Err(format!("unknown option --{}", name));
+    }
+    let value = value.ok_or_else(|| format!("--{} needs a value", name))?;
+    Ok((name.to_string(), value))
+}
+
+// Saved settings with the environment variables and then `flags` applied
+// over them. The interface language is set from the result.
+pub fn load_settings(flags: &[(String, String)]) -> Result<Bundle, String> {
+    let mut bundle = settings::load_saved().unwrap_or_default();
+    overrides::apply_all(&mut bundle, std::env::vars(), flags)?;
+    i18n::set_language(bundle.language);
+    Ok(bundle)
+}
+
+// Convert every file with the saved settings and the overrides. The exit
+// status tells whether all, some or none of the files failed.
+pub fn run(args: &Args) -> ExitCode {
+    if args.help {
+        print!("{}", usage());
+        return ExitCode::SUCCESS;
+    }
+
+    let options = match load_settings(&args.overrides) {
+        Ok(bundle) => bundle.options,
+        Err(message) => {
+            eprintln!("Error: {}", message);
+            return ExitCode::from(2);
+        }
+    };
+
+    if args.stdin {
+        return run_stdin(&options);
+    }
+
+    let json = args.progress == Progress::JsonLines;
+    let mut failed = Vec::new();
+    for (index, file) in args.files.iter().enumerate() {
+        if json {
+            emit(&Event::Started {
+                file,
+                index,
+                total: args.files.len(),
+            });
+        }
+
+        // Only whole percent steps forward, so fast files do not flood the
+        // output and the decoder seeking back never shows as going backwards
+        let mut last = None;
+        let mut report = |done: f32| {
+            let percent = (done.clamp(0.0, 1.0) * 100.0) as u8;
+            if json && last.is_none_or(|last| percent > last) {
+                last = Some(percent);
+                emit(&Event::Progress { file, percent });
+            }
+        };
+
+        // Measured first; the output can replace the source
+        let (original_size, input_bytes) = if args.json {
+            (size(file), file_size(file))
+        } else {
+            (None, None)
+        };
+        let result = process_image_with(file, &options, &mut report, &CancelToken::default());
+        if let Err(e) = &result {
+            failed.push(file);
+            tracing::error!("{}", e.report());
+        }
+
+        match &result {
+            _ if args.json => emit(&Report::new(file, original_size, input_bytes, &result)),
+            Ok(processed) if json => emit(&Event::Finished {
+                file,
+                output: &processed.path,
+                warnings: &processed.warnings,
+            }),
+            Ok(processed) => {
+                println!("{} -> {}", file.display(), processed.path.display());
+                for warning in &processed.warnings {
+                    eprintln!("Warning: {}", warning);
+                }
+            }
+            Err(e) if json => emit(&Event::Failed {
+                file,
+                error: e.user_message(),
+            }),
+            Err(e) => eprintln!("Error: {}: {}", file.display(), e.user_message()),
+        }
+    }
+
+    // Listed again at the end, where a long batch leaves them easy to find
+    if !json && !args.json && failed.len() > 1 {
+        eprintln!("{} of {} files failed:", failed.len(), args.files.len());
+        for file in &failed {
+            eprintln!("  {}", file.display());
+        }
+    }
+
+    match failed.len() {
+        0 => ExitCode::SUCCESS,
+        failed if failed == args.files.len() => ExitCode::from(ALL_FAILED),
+        _ => ExitCode::from(SOME_FAILED),
+    }
+}
+
+// `curl ... | artcover_image_conversor convert --stdin > cover.jpg`.
+// Warnings and errors go to stderr, stdout only gets the image.
+fn run_stdin(options: &ProcessOptions) -> ExitCode {
+    if io::stdout().is_terminal() {
+        eprintln!("Error: stdout is a terminal, redirect it to a file or another program");
+        return ExitCode::from(2);
+    }
+
+    let mut input = Vec::new();
+    if let Err(e) = io::stdin().lock().read_to_end(&mut input) {
+        eprintln!("Error: stdin could not be read: {}", e);
+        return ExitCode::FAILURE;
+    }
+
+    match convert_bytes(&input, options) {
+        Ok(converted) => {
+            for warning in &converted.warnings {
+                eprintln!("Warning: {}", warning);
+            }
+            let mut stdout = io::stdout().lock();
+            match stdout
+                .write_all(&converted.bytes)
+                .and_then(|_| stdout.flush())
+            {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(e) => {
+                    eprintln!("Error: stdout could not be written: {}", e);
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        Err(e) => {
+            tracing::error!("{}", e.report());
+            eprintln!("Error: {}", e.user_message());
+            ExitCode::FAILURE
+        }
+    }
+}
+
+// Written and flushed at once, so readers see each event as it happens
+fn emit(line: &impl Serialize) {
+    if let Ok(json) = serde_json::to_string(line) {
+        let mut stdout = io::stdout().lock();
+        let _ = writeln!(stdout, "{}", json);
+        let _ = stdout.flush();
+    }
+}
@@ -0,0 +1,96 @@
+use crate::ArtCoverError;
+use crate::preset::Preset;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+
+// Preset files are tiny; anything bigger is not one
+const DOWNLOAD_LIMIT: u64 = 1024 * 1024;
+
+// Static JSON feed listing shared presets:
+// {"presets": [{"name", "description", "url", "sha256"}]}
+#[derive(Debug, Clone, Deserialize)]
+pub struct Index {
+    pub presets: Vec<IndexEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct IndexEntry {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    // Relative URLs are resolved against the index location
+    pub url: String,
+    // Hex digest of the preset file, checked before anything is parsed
+    pub sha256: String,
+}
+
+pub fn fetch_index(location: &str) -> Result<Index, ArtCoverError> {
+    let bytes = fetch(location)?;
+    serde_json::from_slice(&bytes).map_err(|e| ArtCoverError::Download {
+        url: location.to_string(),
+        message: format!("not a preset index: {}", e),
+    })
+}
+
+// Download one preset of the index and verify it against its digest
+pub fn fetch_preset(index_location: &str, entry: &IndexEntry) -> Result<Preset, ArtCoverError> {
+    let location = resolve(index_location, &entry.url);
+    let bytes = fetch(&location)?;
+
+    if sha256_hex(&bytes) != entry.sha256.trim().to_lowercase() {
+        return Err(ArtCoverError::ChecksumMismatch(entry.name.clone()));
+    }
+
+    serde_json::from_slice(&bytes).map_err(|e| ArtCoverError::Download {
+        url: location,
+        message: format!("not a preset: {}", e),
+    })
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+// http(s) URLs are downloaded, anything else is read as a local path
+// (mirrors on a USB stick or a network share)
+fn fetch(location: &str) -> Result<Vec<u8>, ArtCoverError> {
+    if !is_remote(location) {
+        let path = Path::new(location);
+        return fs::read(path).map_err(|e| ArtCoverError::io(path, e));
+    }
+
+    ureq::get(location)
+        .call()
+        .and_then(|mut response| {
+            response
+                .body_mut()
+                .with_config()
+                .limit(DOWNLOAD_LIMIT)
+                .read_to_vec()
+        })
+        .map_err(|e| ArtCoverError::Download {
+            url: location.to_string(),
+            message: e.to_string(),
+        })
+}
+
+fn resolve(index_location: &str, url: &str) -> String {
+    if url.contains("://") || Path::new(url).is_absolute() {
+        return url.to_string();
+    }
+    if is_remote(index_location) {
+        let base = index_location.rsplit_once('/').map_or("", |(base, _)| base);
+        return format!("{}/{}", base, url);
+    }
+    let base = Path::new(index_location).parent().unwrap_or(Path::new(""));
+    base.join(url).display().to_string()
+}
+
+fn is_remote(location: &str) -> bool {
+    location.starts_with("http://") || location.starts_with("https://")
+}
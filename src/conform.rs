@@ -0,0 +1,128 @@
+use crate::i18n::t;
+use crate::probe::InputEncoding;
+use crate::quantize::ColorMode;
+use crate::{ArtCoverError, ProcessOptions, resize};
+use image::{ColorType, ImageDecoder, ImageFormat, ImageReader};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+// Larger files are re-encoded even if everything else already fits; the
+// players load the whole file into their small art cache
+pub const MAX_BYTES: u64 = 512 * 1024;
+
+// What happens to a source that needs no change
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Passthrough {
+    // Decode and encode like any other image
+    Off,
+    #[default]
+    Copy,
+    // Falls back to a copy across file systems
+    HardLink,
+}
+
+impl Passthrough {
+    pub const ALL: [Passthrough; 3] = [Passthrough::Off, Passthrough::Copy, Passthrough::HardLink];
+}
+
+impl fmt::Display for Passthrough {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Passthrough::Off => "Always re-encode",
+            Passthrough::Copy => "Copy conforming files",
+            Passthrough::HardLink => "Link conforming files",
+        };
+        write!(f, "{}", t(label))
+    }
+}
+
+// Whether converting `source` into `destination` would change nothing:
+// same format, no resize or color reduction, a plain color space, a
+// modest file size and no encoding the devices reject
+pub fn conforms(
+    source: &Path,
+    destination: &Path,
+    encoding: &InputEncoding,
+    options: &ProcessOptions,
+) -> bool {
+    if options.passthrough == Passthrough::Off
+        || options.auto_trim
+        || options.preset.bit_depth.is_some()
+        || options.color_mode != ColorMode::Full
+        || options.crop.is_some()
+        || options.preset.lut.is_some()
+        // A copy carries neither the provenance note nor the pinned encode
+        || options.provenance
+        || options.deterministic
+        || encoding.needs_normalization(options.baseline_jpeg)
+    {
+        return false;
+    }
+
+    let size = fs::metadata(source).map(|m| m.len()).unwrap_or(u64::MAX);
+    if size > MAX_BYTES {
+        return false;
+    }
+
+    let (Ok(source_format), Ok(output_format)) = (
+        ImageFormat::from_path(source),
+        ImageFormat::from_path(destination),
+    ) else {
+        return false;
+    };
+    // An unoptimized PNG is not kept when optimization was asked for
+    if source_format != output_format || (options.optimize_png && output_format == ImageFormat::Png)
+    {
+        return false;
+    }
+
+    // Only the header is read
+    let Some(((width, height), color)) = ImageReader::open(source)
+        .ok()
+        .and_then(|reader| reader.with_guessed_format().ok())
+        .and_then(|reader| reader.into_decoder().ok())
+        .map(|decoder| (decoder.dimensions(), decoder.color_type()))
+    else {
+        return false;
+    };
+
+    let plain_color = matches!(color, ColorType::L8 | ColorType::Rgb8 | ColorType::Rgba8);
+
+    plain_color
+        && resize::is_unchanged(
+            width,
+            height,
+            options.resize_mode,
+            options.upscale,
+            &options.preset,
+        )
+}
+
+// Put the untouched source at `destination`
+pub fn pass_through(
+    source: &Path,
+    destination: &Path,
+    mode: Passthrough,
+) -> Result<(), ArtCoverError> {
+    // Already in place (cover.jpg converted to cover.jpg)
+    if let (Ok(a), Ok(b)) = (source.canonicalize(), destination.canonicalize())
+        && a == b
+    {
+        return Ok(());
+    }
+
+    // An earlier link would make the copy truncate the source itself
+    if destination.exists() {
+        fs::remove_file(destination).map_err(|e| ArtCoverError::io(destination, e))?;
+    }
+
+    if mode == Passthrough::HardLink && fs::hard_link(source, destination).is_ok() {
+        return Ok(());
+    }
+
+    fs::copy(source, destination)
+        .map(|_| ())
+        .map_err(|e| ArtCoverError::io(destination, e))
+}
@@ -0,0 +1,97 @@
+// Front covers from the Cover Art Archive, for albums with no artwork of
+// their own. Releases are found by the MusicBrainz id in the tags, or else
+// by searching MusicBrainz for the artist and album names.
+
+use crate::ArtCoverError;
+use serde::Deserialize;
+use std::thread;
+use std::time::Duration;
+
+const SEARCH_URL: &str = "https://musicbrainz.org/ws/2/release/";
+const COVER_URL: &str = "https://coverartarchive.org/release";
+// MusicBrainz refuses requests without a name it can reach the sender by
+const USER_AGENT: &str = concat!(
+    "ArtCover/",
+    env!("CARGO_PKG_VERSION"),
+    " ( https://github.com/holairs/ArtCover-Image-Converter )"
+);
+// MusicBrainz allows one search a second
+const SEARCH_INTERVAL: Duration = Duration::from_secs(1);
+// Search results scored lower are more likely another album
+const MIN_SCORE: u32 = 90;
+// Larger than any preset, smaller than most originals
+const COVER_SIZE: u32 = 1200;
+const DOWNLOAD_LIMIT: u64 = 16 * 1024 * 1024;
+
+#[derive(Debug, Deserialize)]
+struct SearchResults {
+    releases: Vec<Release>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Release {
+    id: String,
+    #[serde(default)]
+    score: u32,
+}
+
+// Id of the best matching release, if one matches well enough
+pub fn search(artist: &str, album: &str) -> Result<Option<String>, ArtCoverError> {
+    let query = format!(
+        "release:\"{}\" AND artist:\"{}\"",
+        quote(album),
+        quote(artist)
+    );
+    let response = ureq::get(SEARCH_URL)
+        .header("User-Agent", USER_AGENT)
+        .query("query", &query)
+        .query("fmt", "json")
+        .query("limit", "1")
+        .call()
+        .and_then(|mut response| response.body_mut().read_to_string());
+    thread::sleep(SEARCH_INTERVAL);
+
+    let body = response.map_err(|e| download_error(SEARCH_URL, e))?;
+    let results: SearchResults =
+        serde_json::from_str(&body).map_err(|e| ArtCoverError::Download {
+            url: SEARCH_URL.to_string(),
+            message: e.to_string(),
+        })?;
+    Ok(results
+        .releases
+        .into_iter()
+        .find(|release| release.score >= MIN_SCORE)
+        .map(|release| release.id))
+}
+
+// Front cover of a release; None when the archive has none
+pub fn front_cover(release_id: &str) -> Result<Option<Vec<u8>>, ArtCoverError> {
+    let url = format!("{}/{}/front-{}", COVER_URL, release_id, COVER_SIZE);
+    let response = ureq::get(&url)
+        .header("User-Agent", USER_AGENT)
+        .call()
+        .and_then(|mut response| {
+            response
+                .body_mut()
+                .with_config()
+                .limit(DOWNLOAD_LIMIT)
+                .read_to_vec()
+        });
+    match response {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(ureq::Error::StatusCode(404)) => Ok(None),
+        Err(e) => Err(download_error(&url, e)),
+    }
+}
+
+fn download_error(url: &str, error: ureq::Error) -> ArtCoverError {
+    ArtCoverError::Download {
+        url: url.to_string(),
+        message: error.to_string(),
+    }
+}
+
+// Inside the quotes of a Lucene query
+fn quote(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
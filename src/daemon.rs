@@ -0,0 +1,281 @@
+use crate::preset::{self, Preset};
+use crate::{ArtCoverError, CancelToken, ProcessOptions, convert_to, process_image, settings};
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+
+// Loopback port used where Unix sockets are not available
+#[cfg(not(unix))]
+pub const DEFAULT_PORT: u16 = 47800;
+
+// One conversion asked by a client, sent as a single JSON line:
+// {"path": "/music/front.jpg", "preset": "iPod (JPEG)"}
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Request {
+    pub path: PathBuf,
+    // Name of a built-in or imported preset; the saved one when missing
+    #[serde(default)]
+    pub preset: Option<String>,
+    // Fixed output file instead of the name derived from the source; only
+    // in the source's own folder, so a client cannot write anywhere else,
+    // and never the source itself
+    #[serde(default)]
+    pub destination: Option<PathBuf>,
+    // Contents of the token file, where the daemon listens on a TCP port
+    // any local user can reach
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+// Answer to a request, one JSON line per request
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Response {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub warnings: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+// Settings every request starts from
+struct Defaults {
+    options: ProcessOptions,
+    presets: Vec<Preset>,
+    // Required in every request when set
+    token: Option<String>,
+}
+
+// Where the daemon listens when no socket is given: the per-user runtime
+// folder, or else a folder of the config folder only the user can enter.
+// Never the shared temporary folder, where anyone could reach it.
+#[cfg(unix)]
+pub fn default_socket() -> Result<PathBuf, ArtCoverError> {
+    use std::os::unix::fs::DirBuilderExt;
+
+    if let Some(runtime) = std::env::var_os("XDG_RUNTIME_DIR") {
+        return Ok(PathBuf::from(runtime).join("artcover.sock"));
+    }
+    let folder = settings::saved_path()
+        .as_deref()
+        .and_then(Path::parent)
+        .map(|config| config.join("daemon"))
+        .ok_or_else(|| {
+            ArtCoverError::io(
+                Path::new("artcover.sock"),
+                io::Error::new(io::ErrorKind::NotFound, "no per-user folder for the socket"),
+            )
+        })?;
+    std::fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(&folder)
+        .map_err(|e| ArtCoverError::io(&folder, e))?;
+    Ok(folder.join("artcover.sock"))
+}
+
+// File holding the token clients of the TCP port must send, in the config
+// folder of the user running the daemon
+#[cfg(not(unix))]
+pub fn token_path() -> Option<PathBuf> {
+    Some(settings::saved_path()?.with_file_name("daemon.token"))
+}
+
+// Serve conversions until the process is stopped. Each connection gets
+// its own thread and may send any number of requests.
+#[cfg(unix)]
+pub fn serve(
+    socket: &Path,
+    options: ProcessOptions,
+    presets: Vec<Preset>,
+) -> Result<(), ArtCoverError> {
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    // A socket left by a daemon that did not shut down cleanly; a live one
+    // still answers and is not taken over
+    if socket.exists() {
+        if UnixStream::connect(socket).is_ok() {
+            return Err(ArtCoverError::io(
+                socket,
+                io::Error::new(io::ErrorKind::AddrInUse, "another daemon is running"),
+            ));
+        }
+        std::fs::remove_file(socket).map_err(|e| ArtCoverError::io(socket, e))?;
+    }
+
+    let listener = UnixListener::bind(socket).map_err(|e| ArtCoverError::io(socket, e))?;
+    // Only the user may connect, whatever folder the socket is in
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(socket, std::fs::Permissions::from_mode(0o600))
+            .map_err(|e| ArtCoverError::io(socket, e))?;
+    }
+    let defaults = Arc::new(Defaults {
+        options,
+        presets,
+        token: None,
+    });
+    for stream in listener.incoming().flatten() {
+        let defaults = Arc::clone(&defaults);
+        thread::spawn(move || {
+            // A client that hangs up early only ends its own connection
+            let _ = serve_connection(&stream, &stream, &defaults);
+        });
+    }
+    Ok(())
+}
+
+// Windows and others: a TCP port on the loopback interface, same protocol.
+// Other users of the machine can reach the port too, so every request must
+// carry the token written into `token_path()` when the daemon starts.
+#[cfg(not(unix))]
+pub fn serve(
+    port: u16,
+    options: ProcessOptions,
+    presets: Vec<Preset>,
+) -> Result<(), ArtCoverError> {
+    use std::net::TcpListener;
+
+    let token = new_token();
+    let token_file = token_path().ok_or_else(|| {
+        ArtCoverError::io(
+            Path::new("daemon.token"),
+            io::Error::new(io::ErrorKind::NotFound, "no per-user folder for the token"),
+        )
+    })?;
+    if let Some(folder) = token_file.parent() {
+        std::fs::create_dir_all(folder).map_err(|e| ArtCoverError::io(folder, e))?;
+    }
+    std::fs::write(&token_file, &token).map_err(|e| ArtCoverError::io(&token_file, e))?;
+
+    let address = format!("127.0.0.1:{}", port);
+    let listener =
+        TcpListener::bind(&address).map_err(|e| ArtCoverError::io(Path::new(&address), e))?;
+    let defaults = Arc::new(Defaults {
+        options,
+        presets,
+        token: Some(token),
+    });
+    for stream in listener.incoming().flatten() {
+        let defaults = Arc::clone(&defaults);
+        thread::spawn(move || {
+            let _ = serve_connection(&stream, &stream, &defaults);
+        });
+    }
+    Ok(())
+}
+
+fn serve_connection(
+    reader: impl Read,
+    mut writer: impl Write,
+    defaults: &Defaults,
+) -> io::Result<()> {
+    for line in BufReader::new(reader).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => handle(&request, defaults),
+            Err(e) => failure(format!("invalid request: {}", e)),
+        };
+        let json = serde_json::to_string(&response).map_err(io::Error::other)?;
+        writeln!(writer, "{}", json)?;
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+// A new secret per start: the hasher keys of the standard library come
+// from the operating system's random source
+#[cfg(not(unix))]
+fn new_token() -> String {
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+
+    (0..4)
+        .map(|round| {
+            let mut hasher = RandomState::new().build_hasher();
+            hasher.write_u32(std::process::id());
+            hasher.write_u32(round);
+            format!("{:016x}", hasher.finish())
+        })
+        .collect()
+}
+
+fn handle(request: &Request, defaults: &Defaults) -> Response {
+    if defaults.token.is_some() && request.token != defaults.token {
+        return failure("invalid or missing token".to_string());
+    }
+    if let Some(destination) = &request.destination {
+        if !in_source_folder(&request.path, destination) {
+            return failure(format!(
+                "destination {} is not in the folder of {}",
+                destination.display(),
+                request.path.display()
+            ));
+        }
+        if crate::is_same_file(&request.path, destination) {
+            return failure(format!(
+                "destination {} is the source itself",
+                destination.display()
+            ));
+        }
+    }
+
+    let mut options = defaults.options.clone();
+    if let Some(name) = &request.preset {
+        match preset::find(name, &defaults.presets) {
+            Some(preset) => options.preset = preset,
+            None => return failure(format!("unknown preset: {}", name)),
+        }
+    }
+
+    let result = match &request.destination {
+        Some(destination) => convert_to(
+            &request.path,
+            destination,
+            &options,
+            &mut |_| {},
+            &CancelToken::default(),
+        ),
+        None => process_image(&request.path, &options),
+    };
+    match result {
+        Ok(processed) => Response {
+            ok: true,
+            output: Some(processed.path),
+            warnings: processed.warnings,
+            error: None,
+        },
+        Err(e) => {
+            tracing::error!("{}", e.report());
+            failure(e.user_message())
+        }
+    }
+}
+
+// Both in the same existing folder, links resolved, and an image name
+fn in_source_folder(source: &Path, destination: &Path) -> bool {
+    let folder = |path: &Path| {
+        path.parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .unwrap_or(Path::new("."))
+            .canonicalize()
+            .ok()
+    };
+    crate::is_supported_image(destination)
+        && folder(source).is_some()
+        && folder(source) == folder(destination)
+}
+
+fn failure(message: String) -> Response {
+    Response {
+        ok: false,
+        error: Some(message),
+        ..Response::default()
+    }
+}
@@ -0,0 +1,182 @@
+use crate::ArtCoverError;
+use crate::phash;
+use image::GenericImageView;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Hashes closer than this are considered the same cover
+pub const DEFAULT_THRESHOLD: u32 = 6;
+
+#[derive(Debug, Clone)]
+pub struct CoverFile {
+    pub path: PathBuf,
+    pub width: u32,
+    pub height: u32,
+    pub bytes: u64,
+}
+
+// Perceptually similar covers; `keep` is the file that survives
+#[derive(Debug, Clone)]
+pub struct Cluster {
+    pub files: Vec<CoverFile>,
+    pub keep: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateAction {
+    Delete,
+    Link,
+}
+
+// Group the images below `dir` by perceptual similarity. Only clusters with
+// more than one file are returned, best candidate preselected.
+pub fn scan(dir: &Path, threshold: u32) -> Result<Vec<Cluster>, ArtCoverError> {
+    let mut paths = Vec::new();
+    collect_images(dir, &mut paths).map_err(|e| ArtCoverError::io(dir, e))?;
+
+    let mut files = Vec::new();
+    let mut hashes = Vec::new();
+
+    for path in paths {
+        // Unreadable files are not duplicates of anything
+        let Ok(img) = image::open(&path) else {
+            continue;
+        };
+        let (width, height) = img.dimensions();
+        let bytes = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+
+        hashes.push(phash::dhash(&img));
+        files.push(CoverFile {
+            path,
+            width,
+            height,
+            bytes,
+        });
+    }
+
+    // Union-find over every pair below the threshold
+    let mut parent: Vec<usize> = (0..files.len()).collect();
+    for i in 0..files.len() {
+        for j in (i + 1)..files.len() {
+            if phash::distance(hashes[i], hashes[j]) <= threshold {
+                let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+                parent[b] = a;
+            }
+        }
+    }
+
+    let mut groups: Vec<(usize, Vec<CoverFile>)> = Vec::new();
+    for (index, file) in files.into_iter().enumerate() {
+        let group = root(&mut parent, index);
+        match groups.iter_mut().find(|(id, _)| *id == group) {
+            Some((_, members)) => members.push(file),
+            None => groups.push((group, vec![file])),
+        }
+    }
+
+    Ok(groups
+        .into_iter()
+        .filter(|(_, members)| members.len() > 1)
+        .map(|(_, files)| {
+            let keep = best_index(&files);
+            Cluster { files, keep }
+        })
+        .collect())
+}
+
+// What happened to the duplicates of a cluster
+#[derive(Debug, Default)]
+pub struct Resolution {
+    pub handled: usize,
+    // Duplicates left in place, with why
+    pub failed: Vec<(PathBuf, ArtCoverError)>,
+}
+
+// Remove or hard-link every file of the cluster except the kept one. A
+// file that fails is left as it was and the others are still handled.
+pub fn resolve(cluster: &Cluster, action: DuplicateAction) -> Resolution {
+    let best = &cluster.files[cluster.keep].path;
+    let mut resolution = Resolution::default();
+
+    for (index, file) in cluster.files.iter().enumerate() {
+        if index == cluster.keep {
+            continue;
+        }
+        let result = match action {
+            DuplicateAction::Delete => {
+                fs::remove_file(&file.path).map_err(|e| ArtCoverError::io(&file.path, e))
+            }
+            DuplicateAction::Link => link(best, &file.path),
+        };
+        match result {
+            Ok(()) => resolution.handled += 1,
+            Err(error) => resolution.failed.push((file.path.clone(), error)),
+        }
+    }
+
+    resolution
+}
+
+// Replace `duplicate` with a hard link to `best`. The link keeps the
+// duplicate's name but must match the kept format, so it may get another
+// extension; it is made under a temporary name and renamed into place, and
+// the duplicate is only removed once the link exists.
+fn link(best: &Path, duplicate: &Path) -> Result<(), ArtCoverError> {
+    let link = match best.extension() {
+        Some(extension) => duplicate.with_extension(extension),
+        None => duplicate.to_path_buf(),
+    };
+    let exists = || ArtCoverError::io(&link, io::Error::from(io::ErrorKind::AlreadyExists));
+    if link == best || (link != duplicate && link.exists()) {
+        return Err(exists());
+    }
+
+    let mut temporary_name = OsString::from(".");
+    temporary_name.push(link.file_name().unwrap_or_default());
+    temporary_name.push(".link");
+    let temporary = link.with_file_name(temporary_name);
+    fs::hard_link(best, &temporary).map_err(|e| ArtCoverError::io(&temporary, e))?;
+    if let Err(e) = fs::rename(&temporary, &link) {
+        let _ = fs::remove_file(&temporary);
+        return Err(ArtCoverError::io(&link, e));
+    }
+    if link != duplicate {
+        fs::remove_file(duplicate).map_err(|e| ArtCoverError::io(duplicate, e))?;
+    }
+    Ok(())
+}
+
+// Highest resolution wins, larger file (less compression) breaks ties
+fn best_index(files: &[CoverFile]) -> usize {
+    files
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, file)| (file.width as u64 * file.height as u64, file.bytes))
+        .map(|(index, _)| index)
+        .unwrap_or(0)
+}
+
+fn root(parent: &mut [usize], mut index: usize) -> usize {
+    while parent[index] != index {
+        parent[index] = parent[parent[index]];
+        index = parent[index];
+    }
+    index
+}
+
+fn collect_images(dir: &Path, paths: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        // Not through symlinks: a link would list the same covers twice and
+        // a link to a parent would never end
+        if entry.file_type()?.is_dir() {
+            collect_images(&path, paths)?;
+        } else if crate::is_supported_image(&path) {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use crate::library::COVER_FILE_NAME;
+use crate::pipeline::{self, CancelToken, Stage};
+use crate::preset::OutputFormat;
+use crate::{ArtCoverError, ProcessOptions, ProcessedImage, lock, probe, retry};
+use lofty::file::{FileType, TaggedFile, TaggedFileExt};
+use lofty::picture::{Picture, PictureType};
+use lofty::probe::Probe;
+use std::fs;
+use std::io::{self, Cursor};
+use std::path::Path;
+
+// Picture stored in the tags of an audio file (ID3 APIC, FLAC PICTURE,
+// MP4 covr...), the front cover if several are present
+pub fn read_artwork(path: &Path) -> Result<Vec<u8>, ArtCoverError> {
+    let tagged = lofty::read_from_path(path).map_err(|e| tag_read(path, e))?;
+
+    front_cover(&tagged).ok_or_else(|| ArtCoverError::NoEmbeddedArt(path.to_path_buf()))
+}
+
+// Same as read_artwork, from the contents of `path` already read
+fn artwork_in(path: &Path, contents: Vec<u8>) -> Result<Vec<u8>, ArtCoverError> {
+    let probe = Probe::new(Cursor::new(contents));
+    let probe = match FileType::from_path(path) {
+        Some(file_type) => probe.set_file_type(file_type),
+        None => probe
+            .guess_file_type()
+            .map_err(|e| ArtCoverError::io(path, e))?,
+    };
+    let tagged = probe.read().map_err(|e| tag_read(path, e))?;
+
+    front_cover(&tagged).ok_or_else(|| ArtCoverError::NoEmbeddedArt(path.to_path_buf()))
+}
+
+fn tag_read(path: &Path, error: lofty::error::FileParseError) -> ArtCoverError {
+    ArtCoverError::TagRead {
+        path: path.to_path_buf(),
+        message: error.to_string(),
+    }
+}
+
+pub fn front_cover(tagged: &TaggedFile) -> Option<Vec<u8>> {
+    let pictures: Vec<&Picture> = tagged
+        .tags()
+        .iter()
+        .flat_map(|tag| tag.pictures())
+        .collect();
+    pictures
+        .iter()
+        .find(|picture| picture.pic_type() == PictureType::CoverFront)
+        .or_else(|| pictures.first())
+        .map(|picture| picture.data().to_vec())
+}
+
+// Resize the embedded artwork of `path` into a cover.jpg next to the track.
+// A cover.jpg already there is only replaced when `replace` is set.
+pub fn extract_cover(
+    path: &Path,
+    options: &ProcessOptions,
+    replace: bool,
+    progress: &mut dyn FnMut(f32),
+    cancel: &CancelToken,
+) -> Result<ProcessedImage, ArtCoverError> {
+    let _span = tracing::info_span!("extract", source = %path.display()).entered();
+    let destination = path.with_file_name(COVER_FILE_NAME);
+    let _lock = lock::acquire(&destination, cancel)?;
+    if !replace && destination.exists() {
+        return Err(ArtCoverError::io(
+            &destination,
+            io::Error::from(io::ErrorKind::AlreadyExists),
+        ));
+    }
+
+    // Reading the track can fail for a moment (network shares), parsing
+    // its tags fails the same way every time
+    let contents = retry::with_retries(options.retries, cancel, || {
+        fs::read(path).map_err(|e| ArtCoverError::io(path, e))
+    })?;
+    let bytes = artwork_in(path, contents)?;
+    let warnings = probe::probe_bytes(&bytes).warnings();
+
+    let img = pipeline::decode_artwork(&bytes, path, &options.preset)?;
+    progress(Stage::Decode.completed_at());
+
+    let mut options = options.clone();
+    options.preset.format = OutputFormat::Jpeg;
+
+    pipeline::convert_decoded(
+        img,
+        path,
+        &destination,
+        warnings,
+        &options,
+        progress,
+        cancel,
+    )
+}
@@ -0,0 +1,86 @@
+use crate::ArtCoverError;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use image::{DynamicImage, ImageFormat};
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+
+// Quality used when nothing else is configured
+pub const DEFAULT_JPEG_QUALITY: u8 = 90;
+
+// oxipng preset: a good size/time balance for cover-sized images
+const PNG_OPTIMIZATION_LEVEL: u8 = 3;
+
+// Encoder parameters. Every field is set explicitly so outputs do not drift
+// when the image crate changes its defaults.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncodeSettings {
+    pub jpeg_quality: u8,
+    pub png_compression: CompressionType,
+    pub png_filter: FilterType,
+}
+
+impl EncodeSettings {
+    // Fixed parameters of the determinism mode, independent of user tweaks
+    pub const PINNED: EncodeSettings = EncodeSettings {
+        jpeg_quality: DEFAULT_JPEG_QUALITY,
+        png_compression: CompressionType::Default,
+        png_filter: FilterType::Adaptive,
+    };
+
+    pub fn with_quality(jpeg_quality: u8) -> Self {
+        Self {
+            jpeg_quality,
+            ..Self::PINNED
+        }
+    }
+}
+
+// Encode in memory first so a failed encode never leaves a partial file
+pub fn save(
+    img: &DynamicImage,
+    path: &Path,
+    settings: &EncodeSettings,
+) -> Result<(), ArtCoverError> {
+    let bytes = encode(img, path, settings).map_err(|e| ArtCoverError::encode(path, e))?;
+    fs::write(path, bytes).map_err(|e| ArtCoverError::io(path, e))
+}
+
+// Lossless recompression of an encoded PNG: filter and deflate trials plus
+// palette, bit depth and color type reductions. Returns the smaller file,
+// if one was found.
+pub fn optimize_png(bytes: &[u8]) -> Option<Vec<u8>> {
+    let options = oxipng::Options::from_preset(PNG_OPTIMIZATION_LEVEL);
+    oxipng::optimize_from_memory(bytes, &options)
+        .ok()
+        .filter(|optimized| optimized.len() < bytes.len())
+}
+
+pub fn encode(
+    img: &DynamicImage,
+    path: &Path,
+    settings: &EncodeSettings,
+) -> Result<Vec<u8>, image::ImageError> {
+    let format = ImageFormat::from_path(path)?;
+    let mut bytes = Vec::new();
+
+    match format {
+        ImageFormat::Jpeg => {
+            img.write_with_encoder(JpegEncoder::new_with_quality(
+                &mut bytes,
+                settings.jpeg_quality,
+            ))?;
+        }
+        ImageFormat::Png => {
+            img.write_with_encoder(PngEncoder::new_with_quality(
+                &mut bytes,
+                settings.png_compression,
+                settings.png_filter,
+            ))?;
+        }
+        format => img.write_to(&mut Cursor::new(&mut bytes), format)?,
+    }
+
+    Ok(bytes)
+}
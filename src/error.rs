@@ -0,0 +1,194 @@
+use crate::i18n::{t, tf};
+use std::error::Error;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ArtCoverError {
+    #[error("failed to decode {}", path.display())]
+    Decode {
+        path: PathBuf,
+        #[source]
+        source: image::ImageError,
+    },
+
+    #[error("unsupported format: {0}")]
+    UnsupportedFormat(String),
+
+    #[error("failed to resize: {0}")]
+    Resize(String),
+
+    #[error("failed to encode {}", path.display())]
+    Encode {
+        path: PathBuf,
+        #[source]
+        source: image::ImageError,
+    },
+
+    #[error("i/o error on {}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("failed to read tags from {}: {message}", path.display())]
+    TagRead { path: PathBuf, message: String },
+
+    #[error("no embedded artwork in {}", .0.display())]
+    NoEmbeddedArt(PathBuf),
+
+    #[error("invalid Rockbox theme: {0}")]
+    InvalidTheme(String),
+
+    #[error("failed to download {url}: {message}")]
+    Download { url: String, message: String },
+
+    #[error("checksum mismatch for {0}")]
+    ChecksumMismatch(String),
+
+    #[error("PDFium library not available: {0}")]
+    PdfUnavailable(String),
+
+    #[error("invalid color LUT: {0}")]
+    InvalidLut(String),
+
+    #[error("{} is locked by another process", .0.display())]
+    Locked(PathBuf),
+
+    #[error("{} is too large to decode ({width}x{height})", path.display())]
+    TooLarge {
+        path: PathBuf,
+        width: u32,
+        height: u32,
+    },
+
+    #[error("invalid settings bundle: {0}")]
+    InvalidBundle(String),
+
+    #[error("cancelled")]
+    Cancelled,
+}
+
+impl ArtCoverError {
+    pub fn io(path: &Path, source: io::Error) -> Self {
+        ArtCoverError::Io {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+
+    // Errors from image::open distinguish unreadable files from bad data
+    pub fn decode(path: &Path, source: image::ImageError) -> Self {
+        match source {
+            image::ImageError::IoError(e) => ArtCoverError::io(path, e),
+            image::ImageError::Unsupported(e) => ArtCoverError::UnsupportedFormat(e.to_string()),
+            source => ArtCoverError::Decode {
+                path: path.to_path_buf(),
+                source,
+            },
+        }
+    }
+
+    pub fn encode(path: &Path, source: image::ImageError) -> Self {
+        match source {
+            image::ImageError::IoError(e) => ArtCoverError::io(path, e),
+            source => ArtCoverError::Encode {
+                path: path.to_path_buf(),
+                source,
+            },
+        }
+    }
+
+    // Short explanation for the status line
+    pub fn user_message(&self) -> String {
+        match self {
+            ArtCoverError::Decode { path, .. } => {
+                tf("{} is damaged or not an image", &[&file_name(path)])
+            }
+            ArtCoverError::UnsupportedFormat(_) => {
+                t("This image format is not supported").to_string()
+            }
+            ArtCoverError::Resize(_) => t("The image could not be resized").to_string(),
+            ArtCoverError::Encode { path, .. } => tf(
+                "{} could not be written in this format",
+                &[&file_name(path)],
+            ),
+            ArtCoverError::Io { path, source } => match source.kind() {
+                io::ErrorKind::NotFound => tf("{} was not found", &[&file_name(path)]),
+                io::ErrorKind::PermissionDenied => {
+                    tf("Permission denied for {}", &[&file_name(path)])
+                }
+                io::ErrorKind::AddrInUse => tf("{} is already in use", &[&file_name(path)]),
+                io::ErrorKind::AlreadyExists => tf("{} already exists", &[&file_name(path)]),
+                _ => tf("{} could not be accessed", &[&file_name(path)]),
+            },
+            ArtCoverError::TagRead { path, .. } => {
+                tf("The tags of {} could not be read", &[&file_name(path)])
+            }
+            ArtCoverError::NoEmbeddedArt(path) => {
+                tf("{} has no embedded artwork", &[&file_name(path)])
+            }
+            ArtCoverError::InvalidTheme(message) => message.clone(),
+            ArtCoverError::Download { .. } => t("The download failed").to_string(),
+            ArtCoverError::ChecksumMismatch(name) => tf(
+                "{} does not match its checksum and was not imported",
+                &[&name],
+            ),
+            ArtCoverError::PdfUnavailable(_) => {
+                t("PDF booklets need the PDFium library (pdfium) next to the program").to_string()
+            }
+            ArtCoverError::InvalidLut(message) => tf("The color LUT is invalid: {}", &[&message]),
+            ArtCoverError::Locked(path) => tf(
+                "{} is being written by another process, try again later",
+                &[&file_name(path)],
+            ),
+            ArtCoverError::TooLarge {
+                path,
+                width,
+                height,
+            } => tf(
+                "{} is too large to convert ({}x{} pixels)",
+                &[&file_name(path), width, height],
+            ),
+            ArtCoverError::InvalidBundle(_) => t("This is not a valid settings file").to_string(),
+            ArtCoverError::Cancelled => t("Cancelled").to_string(),
+        }
+    }
+
+    // Failures that can go away by trying again a little later, such as a
+    // file another process is still writing
+    pub fn is_transient(&self) -> bool {
+        let ArtCoverError::Io { source, .. } = self else {
+            return matches!(self, ArtCoverError::Locked(_));
+        };
+        matches!(
+            source.kind(),
+            io::ErrorKind::Interrupted
+                | io::ErrorKind::WouldBlock
+                | io::ErrorKind::TimedOut
+                | io::ErrorKind::ResourceBusy
+                | io::ErrorKind::StaleNetworkFileHandle
+                | io::ErrorKind::NetworkDown
+                | io::ErrorKind::ConnectionReset
+                | io::ErrorKind::ConnectionAborted
+        )
+    }
+
+    // Full cause chain for logs
+    pub fn report(&self) -> String {
+        let mut report = self.to_string();
+        let mut source = self.source();
+        while let Some(cause) = source {
+            report.push_str(&format!(": {}", cause));
+            source = cause.source();
+        }
+        report
+    }
+}
+
+pub(crate) fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
@@ -0,0 +1,147 @@
+use crate::pipeline::{self, CancelToken};
+use crate::preset::Preset;
+use crate::rockbox::{self, Target};
+use crate::{ArtCoverError, ProcessOptions, ProcessedImage, naming};
+use std::path::{Path, PathBuf};
+
+// One output of a multi-size export. Profiles are processed in list order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportProfile {
+    pub kind: ProfileKind,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProfileKind {
+    // One file named after the source
+    Single(Preset),
+    // The cover.jpg / cover.WxH.jpg set of a Rockbox target, written into
+    // the album directory
+    Rockbox(Target),
+}
+
+impl ExportProfile {
+    pub fn name(&self) -> String {
+        match &self.kind {
+            ProfileKind::Single(preset) => preset.name.clone(),
+            ProfileKind::Rockbox(target) => format!("Rockbox set: {}", target.name),
+        }
+    }
+}
+
+// Outcome of one profile for one source
+#[derive(Debug)]
+pub struct ProfileResult {
+    pub profile: String,
+    pub result: Result<ProcessedImage, ArtCoverError>,
+}
+
+// Every preset as a profile, all disabled
+pub fn profiles(presets: &[Preset]) -> Vec<ExportProfile> {
+    presets
+        .iter()
+        .map(|preset| ExportProfile {
+            kind: ProfileKind::Single(preset.clone()),
+            enabled: false,
+        })
+        .collect()
+}
+
+// One profile per known Rockbox target, all disabled
+pub fn rockbox_profiles() -> Vec<ExportProfile> {
+    rockbox::TARGETS
+        .iter()
+        .map(|target| ExportProfile {
+            kind: ProfileKind::Rockbox(*target),
+            enabled: false,
+        })
+        .collect()
+}
+
+// Move the profile at `from` to `to`, shifting the ones in between
+pub fn move_profile(profiles: &mut Vec<ExportProfile>, from: usize, to: usize) {
+    if from == to || from >= profiles.len() || to >= profiles.len() {
+        return;
+    }
+    let profile = profiles.remove(from);
+    profiles.insert(to, profile);
+}
+
+// "<stem>_<profile>_processed.<ext>", so sizes do not overwrite each other
+pub fn output_path(source: &Path, preset: &Preset) -> PathBuf {
+    let stem = source
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("image");
+    let extension = preset
+        .format
+        .extension(source.extension().and_then(|s| s.to_str()).unwrap_or("png"));
+
+    naming::output_path(
+        source,
+        Some(&format!("{}_{}", stem, slug(&preset.name))),
+        extension,
+        preset.ascii_names,
+    )
+}
+
+// Convert `path` once per enabled profile. Progress covers the whole
+// export; a cancellation stops before the remaining profiles. Rockbox sets
+// replace the cover files already in the album only with `replace_art`.
+pub fn export(
+    path: &Path,
+    options: &ProcessOptions,
+    profiles: &[ExportProfile],
+    replace_art: bool,
+    progress: &mut dyn FnMut(f32),
+    cancel: &CancelToken,
+) -> Vec<ProfileResult> {
+    let enabled: Vec<&ExportProfile> = profiles.iter().filter(|p| p.enabled).collect();
+    let mut results = Vec::new();
+
+    for (index, profile) in enabled.iter().enumerate() {
+        let share = 1.0 / enabled.len() as f32;
+        let mut progress = |done| progress((index as f32 + done) * share);
+
+        let outputs = match &profile.kind {
+            ProfileKind::Single(preset) => {
+                let mut options = options.clone();
+                options.preset = preset.clone();
+                let destination = output_path(path, preset);
+                vec![pipeline::convert_to(
+                    path,
+                    &destination,
+                    &options,
+                    &mut progress,
+                    cancel,
+                )]
+            }
+            ProfileKind::Rockbox(target) => {
+                rockbox::export_album_art(path, target, options, replace_art, &mut progress, cancel)
+            }
+        };
+
+        let mut cancelled = false;
+        for result in outputs {
+            cancelled |= matches!(result, Err(ArtCoverError::Cancelled));
+            results.push(ProfileResult {
+                profile: profile.name(),
+                result,
+            });
+        }
+        if cancelled {
+            break;
+        }
+    }
+
+    results
+}
+
+// "iPod (JPEG)" -> "ipod-jpeg"
+fn slug(name: &str) -> String {
+    name.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
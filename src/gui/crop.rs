@@ -0,0 +1,352 @@
+// Square selection over an image: dragged from inside to move it, by a
+// corner to resize it, anywhere else to draw a new one. The canvas of iced
+// 0.12 cannot draw images, so this is a widget drawing both itself.
+
+use artcover_image_conversor::resize::Square;
+use iced::advanced::layout::{self, Layout};
+use iced::advanced::renderer::{self, Quad};
+use iced::advanced::widget::{Tree, tree};
+use iced::advanced::{Clipboard, Shell, Widget, image};
+use iced::widget::image::{FilterMethod, Handle};
+use iced::{Border, Color, Element, Event, Length, Point, Rectangle, Size, event, mouse};
+
+// Reach of the corners around the pointer, in screen pixels
+const CORNER: f32 = 10.0;
+const SHADE: Color = Color::from_rgba(0.0, 0.0, 0.0, 0.6);
+
+pub struct CropEditor<'a, Message> {
+    handle: Handle,
+    // Size of the source; the selection is in its pixels
+    width: u32,
+    height: u32,
+    selection: Square,
+    // Longest side on screen
+    side: f32,
+    on_change: Box<dyn Fn(Square) -> Message + 'a>,
+}
+
+pub fn crop_editor<'a, Message>(
+    handle: Handle,
+    (width, height): (u32, u32),
+    selection: Square,
+    side: f32,
+    on_change: impl Fn(Square) -> Message + 'a,
+) -> CropEditor<'a, Message> {
+    CropEditor {
+        handle,
+        width: width.max(1),
+        height: height.max(1),
+        selection,
+        side,
+        on_change: Box::new(on_change),
+    }
+}
+
+// Points are in source pixels
+#[derive(Debug, Clone, Copy, Default)]
+enum Drag {
+    #[default]
+    Idle,
+    // Where the selection was grabbed, from its top left corner
+    Move {
+        grab: Point,
+    },
+    // The corner that stays in place
+    Resize {
+        anchor: Point,
+    },
+}
+
+impl<Message> CropEditor<'_, Message> {
+    fn scale(&self) -> f32 {
+        self.side / self.width.max(self.height) as f32
+    }
+
+    fn shown(&self) -> Size {
+        let scale = self.scale();
+        Size::new(self.width as f32 * scale, self.height as f32 * scale)
+    }
+
+    // The selection on screen
+    fn frame(&self, bounds: Rectangle) -> Rectangle {
+        let scale = self.scale();
+        Rectangle {
+            x: bounds.x + self.selection.x as f32 * scale,
+            y: bounds.y + self.selection.y as f32 * scale,
+            width: self.selection.side as f32 * scale,
+            height: self.selection.side as f32 * scale,
+        }
+    }
+
+    // Source pixel under a screen point, kept inside the image
+    fn to_source(&self, bounds: Rectangle, point: Point) -> Point {
+        let scale = self.scale();
+        Point::new(
+            ((point.x - bounds.x) / scale).clamp(0.0, self.width as f32),
+            ((point.y - bounds.y) / scale).clamp(0.0, self.height as f32),
+        )
+    }
+
+    // The corner of the selection opposite the one near `point`
+    fn opposite_corner(&self, bounds: Rectangle, point: Point) -> Option<Point> {
+        let frame = self.frame(bounds);
+        let Square { x, y, side } = self.selection;
+        let (left, top, right, bottom) = (x as f32, y as f32, (x + side) as f32, (y + side) as f32);
+        [
+            (frame.x, frame.y, Point::new(right, bottom)),
+            (frame.x + frame.width, frame.y, Point::new(left, bottom)),
+            (frame.x, frame.y + frame.height, Point::new(right, top)),
+            (
+                frame.x + frame.width,
+                frame.y + frame.height,
+                Point::new(left, top),
+            ),
+        ]
+        .into_iter()
+        .find(|(corner_x, corner_y, _)| {
+            (point.x - corner_x).abs() <= CORNER && (point.y - corner_y).abs() <= CORNER
+        })
+        .map(|(_, _, opposite)| opposite)
+    }
+
+    fn dragged(&self, drag: Drag, to: Point) -> Option<Square> {
+        let (width, height) = (self.width as f32, self.height as f32);
+        match drag {
+            Drag::Idle => None,
+            Drag::Move { grab } => Some(
+                Square {
+                    x: (to.x - grab.x).max(0.0) as u32,
+                    y: (to.y - grab.y).max(0.0) as u32,
+                    side: self.selection.side,
+                }
+                .within(self.width, self.height),
+            ),
+            // The square grows from the anchor toward the pointer, as far
+            // as the image goes in that direction
+            Drag::Resize { anchor } => {
+                let room_x = if to.x >= anchor.x {
+                    width - anchor.x
+                } else {
+                    anchor.x
+                };
+                let room_y = if to.y >= anchor.y {
+                    height - anchor.y
+                } else {
+                    anchor.y
+                };
+                let side = (to.x - anchor.x)
+                    .abs()
+                    .max((to.y - anchor.y).abs())
+                    .min(room_x)
+                    .min(room_y)
+                    .max(1.0);
+                let x = if to.x >= anchor.x {
+                    anchor.x
+                } else {
+                    anchor.x - side
+                };
+                let y = if to.y >= anchor.y {
+                    anchor.y
+                } else {
+                    anchor.y - side
+                };
+                Some(
+                    Square {
+                        x: x.round() as u32,
+                        y: y.round() as u32,
+                        side: side.round() as u32,
+                    }
+                    .within(self.width, self.height),
+                )
+            }
+        }
+    }
+}
+
+impl<Message, Theme, Renderer> Widget<Message, Theme, Renderer> for CropEditor<'_, Message>
+where
+    Renderer: image::Renderer<Handle = Handle>,
+{
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<Drag>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(Drag::default())
+    }
+
+    fn size(&self) -> Size<Length> {
+        let shown = self.shown();
+        Size::new(Length::Fixed(shown.width), Length::Fixed(shown.height))
+    }
+
+    fn layout(
+        &self,
+        _tree: &mut Tree,
+        _renderer: &Renderer,
+        _limits: &layout::Limits,
+    ) -> layout::Node {
+        layout::Node::new(self.shown())
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        _renderer: &Renderer,
+        _clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        _viewport: &Rectangle,
+    ) -> event::Status {
+        let bounds = layout.bounds();
+        let drag = tree.state.downcast_mut::<Drag>();
+        match event {
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                let Some(point) = cursor.position_over(bounds) else {
+                    return event::Status::Ignored;
+                };
+                let at = self.to_source(bounds, point);
+                *drag = match self.opposite_corner(bounds, point) {
+                    Some(anchor) => Drag::Resize { anchor },
+                    None if self.frame(bounds).contains(point) => Drag::Move {
+                        grab: Point::new(
+                            at.x - self.selection.x as f32,
+                            at.y - self.selection.y as f32,
+                        ),
+                    },
+                    None => Drag::Resize { anchor: at },
+                };
+                event::Status::Captured
+            }
+            Event::Mouse(mouse::Event::CursorMoved { position }) => {
+                match self.dragged(*drag, self.to_source(bounds, position)) {
+                    Some(square) => {
+                        self.selection = square;
+                        shell.publish((self.on_change)(square));
+                        event::Status::Captured
+                    }
+                    None => event::Status::Ignored,
+                }
+            }
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left))
+                if !matches!(drag, Drag::Idle) =>
+            {
+                *drag = Drag::Idle;
+                event::Status::Captured
+            }
+            _ => event::Status::Ignored,
+        }
+    }
+
+    fn draw(
+        &self,
+        _tree: &Tree,
+        renderer: &mut Renderer,
+        _theme: &Theme,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor: mouse::Cursor,
+        _viewport: &Rectangle,
+    ) {
+        let bounds = layout.bounds();
+        renderer.draw(self.handle.clone(), FilterMethod::Linear, bounds);
+
+        // The parts left out are shaded
+        let frame = self.frame(bounds);
+        let (right, bottom) = (bounds.x + bounds.width, bounds.y + bounds.height);
+        let (frame_right, frame_bottom) = (frame.x + frame.width, frame.y + frame.height);
+        let shaded = [
+            Rectangle::new(
+                bounds.position(),
+                Size::new(bounds.width, frame.y - bounds.y),
+            ),
+            Rectangle::new(
+                Point::new(bounds.x, frame_bottom),
+                Size::new(bounds.width, bottom - frame_bottom),
+            ),
+            Rectangle::new(
+                Point::new(bounds.x, frame.y),
+                Size::new(frame.x - bounds.x, frame.height),
+            ),
+            Rectangle::new(
+                Point::new(frame_right, frame.y),
+                Size::new(right - frame_right, frame.height),
+            ),
+        ];
+        for part in shaded {
+            renderer.fill_quad(
+                Quad {
+                    bounds: part,
+                    ..Quad::default()
+                },
+                SHADE,
+            );
+        }
+
+        renderer.fill_quad(
+            Quad {
+                bounds: frame,
+                border: Border {
+                    color: Color::WHITE,
+                    width: 2.0,
+                    radius: 0.0.into(),
+                },
+                ..Quad::default()
+            },
+            Color::TRANSPARENT,
+        );
+        for (x, y) in [
+            (frame.x, frame.y),
+            (frame_right, frame.y),
+            (frame.x, frame_bottom),
+            (frame_right, frame_bottom),
+        ] {
+            renderer.fill_quad(
+                Quad {
+                    bounds: Rectangle::new(
+                        Point::new(x - CORNER / 2.0, y - CORNER / 2.0),
+                        Size::new(CORNER, CORNER),
+                    ),
+                    ..Quad::default()
+                },
+                Color::WHITE,
+            );
+        }
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        _viewport: &Rectangle,
+        _renderer: &Renderer,
+    ) -> mouse::Interaction {
+        let bounds = layout.bounds();
+        match tree.state.downcast_ref::<Drag>() {
+            Drag::Move { .. } => mouse::Interaction::Grabbing,
+            Drag::Resize { .. } => mouse::Interaction::Crosshair,
+            Drag::Idle => match cursor.position_over(bounds) {
+                Some(point) if self.opposite_corner(bounds, point).is_some() => {
+                    mouse::Interaction::Crosshair
+                }
+                Some(point) if self.frame(bounds).contains(point) => mouse::Interaction::Grab,
+                Some(_) => mouse::Interaction::Crosshair,
+                None => mouse::Interaction::Idle,
+            },
+        }
+    }
+}
+
+impl<'a, Message, Theme, Renderer> From<CropEditor<'a, Message>>
+    for Element<'a, Message, Theme, Renderer>
+where
+    Message: 'a,
+    Renderer: image::Renderer<Handle = Handle> + 'a,
+{
+    fn from(editor: CropEditor<'a, Message>) -> Self {
+        Element::new(editor)
+    }
+}
@@ -0,0 +1,3715 @@
+use artcover::conform::Passthrough;
+use artcover::hash_index::DuplicateCheck;
+use artcover::i18n::{self, Language, t, tf};
+use artcover::quantize::{self, ColorMode};
+use artcover::resize::{ResizeMode, Square, UpscaleMode};
+use artcover::{
+    ArtCoverError, CancelToken, ProcessOptions, ProcessedImage, audit, booklet, community, dedupe,
+    embedded, export, health, history, ipod, library, lut, ocr, overrides, preset, probe, retry,
+    rockbox, schedule, settings,
+};
+use artcover_image_conversor as artcover;
+use chrono::{DateTime, Local};
+use iced::futures::channel::mpsc;
+use iced::keyboard;
+use iced::widget::{
+    Image, Space, button, checkbox, column, container, mouse_area, pick_list, progress_bar, radio,
+    row, scrollable, slider, text, text_input,
+};
+use iced::{Application, Command, Element, Event, Length, Subscription, event, executor};
+use std::collections::{HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+// How often the OS theme is checked while following it
+const SYSTEM_THEME_INTERVAL: Duration = Duration::from_secs(5);
+
+// How often the log pane picks up new lines
+const LOG_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+// How often the clock is compared with the next nightly run; a check after
+// the computer wakes up catches up on a missed one
+const NIGHTLY_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+use crate::logging;
+
+mod crop;
+mod notification;
+mod preview;
+mod session;
+pub(crate) mod shortcuts;
+mod watch;
+
+// Define App status. The fields the tests look at (tests/gui.rs) are
+// pub(crate).
+#[derive(Debug, Default)]
+pub struct ImageProcessor {
+    pub(crate) message: String,
+    // Last outcome, repeated in the window title for screen readers
+    announcement: Option<String>,
+    // Converted and failed files of the last finished batch, at the front
+    // of the title until the next one starts
+    batch_summary: Option<(usize, usize)>,
+    // Batches finishing behind other windows are notified
+    focused: bool,
+    pub(crate) processed_image: Option<PathBuf>,
+    // Kept with the result, loading it can decode the whole image
+    preview: Option<preview::Preview>,
+    text_hint: Option<String>,
+    warnings: Vec<String>,
+    upscaled: bool,
+    // Frames (or booklet pages) of the last source, which can be converted
+    // again with another one
+    frames: Option<(PathBuf, usize)>,
+    // Headers of the file converted last, read before converting it
+    pub(crate) metadata: Option<probe::Metadata>,
+    // Full-window view of the result, optionally with a pixel grid
+    large_preview: bool,
+    pixel_grid: Option<iced::widget::image::Handle>,
+    // Whole multiple of the image pixels in the full-window view; None
+    // fits the window
+    zoom: Option<u32>,
+    // The source instead of the result, loaded when first asked for
+    show_original: bool,
+    original_preview: Option<preview::Preview>,
+    // Square chosen by hand for one source, used whenever it is converted
+    pub(crate) crop: Option<(PathBuf, Square)>,
+    crop_editor: Option<CropEditing>,
+    pub(crate) is_processing: bool,
+    progress: f32,
+    cancel: Option<CancelToken>,
+    current: Option<PathBuf>,
+    queue: VecDeque<PathBuf>,
+    drops: session::DropGuard,
+    // Conversions left by the last run, until resumed or discarded
+    unfinished: Vec<PathBuf>,
+    // Session and settings as last written, so only changes are written
+    written_session: session::Saved,
+    written_settings: String,
+    // Settings as loaded, and as in use once overridden at launch
+    own_settings: settings::Bundle,
+    overridden_settings: settings::Bundle,
+    // Drops ignored as repeats, with the reason shown to the user
+    skipped: Vec<(PathBuf, String)>,
+    // Outcome of the startup check, until dismissed
+    health: Vec<health::Check>,
+    options: ProcessOptions,
+    presets: Vec<preset::Preset>,
+    profiles: Vec<export::ExportProfile>,
+    // Named sets of options, and the name typed for saving the current one
+    option_profiles: Vec<settings::OptionsProfile>,
+    profile_name: String,
+    // Profile picked, saved or imported last, named in exports
+    selected_profile: Option<String>,
+    // Profile held by its drag handle
+    dragging: Option<usize>,
+    // Rockbox sets write over the cover files already in the album
+    replace_art: bool,
+    // Multi-size results of the current batch, one group per profile
+    export_report: Vec<(String, Vec<String>)>,
+    // Every file of the current batch, in the order it arrived
+    pub(crate) results: Vec<FileResult>,
+    // Outputs of earlier conversions, newest last, for Ctrl+Z
+    pub(crate) undo: Vec<(PathBuf, Vec<PathBuf>)>,
+    // Source converted last, for converting again with Enter
+    last_source: Option<PathBuf>,
+    show_help: bool,
+    show_log: bool,
+    // Copy of the in-memory log, refreshed while the pane is shown
+    log: Vec<String>,
+    appearance: settings::Appearance,
+    language: Language,
+    // Last dark mode setting seen from the OS
+    system_dark: bool,
+    // Earlier versions of the last source, when outputs are versioned
+    history: Vec<history::Version>,
+    screen: Screen,
+    dedupe: DedupeState,
+    library: LibraryState,
+    community: CommunityState,
+    watch: WatchState,
+}
+
+// Top level sections of the window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Screen {
+    #[default]
+    Convert,
+    Watch,
+    Dedupe,
+    Library,
+    Community,
+}
+
+// Automatic conversion of images arriving in a folder
+#[derive(Debug, Default)]
+struct WatchState {
+    folder: String,
+    is_active: bool,
+    sources: HashSet<PathBuf>,
+    log: Vec<String>,
+}
+
+// Duplicate cleanup of an art folder
+#[derive(Debug, Default)]
+struct DedupeState {
+    folder: String,
+    clusters: Vec<dedupe::Cluster>,
+    status: String,
+    is_busy: bool,
+    // Action waiting for confirmation
+    pending: Option<dedupe::DuplicateAction>,
+    // Duplicates the last cleanup left in place, with why
+    failures: Vec<String>,
+}
+
+// Duplicates of a cluster left in place, errors already described
+#[derive(Debug, Clone)]
+pub struct DedupeOutcome {
+    cluster: usize,
+    failed: Vec<(PathBuf, String)>,
+}
+
+// Conversion of a whole music folder, one cover.jpg per album
+#[derive(Debug, Default)]
+struct LibraryState {
+    folder: String,
+    root: PathBuf,
+    albums: Vec<library::Album>,
+    // Outcome per album, in the same order once converted
+    results: Vec<Option<Result<Option<PathBuf>, String>>>,
+    status: String,
+    is_busy: bool,
+    cancel: Option<CancelToken>,
+    // Whether albums that already have a cover.jpg get a new one
+    replace_covers: bool,
+    // Mounted players, the one picked and the artwork folder on it (empty:
+    // the album folders)
+    devices: Vec<String>,
+    device: Option<String>,
+    artwork_folder: String,
+    // Dry run of the copy onto the player, shown until it is carried out
+    plan: Option<ipod::Plan>,
+    // Albums lacking a good cover, with the outcome of fixing each once
+    // tried, and whether the Cover Art Archive may be asked
+    gaps: Option<Vec<audit::Gap>>,
+    fixes: Vec<Option<Result<Option<audit::Found>, String>>>,
+    online: bool,
+    // Next nightly scan, audit and fix of the folder when scheduled, and
+    // whether one is going on
+    nightly: Option<DateTime<Local>>,
+    nightly_running: bool,
+}
+
+// Square being chosen for `source`, shown over `preview`
+#[derive(Debug, Clone)]
+struct CropEditing {
+    source: PathBuf,
+    size: (u32, u32),
+    preview: preview::Preview,
+    selection: Square,
+}
+
+// One file of a batch and where it stands
+#[derive(Debug, Clone)]
+pub(crate) struct FileResult {
+    pub(crate) source: PathBuf,
+    pub(crate) status: FileStatus,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum FileStatus {
+    Queued,
+    Processing,
+    // Every file written for the source
+    Done(Vec<PathBuf>),
+    Failed(String),
+    // Outputs deleted again with undo
+    Removed,
+}
+
+impl FileStatus {
+    fn icon(&self) -> &'static str {
+        match self {
+            FileStatus::Queued => "○",
+            FileStatus::Processing => "◐",
+            FileStatus::Done(_) => "✓",
+            FileStatus::Failed(_) => "✗",
+            FileStatus::Removed => "–",
+        }
+    }
+}
+
+// Browser for a shared preset index
+#[derive(Debug, Default)]
+struct CommunityState {
+    index: String,
+    entries: Vec<community::IndexEntry>,
+    status: String,
+    is_busy: bool,
+}
+
+// Define Messages (Events)
+#[derive(Debug, Clone)]
+pub enum Message {
+    FileDropped(PathBuf),
+    FileHashed((PathBuf, Option<u64>)),
+    ProcessAnyway(PathBuf),
+    RetryFile(PathBuf),
+    RetryFailed,
+    DismissSkipped,
+    CheckHealth,
+    HealthChecked(Vec<health::Check>),
+    DismissHealth,
+    FrameChanged(u32),
+    PreviewToggled,
+    PixelGridToggled,
+    OriginalToggled,
+    OpenCropEditor,
+    CropChanged(Square),
+    ApplyCrop,
+    AutomaticCrop,
+    CloseCropEditor,
+    PreviewZoomed(Option<u32>),
+    ReconvertFrame,
+    Progress(f32),
+    Inspected(probe::Metadata),
+    CancelProcessing,
+    ImageProcessed(Result<ProcessedImage, Arc<ArtCoverError>>),
+    ProfilesExported(Vec<(String, Result<ProcessedImage, Arc<ArtCoverError>>)>),
+    EventOccurred(Event),
+    Shortcut(shortcuts::Action),
+    HelpToggled,
+    LogToggled,
+    LogRefresh,
+    CopyDiagnostics,
+    Pasted(Option<String>),
+    OpenFiles,
+    FilesPicked(Vec<PathBuf>),
+    AutoTrimToggled(bool),
+    TrimToleranceChanged(u8),
+    ResizeModeSelected(ResizeMode),
+    UpscaleSelected(UpscaleMode),
+    PresetSelected(preset::Preset),
+    AsciiNamesToggled(bool),
+    PickLut,
+    LutSelected(Option<PathBuf>),
+    LutLoaded(Result<PathBuf, Arc<ArtCoverError>>),
+    ClearLut,
+    ProfileToggled(usize, bool),
+    ProfileDragStarted(usize),
+    ProfileDraggedOver(usize),
+    ReplaceArtToggled(bool),
+    ThemeImported(Result<preset::Preset, Arc<ArtCoverError>>),
+    ExportSettings,
+    SettingsExported(Option<Result<PathBuf, Arc<ArtCoverError>>>),
+    ImportSettings,
+    SettingsImported(Option<Result<settings::Bundle, Arc<ArtCoverError>>>),
+    OptionsProfileSelected(String),
+    OptionsProfileNameChanged(String),
+    SaveOptionsProfile,
+    ExportOptionsProfile,
+    OptionsProfileExported(Option<Result<PathBuf, Arc<ArtCoverError>>>),
+    ImportOptionsProfile,
+    OptionsProfileImported(Option<Result<settings::OptionsProfile, Arc<ArtCoverError>>>),
+    OcrToggled(bool),
+    OcrLanguagesChanged(String),
+    JpegQualityChanged(u8),
+    AutoQualityToggled(bool),
+    TargetSsimChanged(f32),
+    DeterministicToggled(bool),
+    BaselineJpegToggled(bool),
+    PassthroughSelected(Passthrough),
+    DuplicatesSelected(DuplicateCheck),
+    OptimizePngToggled(bool),
+    ProvenanceToggled(bool),
+    ColorModeSelected(ColorMode),
+    PaletteColorsChanged(u16),
+    RetriesChanged(u32),
+    AppearanceSelected(settings::Appearance),
+    LanguageSelected(Language),
+    SystemThemeCheck,
+    SystemThemeDetected(bool),
+    VersionedToggled(bool),
+    ShowVersion(usize),
+    RestoreVersion(usize),
+    ScreenSelected(Screen),
+    WatchFolderChanged(String),
+    WatchToggled,
+    Watch(watch::Event),
+    DedupeFolderChanged(String),
+    DedupeScan,
+    DedupeScanned(Result<Vec<dedupe::Cluster>, Arc<ArtCoverError>>),
+    DedupeKeepSelected(usize, usize),
+    DedupeResolve(dedupe::DuplicateAction),
+    DedupeConfirm,
+    DedupeCancel,
+    DedupeResolved(usize, Vec<DedupeOutcome>),
+    LibraryFolderChanged(String),
+    LibraryScan,
+    LibraryScanned(Result<Vec<library::Album>, Arc<ArtCoverError>>),
+    LibraryConvert,
+    ReplaceCoversToggled(bool),
+    LibraryAlbumConverted(usize, Result<Option<PathBuf>, Arc<ArtCoverError>>),
+    LibraryFinished,
+    LibraryCancel,
+    LibraryAudit,
+    LibraryAudited(Vec<audit::Gap>),
+    OnlineLookupToggled(bool),
+    FixCovers,
+    CoverFixed(usize, Result<Option<audit::Found>, Arc<ArtCoverError>>),
+    CoversFixed,
+    NightlyToggled(bool),
+    NightlyCheck,
+    FindDevices,
+    DevicesFound(Vec<PathBuf>),
+    DeviceSelected(String),
+    ArtworkFolderChanged(String),
+    PreviewDeviceCopy,
+    DeviceCopyPlanned(Result<ipod::Plan, Arc<ArtCoverError>>),
+    CopyToDevice,
+    CopiedToDevice(usize, Vec<String>),
+    CommunityIndexChanged(String),
+    CommunityFetch,
+    CommunityFetched(Result<Vec<community::IndexEntry>, Arc<ArtCoverError>>),
+    CommunityInstall(usize),
+    CommunityInstalled(Result<preset::Preset, Arc<ArtCoverError>>),
+    ResumeSession,
+    DiscardSession,
+    NotificationShown,
+}
+
+// General Logic
+impl Application for ImageProcessor {
+    type Executor = executor::Default;
+    type Message = Message;
+    type Theme = iced::Theme;
+    type Flags = ();
+
+    fn new(_flags: ()) -> (Self, Command<Message>) {
+        // ARTCOVER_* variables apply to the window as to the command line,
+        // but are never saved: `own_settings` keeps the user's values
+        let own_settings = settings::load_saved().unwrap_or_default();
+        let mut saved = own_settings.clone();
+        if let Err(message) = overrides::apply_all(&mut saved, std::env::vars(), &[]) {
+            tracing::warn!("{}", message);
+        }
+        i18n::set_language(saved.language);
+        let restored = session::load();
+        let mut processor = Self {
+            message: t("Drag an image here").to_string(),
+            announcement: None,
+            batch_summary: None,
+            focused: true,
+            processed_image: None,
+            preview: None,
+            text_hint: None,
+            warnings: Vec::new(),
+            upscaled: false,
+            frames: None,
+            metadata: None,
+            large_preview: false,
+            pixel_grid: None,
+            zoom: None,
+            show_original: false,
+            original_preview: None,
+            crop: None,
+            crop_editor: None,
+            is_processing: false,
+            progress: 0.0,
+            cancel: None,
+            current: None,
+            queue: VecDeque::new(),
+            drops: session::DropGuard::default(),
+            unfinished: restored.pending.clone(),
+            written_session: restored.clone(),
+            written_settings: String::new(),
+            own_settings,
+            overridden_settings: settings::Bundle::default(),
+            skipped: Vec::new(),
+            health: Vec::new(),
+            options: saved.options.clone(),
+            presets: preset::builtin(),
+            profiles: [
+                export::profiles(&preset::builtin()),
+                export::rockbox_profiles(),
+            ]
+            .concat(),
+            option_profiles: saved.option_profiles.clone(),
+            profile_name: String::new(),
+            selected_profile: None,
+            dragging: None,
+            replace_art: false,
+            export_report: Vec::new(),
+            results: restored
+                .finished
+                .into_iter()
+                .map(|(source, outputs)| FileResult {
+                    source,
+                    status: FileStatus::Done(outputs),
+                })
+                .collect(),
+            undo: restored.undo,
+            last_source: None,
+            show_help: false,
+            show_log: false,
+            log: Vec::new(),
+            appearance: saved.appearance,
+            language: saved.language,
+            system_dark: system_is_dark(),
+            history: Vec::new(),
+            screen: Screen::default(),
+            dedupe: DedupeState::default(),
+            library: LibraryState::default(),
+            community: CommunityState::default(),
+            watch: WatchState::default(),
+        };
+        for preset in &saved.presets {
+            processor.add_preset(preset.clone());
+        }
+        saved.apply_profiles(&mut processor.profiles);
+        if let Some(folder) = &saved.nightly_library {
+            processor.library.folder = folder.display().to_string();
+            processor.library.nightly =
+                Some(schedule::next_run(&Local::now(), schedule::NIGHTLY_HOUR));
+        }
+        processor.overridden_settings = processor.bundle();
+        processor.written_settings =
+            serde_json::to_string(&processor.own_bundle()).unwrap_or_default();
+
+        let check = processor.check_health();
+        (processor, check)
+    }
+
+    fn theme(&self) -> iced::Theme {
+        let dark = match self.appearance {
+            settings::Appearance::System => self.system_dark,
+            settings::Appearance::Light => false,
+            settings::Appearance::Dark => true,
+        };
+        if dark {
+            iced::Theme::Dark
+        } else {
+            iced::Theme::Light
+        }
+    }
+
+    fn title(&self) -> String {
+        // Title changes are read out by screen readers, unlike plain text
+        let title = match &self.announcement {
+            Some(announcement) => tf("{} - CoverArt Converter for iPod", &[&announcement]),
+            None => t("CoverArt Converter for iPod").to_string(),
+        };
+        match self.batch_summary {
+            Some((done, failed)) => format!("[{} ✓ {} ✗] {}", done, failed, title),
+            None => title,
+        }
+    }
+
+    // Listen OS events
+    fn subscription(&self) -> Subscription<Message> {
+        let mut subscriptions = vec![
+            event::listen().map(Message::EventOccurred),
+            keyboard::on_key_press(|key, modifiers| {
+                shortcuts::action(key, modifiers).map(Message::Shortcut)
+            }),
+        ];
+
+        // The OS setting can change while the app runs
+        if self.appearance == settings::Appearance::System {
+            subscriptions
+                .push(iced::time::every(SYSTEM_THEME_INTERVAL).map(|_| Message::SystemThemeCheck));
+        }
+
+        if self.show_log {
+            subscriptions
+                .push(iced::time::every(LOG_REFRESH_INTERVAL).map(|_| Message::LogRefresh));
+        }
+
+        if self.watch.is_active {
+            let folder = PathBuf::from(self.watch.folder.trim());
+            subscriptions.push(watch::folder(folder).map(Message::Watch));
+        }
+
+        if self.library.nightly.is_some() {
+            subscriptions
+                .push(iced::time::every(NIGHTLY_CHECK_INTERVAL).map(|_| Message::NightlyCheck));
+        }
+
+        Subscription::batch(subscriptions)
+    }
+
+    // Manage messages
+    fn update(&mut self, message: Message) -> Command<Message> {
+        // Arms returning early are persisted with the next message
+        let command = match message {
+            Message::EventOccurred(event) => match event {
+                Event::Window(_id, iced::window::Event::FileDropped(path)) => {
+                    self.handle_file_drop(path)
+                }
+                Event::Window(_id, iced::window::Event::Focused) => {
+                    self.focused = true;
+                    Command::none()
+                }
+                Event::Window(_id, iced::window::Event::Unfocused) => {
+                    self.focused = false;
+                    Command::none()
+                }
+                // Released anywhere, not only over a profile
+                Event::Mouse(iced::mouse::Event::ButtonReleased(iced::mouse::Button::Left)) => {
+                    self.dragging = None;
+                    Command::none()
+                }
+                _ => Command::none(),
+            },
+
+            Message::Shortcut(action) => self.run_shortcut(action),
+
+            Message::HelpToggled => {
+                self.show_help = !self.show_help;
+                Command::none()
+            }
+
+            Message::LogToggled => {
+                self.show_log = !self.show_log;
+                self.log = logging::recent();
+                Command::none()
+            }
+
+            Message::LogRefresh => {
+                self.log = logging::recent();
+                Command::none()
+            }
+
+            Message::CopyDiagnostics => {
+                self.message = t("Diagnostics copied to the clipboard").to_string();
+                iced::clipboard::write(self.diagnostics())
+            }
+
+            Message::Pasted(contents) => {
+                let paths = shortcuts::pasted_paths(contents.as_deref().unwrap_or_default());
+                if paths.is_empty() {
+                    self.message = t("Error: the clipboard holds no file paths").to_string();
+                    return Command::none();
+                }
+                let commands: Vec<_> = paths
+                    .into_iter()
+                    .map(|path| self.handle_file_drop(path))
+                    .collect();
+                Command::batch(commands)
+            }
+
+            // File picker, for setups where drag and drop does not work
+            Message::OpenFiles => Command::perform(pick_files(), Message::FilesPicked),
+
+            Message::FilesPicked(paths) => {
+                let commands: Vec<_> = paths
+                    .into_iter()
+                    .map(|path| self.handle_file_drop(path))
+                    .collect();
+                Command::batch(commands)
+            }
+
+            // Process message
+            Message::FileDropped(path) => {
+                // Overlapping selections are not converted twice
+                if self.drops.contains_path(&path) {
+                    self.skip(path, t("already dropped this session").to_string());
+                    return Command::none();
+                }
+                Command::perform(session::content_hash(path), Message::FileHashed)
+            }
+
+            Message::FileHashed((path, hash)) => {
+                if let Some(hash) = hash {
+                    if let Some(original) = self.drops.find_content(hash) {
+                        let reason = tf("same content as {}", &[&file_name(original)]);
+                        self.skip(path, reason);
+                        return Command::none();
+                    }
+                    self.drops.remember(&path, hash);
+                }
+                self.enqueue(path)
+            }
+
+            Message::ProcessAnyway(path) => {
+                self.skipped.retain(|(skipped, _)| *skipped != path);
+                self.enqueue(path)
+            }
+
+            Message::RetryFile(path) => self.queue_file(path),
+
+            Message::RetryFailed => {
+                let failed: Vec<PathBuf> = self
+                    .results
+                    .iter()
+                    .filter(|result| matches!(result.status, FileStatus::Failed(_)))
+                    .map(|result| result.source.clone())
+                    .collect();
+                Command::batch(failed.into_iter().map(|path| self.queue_file(path)))
+            }
+
+            Message::DismissSkipped => {
+                self.skipped.clear();
+                Command::none()
+            }
+
+            Message::CheckHealth => self.check_health(),
+
+            Message::HealthChecked(checks) => {
+                self.health = checks;
+                Command::none()
+            }
+
+            Message::DismissHealth => {
+                self.health.clear();
+                Command::none()
+            }
+
+            Message::FrameChanged(frame) => {
+                self.options.frame = frame as usize;
+                Command::none()
+            }
+
+            Message::PreviewToggled => {
+                self.large_preview = !self.large_preview && self.processed_image.is_some();
+                Command::none()
+            }
+
+            Message::PixelGridToggled => {
+                self.pixel_grid = match (&self.pixel_grid, &self.processed_image) {
+                    (None, Some(path)) => preview::with_pixel_grid(path),
+                    _ => None,
+                };
+                Command::none()
+            }
+
+            Message::OriginalToggled => {
+                self.show_original = !self.show_original && self.processed_image.is_some();
+                if self.show_original && self.original_preview.is_none() {
+                    self.original_preview = self.last_source.as_deref().map(preview::load);
+                }
+                Command::none()
+            }
+
+            Message::OpenCropEditor => {
+                if let Some(source) = self.last_source.clone()
+                    && let Some((width, height)) = self
+                        .metadata
+                        .as_ref()
+                        .and_then(|metadata| metadata.dimensions)
+                {
+                    let selection = match &self.crop {
+                        Some((cropped, square)) if *cropped == source => *square,
+                        _ => Square::centered(width, height),
+                    };
+                    self.crop_editor = Some(CropEditing {
+                        preview: preview::load(&source),
+                        source,
+                        size: (width, height),
+                        selection,
+                    });
+                }
+                Command::none()
+            }
+
+            Message::CropChanged(square) => {
+                if let Some(editing) = &mut self.crop_editor {
+                    editing.selection = square;
+                }
+                Command::none()
+            }
+
+            Message::ApplyCrop => match self.crop_editor.take() {
+                Some(editing) => {
+                    self.crop = Some((editing.source.clone(), editing.selection));
+                    self.queue_file(editing.source)
+                }
+                None => Command::none(),
+            },
+
+            Message::AutomaticCrop => match self.crop_editor.take() {
+                Some(editing) => {
+                    self.crop = None;
+                    self.queue_file(editing.source)
+                }
+                None => Command::none(),
+            },
+
+            Message::CloseCropEditor => {
+                self.crop_editor = None;
+                Command::none()
+            }
+
+            Message::PreviewZoomed(zoom) => {
+                self.zoom = zoom.map(|zoom| zoom.clamp(1, MAX_ZOOM));
+                Command::none()
+            }
+
+            Message::ReconvertFrame => match self.frames.clone() {
+                Some((path, _)) => self.enqueue(path),
+                None => Command::none(),
+            },
+
+            Message::Progress(progress) => {
+                self.progress = progress;
+                Command::none()
+            }
+
+            Message::Inspected(metadata) => {
+                self.metadata = Some(metadata);
+                Command::none()
+            }
+
+            Message::CancelProcessing => {
+                if let Some(cancel) = &self.cancel {
+                    cancel.cancel();
+                }
+                Command::none()
+            }
+
+            // Finish message
+            Message::ImageProcessed(Ok(processed)) => {
+                self.is_processing = false;
+                self.message = match processed.auto_quality {
+                    _ if processed.duplicate_of.is_some() => {
+                        t("Duplicate of an earlier cover, skipped").to_string()
+                    }
+                    _ if processed.passed_through => t("Already fits, kept as it is").to_string(),
+                    Some(quality) => tf("Image saved at JPEG quality {}", &[&quality]),
+                    None => t("Image processed and saved").to_string(),
+                };
+                if let Some(saved) = processed.png_saved {
+                    self.message = tf(
+                        "{}, PNG optimized by {} KB",
+                        &[&self.message, &(saved / 1024)],
+                    );
+                }
+                self.log_watched(format!(
+                    "{} -> {}",
+                    self.current_name(),
+                    processed.path.display()
+                ));
+                self.frames = processed
+                    .frame_count
+                    .zip(self.current.clone())
+                    .map(|(count, path)| (path, count));
+                if self.pixel_grid.is_some() {
+                    self.pixel_grid = preview::with_pixel_grid(&processed.path);
+                }
+                self.set_status(FileStatus::Done(vec![processed.path.clone()]));
+                // Undo must not delete the earlier cover a duplicate points to
+                if processed.duplicate_of.is_none() {
+                    self.remember_outputs(vec![processed.path.clone()]);
+                }
+                self.history = match (&self.current, processed.version) {
+                    (Some(source), Some(_)) => history::versions_of(source),
+                    _ => Vec::new(),
+                };
+                self.show_result(Some(processed.path));
+                self.text_hint = processed.text_hint;
+                self.warnings = processed.warnings;
+                self.upscaled = processed.upscaled;
+                self.announce();
+                self.process_next()
+            }
+
+            Message::ProfilesExported(results) => {
+                self.is_processing = false;
+                let saved = results.iter().filter(|(_, result)| result.is_ok()).count();
+                self.message = tf("{} of {} files saved", &[&saved, &results.len()]);
+
+                let name = self.current_name();
+                let mut outputs = Vec::new();
+                let mut written = Vec::new();
+                let mut first_error = None;
+                for (profile, result) in results {
+                    let line = match result {
+                        Ok(processed) => {
+                            self.log_watched(format!("{} -> {}", name, processed.path.display()));
+                            self.warnings.extend(processed.warnings);
+                            self.upscaled |= processed.upscaled;
+                            self.show_result(Some(processed.path.clone()));
+                            outputs.push(processed.path.clone());
+                            if processed.duplicate_of.is_none() {
+                                written.push(processed.path.clone());
+                            }
+                            match processed.png_saved {
+                                Some(saved) => tf(
+                                    "{} -> {} ({} KB smaller)",
+                                    &[&name, &file_name(&processed.path), &(saved / 1024)],
+                                ),
+                                None => format!("{} -> {}", name, file_name(&processed.path)),
+                            }
+                        }
+                        Err(error) => {
+                            let error_message = describe(&error);
+                            self.log_watched(tf("{} failed: {}", &[&name, &error_message]));
+                            let line = tf("{} failed: {}", &[&name, &error_message]);
+                            first_error.get_or_insert(error_message);
+                            line
+                        }
+                    };
+                    match self
+                        .export_report
+                        .iter_mut()
+                        .find(|(group, _)| *group == profile)
+                    {
+                        Some((_, lines)) => lines.push(line),
+                        None => self.export_report.push((profile, vec![line])),
+                    }
+                }
+
+                // Groups follow the profile order
+                let position = |name: &String| {
+                    self.profiles
+                        .iter()
+                        .position(|profile| profile.name() == *name)
+                };
+                let mut report = std::mem::take(&mut self.export_report);
+                report.sort_by_key(|(group, _)| position(group).unwrap_or(usize::MAX));
+                self.export_report = report;
+
+                self.remember_outputs(written);
+                self.set_status(match first_error {
+                    Some(error_message) if outputs.is_empty() => FileStatus::Failed(error_message),
+                    _ => FileStatus::Done(outputs),
+                });
+                if saved == 0
+                    && let Some(path) = &self.current
+                {
+                    self.drops.forget(path);
+                }
+                self.announce();
+                self.process_next()
+            }
+
+            // Failure message
+            Message::ImageProcessed(Err(error)) => {
+                let error_message = describe(&error);
+                self.is_processing = false;
+                self.message = match *error {
+                    ArtCoverError::Cancelled => error_message.clone(),
+                    _ => tf("Error: {}", &[&error_message]),
+                };
+                self.log_watched(tf("{} failed: {}", &[&self.current_name(), &error_message]));
+                self.set_status(FileStatus::Failed(error_message));
+                if let Some(path) = &self.current {
+                    self.drops.forget(path);
+                }
+                self.announce();
+                self.process_next()
+            }
+
+            // Options
+            Message::AutoTrimToggled(enabled) => {
+                self.options.auto_trim = enabled;
+                Command::none()
+            }
+
+            Message::TrimToleranceChanged(tolerance) => {
+                self.options.trim_tolerance = tolerance;
+                Command::none()
+            }
+
+            Message::ResizeModeSelected(mode) => {
+                self.options.resize_mode = mode;
+                Command::none()
+            }
+
+            Message::UpscaleSelected(mode) => {
+                self.options.upscale = mode;
+                Command::none()
+            }
+
+            Message::PresetSelected(preset) => {
+                self.options.preset = preset;
+                Command::none()
+            }
+
+            Message::ThemeImported(Ok(preset)) => {
+                self.message = tf("Imported {}", &[&preset.name]);
+                self.add_preset(preset.clone());
+                self.options.preset = preset;
+                Command::none()
+            }
+
+            Message::ThemeImported(Err(error)) => {
+                self.message = tf("Error: {}", &[&describe(&error)]);
+                Command::none()
+            }
+
+            // Stored on the preset, so each device keeps its own choice
+            Message::AsciiNamesToggled(enabled) => {
+                self.update_preset(|preset| preset.ascii_names = enabled);
+                Command::none()
+            }
+
+            // Color LUT of the current preset
+            Message::PickLut => Command::perform(pick_lut(), Message::LutSelected),
+
+            Message::LutSelected(Some(path)) => load_lut(path),
+
+            Message::LutSelected(None) => Command::none(),
+
+            Message::LutLoaded(Ok(path)) => {
+                self.message = tf(
+                    "{} now uses the color LUT {}",
+                    &[&self.options.preset.name, &file_name(&path)],
+                );
+                self.update_preset(|preset| preset.lut = Some(path.clone()));
+                Command::none()
+            }
+
+            Message::LutLoaded(Err(error)) => {
+                self.message = tf("Error: {}", &[&describe(&error)]);
+                Command::none()
+            }
+
+            Message::ClearLut => {
+                self.update_preset(|preset| preset.lut = None);
+                Command::none()
+            }
+
+            Message::ProfileToggled(index, enabled) => {
+                if let Some(profile) = self.profiles.get_mut(index) {
+                    profile.enabled = enabled;
+                }
+                Command::none()
+            }
+
+            Message::ProfileDragStarted(index) => {
+                self.dragging = Some(index);
+                Command::none()
+            }
+
+            Message::ProfileDraggedOver(index) => {
+                if let Some(from) = self.dragging {
+                    export::move_profile(&mut self.profiles, from, index);
+                    self.dragging = Some(index);
+                }
+                Command::none()
+            }
+
+            Message::ReplaceArtToggled(replace) => {
+                self.replace_art = replace;
+                Command::none()
+            }
+
+            // Settings bundle, to move the setup to another machine
+            Message::ExportSettings => {
+                Command::perform(save_bundle(self.own_bundle()), Message::SettingsExported)
+            }
+
+            Message::SettingsExported(result) => {
+                match result {
+                    Some(Ok(path)) => self.message = tf("Settings saved to {}", &[&path.display()]),
+                    Some(Err(error)) => self.message = tf("Error: {}", &[&describe(&error)]),
+                    None => {}
+                }
+                Command::none()
+            }
+
+            Message::ImportSettings => Command::perform(open_bundle(), Message::SettingsImported),
+
+            Message::SettingsImported(Some(Ok(bundle))) => {
+                for preset in &bundle.presets {
+                    self.add_preset(preset.clone());
+                }
+                bundle.apply_profiles(&mut self.profiles);
+                for profile in bundle.option_profiles {
+                    self.keep_options_profile(profile);
+                }
+                self.options = bundle.options;
+                self.message = t("Settings imported").to_string();
+                self.appearance = bundle.appearance;
+                self.language = bundle.language;
+                i18n::set_language(self.language);
+                Command::none()
+            }
+
+            Message::SettingsImported(Some(Err(error))) => {
+                self.message = tf("Error: {}", &[&describe(&error)]);
+                Command::none()
+            }
+
+            Message::SettingsImported(None) => Command::none(),
+
+            // Named option profiles, shareable one at a time as files
+            Message::OptionsProfileSelected(name) => {
+                if let Some(profile) = self
+                    .option_profiles
+                    .iter()
+                    .find(|profile| profile.name == name)
+                    .cloned()
+                {
+                    self.use_options_profile(profile);
+                }
+                Command::none()
+            }
+
+            Message::OptionsProfileNameChanged(name) => {
+                self.profile_name = name;
+                Command::none()
+            }
+
+            Message::SaveOptionsProfile => {
+                let name = self.profile_name.trim().to_string();
+                if name.is_empty() {
+                    self.message = t("Type a name for the profile").to_string();
+                } else {
+                    self.keep_options_profile(settings::OptionsProfile {
+                        name: name.clone(),
+                        options: self.options.clone(),
+                    });
+                    self.message = tf("Profile {} saved", &[&name]);
+                    self.selected_profile = Some(name);
+                    self.profile_name.clear();
+                }
+                Command::none()
+            }
+
+            Message::ExportOptionsProfile => {
+                let profile = settings::OptionsProfile {
+                    name: self
+                        .selected_profile
+                        .clone()
+                        .unwrap_or_else(|| self.options.preset.name.clone()),
+                    options: self.options.clone(),
+                };
+                Command::perform(save_profile(profile), Message::OptionsProfileExported)
+            }
+
+            Message::OptionsProfileExported(result) => {
+                match result {
+                    Some(Ok(path)) => self.message = tf("Profile saved to {}", &[&path.display()]),
+                    Some(Err(error)) => self.message = tf("Error: {}", &[&describe(&error)]),
+                    None => {}
+                }
+                Command::none()
+            }
+
+            Message::ImportOptionsProfile => {
+                Command::perform(open_profile(), Message::OptionsProfileImported)
+            }
+
+            Message::OptionsProfileImported(Some(Ok(profile))) => {
+                self.message = tf("Profile {} imported", &[&profile.name]);
+                self.keep_options_profile(profile.clone());
+                self.use_options_profile(profile);
+                Command::none()
+            }
+
+            Message::OptionsProfileImported(Some(Err(error))) => {
+                self.message = tf("Error: {}", &[&describe(&error)]);
+                Command::none()
+            }
+
+            Message::OptionsProfileImported(None) => Command::none(),
+
+            Message::OcrToggled(enabled) => {
+                self.options.ocr = enabled;
+                Command::none()
+            }
+
+            Message::OcrLanguagesChanged(languages) => {
+                self.options.ocr_languages = languages;
+                Command::none()
+            }
+
+            Message::JpegQualityChanged(quality) => {
+                self.options.jpeg_quality = quality;
+                Command::none()
+            }
+
+            Message::AutoQualityToggled(enabled) => {
+                self.options.auto_quality =
+                    enabled.then_some(artcover::quality::DEFAULT_TARGET_SSIM);
+                Command::none()
+            }
+
+            Message::TargetSsimChanged(target) => {
+                if self.options.auto_quality.is_some() {
+                    self.options.auto_quality = Some(target);
+                }
+                Command::none()
+            }
+
+            Message::DeterministicToggled(enabled) => {
+                self.options.deterministic = enabled;
+                Command::none()
+            }
+
+            Message::BaselineJpegToggled(enabled) => {
+                self.options.baseline_jpeg = enabled;
+                Command::none()
+            }
+
+            Message::PassthroughSelected(mode) => {
+                self.options.passthrough = mode;
+                Command::none()
+            }
+
+            Message::DuplicatesSelected(check) => {
+                self.options.duplicates = check;
+                Command::none()
+            }
+
+            Message::AppearanceSelected(appearance) => {
+                self.appearance = appearance;
+                Command::none()
+            }
+
+            // Text already shown, such as the status, keeps its language
+            Message::LanguageSelected(language) => {
+                self.language = language;
+                i18n::set_language(language);
+                Command::none()
+            }
+
+            Message::SystemThemeCheck => {
+                Command::perform(async { system_is_dark() }, Message::SystemThemeDetected)
+            }
+
+            Message::SystemThemeDetected(dark) => {
+                self.system_dark = dark;
+                Command::none()
+            }
+
+            Message::VersionedToggled(enabled) => {
+                self.options.versioned = enabled;
+                Command::none()
+            }
+
+            Message::ShowVersion(index) => {
+                if let Some(version) = self.history.get(index) {
+                    self.show_result(Some(version.output.clone()));
+                    self.pixel_grid = None;
+                }
+                Command::none()
+            }
+
+            // Back to the settings of an earlier version, to tweak from there
+            Message::RestoreVersion(index) => {
+                if let Some(version) = self.history.get(index) {
+                    self.options = ProcessOptions {
+                        versioned: true,
+                        ..version.options.clone()
+                    };
+                    self.message = tf("Settings of version {} restored", &[&version.number]);
+                }
+                Command::none()
+            }
+
+            Message::OptimizePngToggled(enabled) => {
+                self.options.optimize_png = enabled;
+                Command::none()
+            }
+
+            Message::ProvenanceToggled(enabled) => {
+                self.options.provenance = enabled;
+                Command::none()
+            }
+
+            Message::ColorModeSelected(mode) => {
+                self.options.color_mode = mode;
+                Command::none()
+            }
+
+            Message::PaletteColorsChanged(colors) => {
+                self.options.palette_colors = colors;
+                Command::none()
+            }
+
+            Message::RetriesChanged(retries) => {
+                self.options.retries = retries;
+                Command::none()
+            }
+
+            Message::ScreenSelected(screen) => {
+                self.screen = screen;
+                if screen == Screen::Library && self.library.devices.is_empty() {
+                    return self.update(Message::FindDevices);
+                }
+                Command::none()
+            }
+
+            // Watch folder
+            Message::WatchFolderChanged(folder) => {
+                if !self.watch.is_active {
+                    self.watch.folder = folder;
+                }
+                Command::none()
+            }
+
+            Message::WatchToggled => {
+                if self.watch.is_active {
+                    self.watch.is_active = false;
+                    self.watch.log.push(t("Stopped watching").to_string());
+                } else if Path::new(self.watch.folder.trim()).is_dir() {
+                    self.watch.is_active = true;
+                    self.watch
+                        .log
+                        .push(tf("Watching {}", &[&self.watch.folder.trim()]));
+                } else {
+                    self.watch
+                        .log
+                        .push(tf("Error: {}", &[&t("folder does not exist")]));
+                }
+                Command::none()
+            }
+
+            Message::Watch(watch::Event::NewImage(path)) => {
+                // Renames and creation can both be reported for one file
+                if !self.watch.sources.insert(path.clone()) {
+                    return Command::none();
+                }
+                Command::perform(async { path }, Message::FileDropped)
+            }
+
+            Message::Watch(watch::Event::Failed(error_message)) => {
+                self.watch.log.push(tf("Error: {}", &[&error_message]));
+                Command::none()
+            }
+
+            // Duplicate cleanup
+            Message::DedupeFolderChanged(folder) => {
+                self.dedupe.folder = folder;
+                Command::none()
+            }
+
+            Message::DedupeScan => {
+                if self.dedupe.is_busy || self.dedupe.folder.trim().is_empty() {
+                    return Command::none();
+                }
+                self.dedupe.is_busy = true;
+                self.dedupe.clusters.clear();
+                self.dedupe.status = t("Scanning...").to_string();
+
+                let folder = PathBuf::from(self.dedupe.folder.trim());
+                Command::perform(
+                    async move { dedupe::scan(&folder, dedupe::DEFAULT_THRESHOLD).map_err(Arc::new) },
+                    Message::DedupeScanned,
+                )
+            }
+
+            Message::DedupeScanned(Ok(clusters)) => {
+                self.dedupe.is_busy = false;
+                self.dedupe.status = if clusters.is_empty() {
+                    t("No duplicates found").to_string()
+                } else {
+                    tf("{} groups of similar covers", &[&clusters.len()])
+                };
+                self.dedupe.clusters = clusters;
+                Command::none()
+            }
+
+            Message::DedupeScanned(Err(error)) => {
+                self.dedupe.is_busy = false;
+                self.dedupe.status = tf("Error: {}", &[&describe(&error)]);
+                Command::none()
+            }
+
+            Message::DedupeKeepSelected(cluster, file) => {
+                if let Some(cluster) = self.dedupe.clusters.get_mut(cluster) {
+                    cluster.keep = file;
+                }
+                Command::none()
+            }
+
+            // Files are only removed or linked once confirmed
+            Message::DedupeResolve(action) => {
+                if !self.dedupe.is_busy {
+                    self.dedupe.pending = Some(action);
+                }
+                Command::none()
+            }
+
+            Message::DedupeCancel => {
+                self.dedupe.pending = None;
+                Command::none()
+            }
+
+            Message::DedupeConfirm => {
+                let Some(action) = self.dedupe.pending.take() else {
+                    return Command::none();
+                };
+                if self.dedupe.is_busy {
+                    return Command::none();
+                }
+                self.dedupe.is_busy = true;
+                self.dedupe.failures.clear();
+
+                let clusters = self.dedupe.clusters.clone();
+                Command::perform(
+                    async move {
+                        let mut handled = 0;
+                        let mut outcomes = Vec::new();
+                        for (index, cluster) in clusters.iter().enumerate() {
+                            let resolution = dedupe::resolve(cluster, action);
+                            handled += resolution.handled;
+                            let failed: Vec<_> = resolution
+                                .failed
+                                .into_iter()
+                                .map(|(path, error)| (path, describe(&error)))
+                                .collect();
+                            if !failed.is_empty() {
+                                outcomes.push(DedupeOutcome {
+                                    cluster: index,
+                                    failed,
+                                });
+                            }
+                        }
+                        (handled, outcomes)
+                    },
+                    |(handled, outcomes)| Message::DedupeResolved(handled, outcomes),
+                )
+            }
+
+            // Groups with duplicates left in place stay listed to try again
+            Message::DedupeResolved(handled, outcomes) => {
+                self.dedupe.is_busy = false;
+                let clusters = std::mem::take(&mut self.dedupe.clusters);
+                let failed: usize = outcomes.iter().map(|outcome| outcome.failed.len()).sum();
+                for outcome in outcomes {
+                    let Some(cluster) = clusters.get(outcome.cluster) else {
+                        continue;
+                    };
+                    let kept = cluster.files[cluster.keep].clone();
+                    let mut files = vec![kept];
+                    for (path, error_message) in outcome.failed {
+                        self.dedupe
+                            .failures
+                            .push(tf("{} failed: {}", &[&path.display(), &error_message]));
+                        if let Some(file) = cluster.files.iter().find(|file| file.path == path) {
+                            files.push(file.clone());
+                        }
+                    }
+                    self.dedupe
+                        .clusters
+                        .push(dedupe::Cluster { files, keep: 0 });
+                }
+                self.dedupe.status = if failed == 0 {
+                    tf("{} duplicates cleaned up", &[&handled])
+                } else {
+                    tf(
+                        "{} duplicates cleaned up, {} left in place",
+                        &[&handled, &failed],
+                    )
+                };
+                Command::none()
+            }
+
+            // Library conversion
+            Message::LibraryFolderChanged(folder) => {
+                if !self.library.is_busy {
+                    self.library.folder = folder;
+                }
+                Command::none()
+            }
+
+            Message::LibraryScan => {
+                if self.library.is_busy || self.library.folder.trim().is_empty() {
+                    return Command::none();
+                }
+                self.library.is_busy = true;
+                self.library.albums.clear();
+                self.library.results.clear();
+                self.library.status = t("Scanning...").to_string();
+
+                let folder = PathBuf::from(self.library.folder.trim());
+                self.library.root = folder.clone();
+                Command::perform(
+                    async move { library::scan(&folder).map_err(Arc::new) },
+                    Message::LibraryScanned,
+                )
+            }
+
+            Message::LibraryScanned(Ok(albums)) => {
+                self.library.is_busy = false;
+                let with_art = albums.iter().filter(|album| album.source.is_some()).count();
+                self.library.status = if albums.is_empty() {
+                    t("No album folders found").to_string()
+                } else {
+                    tf("{} albums, {} with artwork", &[&albums.len(), &with_art])
+                };
+                self.library.results = vec![None; albums.len()];
+                self.library.albums = albums;
+                self.library.plan = None;
+                self.library.gaps = None;
+                if self.library.nightly_running {
+                    if !self.library.albums.is_empty() {
+                        return self.update(Message::LibraryAudit);
+                    }
+                    self.library.nightly_running = false;
+                }
+                Command::none()
+            }
+
+            Message::LibraryScanned(Err(error)) => {
+                self.library.is_busy = false;
+                self.library.nightly_running = false;
+                self.library.status = tf("Error: {}", &[&describe(&error)]);
+                Command::none()
+            }
+
+            Message::LibraryConvert => {
+                if self.library.is_busy || self.library.albums.is_empty() {
+                    return Command::none();
+                }
+                self.library.is_busy = true;
+                self.library.results = vec![None; self.library.albums.len()];
+                self.library.status = t("Converting...").to_string();
+
+                let cancel = CancelToken::default();
+                self.library.cancel = Some(cancel.clone());
+
+                // Albums are converted one after another on a worker thread
+                let albums = self.library.albums.clone();
+                let options = self.options.clone();
+                let replace = self.library.replace_covers;
+                let (sender, receiver) = mpsc::unbounded();
+                std::thread::spawn(move || {
+                    for (index, album) in albums.iter().enumerate() {
+                        if cancel.is_cancelled() {
+                            break;
+                        }
+                        let result = library::convert_album(album, &options, replace, &cancel)
+                            .map(|processed| processed.map(|processed| processed.path))
+                            .map_err(Arc::new);
+                        let _ =
+                            sender.unbounded_send(Message::LibraryAlbumConverted(index, result));
+                    }
+                    let _ = sender.unbounded_send(Message::LibraryFinished);
+                });
+
+                Command::run(receiver, |message| message)
+            }
+
+            Message::ReplaceCoversToggled(replace) => {
+                if !self.library.is_busy {
+                    self.library.replace_covers = replace;
+                }
+                Command::none()
+            }
+
+            Message::LibraryAlbumConverted(index, result) => {
+                let result = result.map_err(|error| describe(&error));
+                if let Some(slot) = self.library.results.get_mut(index) {
+                    *slot = Some(result);
+                }
+                Command::none()
+            }
+
+            Message::LibraryFinished => {
+                let converted = self
+                    .library
+                    .results
+                    .iter()
+                    .filter(|result| matches!(result, Some(Ok(Some(_)))))
+                    .count();
+                let failed = self
+                    .library
+                    .results
+                    .iter()
+                    .filter(|result| matches!(result, Some(Err(_))))
+                    .count();
+
+                let cancelled = self
+                    .library
+                    .cancel
+                    .take()
+                    .is_some_and(|cancel| cancel.is_cancelled());
+                self.library.is_busy = false;
+                self.library.plan = None;
+                self.library.status = if cancelled {
+                    tf("Cancelled after {} covers", &[&converted])
+                } else {
+                    tf("{} covers written, {} failed", &[&converted, &failed])
+                };
+                self.announcement = Some(self.library.status.clone());
+                Command::none()
+            }
+
+            Message::LibraryCancel => {
+                if let Some(cancel) = &self.library.cancel {
+                    cancel.cancel();
+                }
+                Command::none()
+            }
+
+            // Albums without a good cover, filled in bulk once listed
+            Message::LibraryAudit => {
+                if self.library.is_busy || self.library.albums.is_empty() {
+                    return Command::none();
+                }
+                self.library.is_busy = true;
+                self.library.status = t("Checking covers...").to_string();
+                let albums = self.library.albums.clone();
+                let preset = self.options.preset.clone();
+                Command::perform(
+                    async move { audit::audit(&albums, &preset) },
+                    Message::LibraryAudited,
+                )
+            }
+
+            Message::LibraryAudited(gaps) => {
+                self.library.is_busy = false;
+                self.library.status = if gaps.is_empty() {
+                    t("Every album has a large enough cover").to_string()
+                } else {
+                    tf("{} albums need a cover", &[&gaps.len()])
+                };
+                self.library.fixes = vec![None; gaps.len()];
+                self.library.gaps = Some(gaps);
+                self.library.plan = None;
+                if self.library.nightly_running {
+                    if self
+                        .library
+                        .gaps
+                        .as_ref()
+                        .is_some_and(|gaps| !gaps.is_empty())
+                    {
+                        return self.update(Message::FixCovers);
+                    }
+                    self.library.nightly_running = false;
+                }
+                Command::none()
+            }
+
+            Message::OnlineLookupToggled(online) => {
+                self.library.online = online;
+                Command::none()
+            }
+
+            Message::FixCovers => {
+                let Some(gaps) = self.library.gaps.clone() else {
+                    return Command::none();
+                };
+                if self.library.is_busy || gaps.is_empty() {
+                    return Command::none();
+                }
+                self.library.is_busy = true;
+                self.library.fixes = vec![None; gaps.len()];
+                self.library.status = t("Looking for covers...").to_string();
+
+                let cancel = CancelToken::default();
+                self.library.cancel = Some(cancel.clone());
+
+                let options = self.options.clone();
+                let online = self.library.online;
+                let (sender, receiver) = mpsc::unbounded();
+                std::thread::spawn(move || {
+                    for (index, gap) in gaps.iter().enumerate() {
+                        if cancel.is_cancelled() {
+                            break;
+                        }
+                        let result = audit::fix(gap, &options, online, &cancel).map_err(Arc::new);
+                        let _ = sender.unbounded_send(Message::CoverFixed(index, result));
+                    }
+                    let _ = sender.unbounded_send(Message::CoversFixed);
+                });
+
+                Command::run(receiver, |message| message)
+            }
+
+            Message::CoverFixed(index, result) => {
+                let result = result.map_err(|error| describe(&error));
+                if let Some(slot) = self.library.fixes.get_mut(index) {
+                    *slot = Some(result);
+                }
+                Command::none()
+            }
+
+            Message::CoversFixed => {
+                let count = |fixed: fn(&Result<Option<audit::Found>, String>) -> bool| {
+                    self.library
+                        .fixes
+                        .iter()
+                        .flatten()
+                        .filter(|result| fixed(result))
+                        .count()
+                };
+                let found = count(|result| matches!(result, Ok(Some(_))));
+                let not_found = count(|result| matches!(result, Ok(None)));
+                let failed = count(|result| result.is_err());
+
+                let cancelled = self
+                    .library
+                    .cancel
+                    .take()
+                    .is_some_and(|cancel| cancel.is_cancelled());
+                self.library.is_busy = false;
+                self.library.nightly_running = false;
+                self.library.status = if cancelled {
+                    tf("Cancelled after {} covers", &[&found])
+                } else {
+                    tf(
+                        "{} covers found, {} albums without one, {} failed",
+                        &[&found, &not_found, &failed],
+                    )
+                };
+                self.announcement = Some(self.library.status.clone());
+                Command::none()
+            }
+
+            // The same scan, audit and fix as by hand, run every night for
+            // libraries that keep growing
+            Message::NightlyToggled(enabled) => {
+                self.library.nightly =
+                    enabled.then(|| schedule::next_run(&Local::now(), schedule::NIGHTLY_HOUR));
+                Command::none()
+            }
+
+            Message::NightlyCheck => {
+                let now = Local::now();
+                if self.library.nightly.is_none_or(|next| now < next) {
+                    return Command::none();
+                }
+                self.library.nightly = Some(schedule::next_run(&now, schedule::NIGHTLY_HOUR));
+                // Work started by hand is not interrupted; the next night runs
+                if self.library.is_busy || self.library.folder.trim().is_empty() {
+                    return Command::none();
+                }
+                tracing::info!(folder = %self.library.folder.trim(), "nightly library check");
+                self.library.nightly_running = true;
+                self.update(Message::LibraryScan)
+            }
+
+            // Copy of the converted covers onto a mounted player, previewed
+            // first
+            Message::FindDevices => {
+                Command::perform(async { health::find_devices() }, Message::DevicesFound)
+            }
+
+            Message::DevicesFound(devices) => {
+                self.library.devices = devices
+                    .iter()
+                    .map(|device| device.display().to_string())
+                    .collect();
+                if self
+                    .library
+                    .device
+                    .as_ref()
+                    .is_none_or(|device| !self.library.devices.contains(device))
+                {
+                    self.library.device = self.library.devices.first().cloned();
+                    self.library.plan = None;
+                }
+                Command::none()
+            }
+
+            Message::DeviceSelected(device) => {
+                self.library.device = Some(device);
+                self.library.plan = None;
+                Command::none()
+            }
+
+            Message::ArtworkFolderChanged(folder) => {
+                self.library.artwork_folder = folder;
+                self.library.plan = None;
+                Command::none()
+            }
+
+            Message::PreviewDeviceCopy => {
+                let Some(device) = self.library.device.clone() else {
+                    return Command::none();
+                };
+                if self.library.is_busy {
+                    return Command::none();
+                }
+                self.library.is_busy = true;
+                self.library.status = t("Comparing with the player...").to_string();
+
+                let device = PathBuf::from(device);
+                let destination = match self.library.artwork_folder.trim() {
+                    "" => ipod::Destination::Albums,
+                    folder => ipod::Destination::Folder(device.join(folder)),
+                };
+                let root = self.library.root.clone();
+                let albums = self.library.albums.clone();
+                Command::perform(
+                    async move { ipod::plan(&root, &albums, &device, &destination).map_err(Arc::new) },
+                    Message::DeviceCopyPlanned,
+                )
+            }
+
+            Message::DeviceCopyPlanned(Ok(plan)) => {
+                self.library.is_busy = false;
+                self.library.status = tf(
+                    "{} new, {} replaced, {} unchanged, {} without a matching album",
+                    &[
+                        &plan.count(ipod::Action::New),
+                        &plan.count(ipod::Action::Replace),
+                        &plan.count(ipod::Action::Unchanged),
+                        &plan.unmatched.len(),
+                    ],
+                );
+                self.library.plan = Some(plan);
+                Command::none()
+            }
+
+            Message::DeviceCopyPlanned(Err(error)) => {
+                self.library.is_busy = false;
+                self.library.status = tf("Error: {}", &[&describe(&error)]);
+                Command::none()
+            }
+
+            Message::CopyToDevice => {
+                let Some(plan) = self.library.plan.clone() else {
+                    return Command::none();
+                };
+                if self.library.is_busy {
+                    return Command::none();
+                }
+                self.library.is_busy = true;
+                self.library.status = t("Copying...").to_string();
+
+                Command::perform(
+                    async move {
+                        let mut copied = 0;
+                        let mut errors = Vec::new();
+                        for copy in &plan.copies {
+                            match ipod::write(copy) {
+                                Ok(()) if copy.action != ipod::Action::Unchanged => copied += 1,
+                                Ok(()) => {}
+                                Err(error) => errors.push(describe(&error)),
+                            }
+                        }
+                        (copied, errors)
+                    },
+                    |(copied, errors)| Message::CopiedToDevice(copied, errors),
+                )
+            }
+
+            Message::CopiedToDevice(copied, errors) => {
+                self.library.is_busy = false;
+                self.library.plan = None;
+                self.library.status = match errors.first() {
+                    None => tf("{} covers copied to the player", &[&copied]),
+                    Some(error) => tf(
+                        "{} covers copied, {} failed: {}",
+                        &[&copied, &errors.len(), error],
+                    ),
+                };
+                self.announcement = Some(self.library.status.clone());
+                Command::none()
+            }
+
+            // Community presets
+            Message::CommunityIndexChanged(index) => {
+                self.community.index = index;
+                Command::none()
+            }
+
+            Message::CommunityFetch => {
+                if self.community.is_busy || self.community.index.trim().is_empty() {
+                    return Command::none();
+                }
+                self.community.is_busy = true;
+                self.community.entries.clear();
+                self.community.status = t("Loading index...").to_string();
+
+                let index = self.community.index.trim().to_string();
+                Command::perform(
+                    async move {
+                        community::fetch_index(&index)
+                            .map(|index| index.presets)
+                            .map_err(Arc::new)
+                    },
+                    Message::CommunityFetched,
+                )
+            }
+
+            Message::CommunityFetched(Ok(entries)) => {
+                self.community.is_busy = false;
+                self.community.status = tf("{} presets available", &[&entries.len()]);
+                self.community.entries = entries;
+                Command::none()
+            }
+
+            Message::CommunityFetched(Err(error)) => {
+                self.community.is_busy = false;
+                self.community.status = tf("Error: {}", &[&describe(&error)]);
+                Command::none()
+            }
+
+            Message::CommunityInstall(index) => {
+                let Some(entry) = self.community.entries.get(index).cloned() else {
+                    return Command::none();
+                };
+                if self.community.is_busy {
+                    return Command::none();
+                }
+                self.community.is_busy = true;
+                self.community.status = tf("Downloading {}...", &[&entry.name]);
+
+                let location = self.community.index.trim().to_string();
+                Command::perform(
+                    async move { community::fetch_preset(&location, &entry).map_err(Arc::new) },
+                    Message::CommunityInstalled,
+                )
+            }
+
+            Message::CommunityInstalled(Ok(preset)) => {
+                self.community.is_busy = false;
+                self.community.status = tf("Installed {}", &[&preset.name]);
+                self.add_preset(preset.clone());
+                self.options.preset = preset;
+                Command::none()
+            }
+
+            Message::CommunityInstalled(Err(error)) => {
+                self.community.is_busy = false;
+                self.community.status = tf("Error: {}", &[&describe(&error)]);
+                Command::none()
+            }
+
+            Message::ResumeSession => {
+                let commands: Vec<Command<Message>> = std::mem::take(&mut self.unfinished)
+                    .into_iter()
+                    .map(|path| self.queue_file(path))
+                    .collect();
+                Command::batch(commands)
+            }
+
+            Message::DiscardSession => {
+                self.unfinished.clear();
+                Command::none()
+            }
+
+            Message::NotificationShown => Command::none(),
+        };
+        self.persist();
+        command
+    }
+
+    // Draw UI
+    fn view(&self) -> Element<'_, Message> {
+        if self.show_help {
+            return self.view_help();
+        }
+
+        if self.large_preview
+            && let Some(preview) = &self.preview
+        {
+            return self.view_large_preview(preview);
+        }
+
+        if let Some(editing) = &self.crop_editor {
+            return view_crop_editor(editing);
+        }
+
+        let tabs = row![
+            tab_button(t("Convert"), Screen::Convert, self.screen),
+            tab_button(t("Watch"), Screen::Watch, self.screen),
+            tab_button(t("Deduplicate"), Screen::Dedupe, self.screen),
+            tab_button(t("Library"), Screen::Library, self.screen),
+            tab_button(t("Presets"), Screen::Community, self.screen),
+        ]
+        .spacing(10);
+
+        let content = match self.screen {
+            Screen::Convert => self.view_convert(),
+            Screen::Watch => self.view_watch(),
+            Screen::Dedupe => self.view_dedupe(),
+            Screen::Library => self.view_library(),
+            Screen::Community => self.view_community(),
+        };
+
+        let mut page = column![tabs];
+        if !self.health.is_empty() {
+            page = page.push(self.view_health());
+        }
+        if !self.unfinished.is_empty() {
+            page = page.push(
+                row![
+                    text(tf(
+                        "{} unfinished conversion(s) from the last session",
+                        &[&self.unfinished.len()]
+                    ))
+                    .size(14),
+                    button(text(t("Resume")).size(14)).on_press(Message::ResumeSession),
+                    button(text(t("Discard")).size(14)).on_press(Message::DiscardSession),
+                ]
+                .spacing(10)
+                .align_items(iced::Alignment::Center),
+            );
+        }
+
+        page = page.push(content);
+        if self.show_log {
+            page = page.push(self.view_log());
+        }
+        let log_label = if self.show_log {
+            t("Hide log")
+        } else {
+            t("Show log")
+        };
+        page = page.push(button(text(log_label).size(14)).on_press(Message::LogToggled));
+
+        container(page.spacing(20).align_items(iced::Alignment::Center))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .padding(20)
+            .center_x()
+            .center_y()
+            .into()
+    }
+}
+
+// Auxiliar actions
+
+impl ImageProcessor {
+    fn view_convert(&self) -> Element<'_, Message> {
+        let profile_names: Vec<String> = self
+            .option_profiles
+            .iter()
+            .map(|profile| profile.name.clone())
+            .collect();
+        let options = column![
+            row![
+                text(t("Profile")),
+                pick_list(
+                    profile_names,
+                    self.selected_profile.clone(),
+                    Message::OptionsProfileSelected,
+                )
+                .placeholder(t("None")),
+                button(t("Export profile...")).on_press(Message::ExportOptionsProfile),
+                button(t("Import profile...")).on_press(Message::ImportOptionsProfile),
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center),
+            row![
+                text_input(t("Profile name"), &self.profile_name)
+                    .on_input(Message::OptionsProfileNameChanged)
+                    .on_submit(Message::SaveOptionsProfile)
+                    .width(Length::Fixed(200.0)),
+                button(t("Save profile")).on_press(Message::SaveOptionsProfile),
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center),
+            row![
+                text(t("Preset")),
+                pick_list(
+                    &self.presets[..],
+                    Some(&self.options.preset),
+                    Message::PresetSelected,
+                ),
+                checkbox(t("ASCII file names"), self.options.preset.ascii_names)
+                    .on_toggle(Message::AsciiNamesToggled),
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center),
+            row![
+                text(t("Color LUT")),
+                text(
+                    self.options
+                        .preset
+                        .lut
+                        .as_deref()
+                        .map_or(t("None").to_string(), file_name)
+                ),
+                button(t("Choose...")).on_press(Message::PickLut),
+                button(t("Clear")).on_press_maybe(
+                    self.options
+                        .preset
+                        .lut
+                        .is_some()
+                        .then_some(Message::ClearLut)
+                ),
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center),
+            row![
+                text(t("Resize mode")),
+                pick_list(
+                    &ResizeMode::ALL[..],
+                    Some(self.options.resize_mode),
+                    Message::ResizeModeSelected,
+                ),
+                pick_list(
+                    &UpscaleMode::ALL[..],
+                    Some(self.options.upscale),
+                    Message::UpscaleSelected,
+                ),
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center),
+            self.view_color_mode(),
+            checkbox(t("Auto-trim borders"), self.options.auto_trim)
+                .on_toggle(Message::AutoTrimToggled),
+            row![
+                text(tf("Tolerance: {}", &[&self.options.trim_tolerance])),
+                slider(
+                    0..=96,
+                    self.options.trim_tolerance,
+                    Message::TrimToleranceChanged
+                ),
+            ]
+            .spacing(10),
+            row![
+                checkbox(t("Read cover text (OCR)"), self.options.ocr)
+                    .on_toggle(Message::OcrToggled),
+                text_input(ocr::DEFAULT_LANGUAGES, &self.options.ocr_languages)
+                    .on_input(Message::OcrLanguagesChanged)
+                    .width(Length::Fixed(120.0)),
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center),
+            row![
+                text(tf("Retries: {}", &[&self.options.retries])),
+                slider(
+                    0..=retry::MAX_RETRIES,
+                    self.options.retries,
+                    Message::RetriesChanged
+                ),
+            ]
+            .spacing(10),
+            self.view_quality(),
+            self.view_profiles(),
+            row![
+                pick_list(
+                    &settings::Appearance::ALL[..],
+                    Some(self.appearance),
+                    Message::AppearanceSelected,
+                ),
+                pick_list(
+                    &Language::ALL[..],
+                    Some(self.language),
+                    Message::LanguageSelected,
+                ),
+            ]
+            .spacing(10),
+            row![
+                checkbox(t("Reproducible output"), self.options.deterministic)
+                    .on_toggle(Message::DeterministicToggled),
+                checkbox(t("Optimize PNG"), self.options.optimize_png)
+                    .on_toggle(Message::OptimizePngToggled),
+                checkbox(t("Keep versions"), self.options.versioned)
+                    .on_toggle(Message::VersionedToggled),
+            ]
+            .spacing(10),
+            checkbox(
+                t("Note the source in the metadata"),
+                self.options.provenance
+            )
+            .on_toggle(Message::ProvenanceToggled),
+            row![
+                pick_list(
+                    &Passthrough::ALL[..],
+                    Some(self.options.passthrough),
+                    Message::PassthroughSelected,
+                ),
+                checkbox(t("Rewrite progressive JPEGs"), self.options.baseline_jpeg)
+                    .on_toggle(Message::BaselineJpegToggled),
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center),
+            pick_list(
+                &DuplicateCheck::ALL[..],
+                Some(self.options.duplicates),
+                Message::DuplicatesSelected,
+            ),
+        ]
+        .spacing(10);
+
+        let mut content = column![
+            // Labeled status region
+            column![text(t("Status")).size(14), text(&self.message).size(24)]
+                .align_items(iced::Alignment::Center),
+            row![
+                button(t("Open...")).on_press(Message::OpenFiles),
+                button(t("Shortcuts (F1)")).on_press(Message::HelpToggled),
+                button(t("Export settings...")).on_press(Message::ExportSettings),
+                button(t("Import settings...")).on_press(Message::ImportSettings),
+            ]
+            .spacing(10),
+            options
+        ]
+        .spacing(20)
+        .align_items(iced::Alignment::Center);
+
+        if self.is_processing {
+            content = content.push(
+                row![
+                    progress_bar(0.0..=1.0, self.progress).height(Length::Fixed(12.0)),
+                    button(t("Cancel")).on_press(Message::CancelProcessing),
+                ]
+                .spacing(10)
+                .align_items(iced::Alignment::Center),
+            );
+        }
+
+        if let Some(metadata) = &self.metadata {
+            content = content.push(view_metadata(metadata));
+        }
+
+        if !self.skipped.is_empty() {
+            content = content.push(self.view_skipped());
+        }
+
+        if !self.results.is_empty() {
+            content = content.push(self.view_results());
+        }
+
+        for (profile, lines) in &self.export_report {
+            content = content.push(text(profile));
+            for line in lines {
+                content = content.push(text(line).size(14));
+            }
+        }
+
+        if let Some((source, count)) = &self.frames {
+            let (label, unit) = if booklet::is_pdf(source) {
+                (t("Page"), t("page"))
+            } else {
+                (t("Frame"), t("frame"))
+            };
+            let last = count.saturating_sub(1) as u32;
+            let frame = (self.options.frame as u32).min(last);
+            content = content.push(
+                row![
+                    text(tf("{} {} of {}", &[&label, &(frame + 1), &count])),
+                    slider(0..=last, frame, Message::FrameChanged),
+                    button(text(tf("Use {}", &[&unit]))).on_press(Message::ReconvertFrame),
+                ]
+                .spacing(10)
+                .align_items(iced::Alignment::Center),
+            );
+        }
+
+        if !self.history.is_empty() {
+            content = content.push(self.view_history());
+        }
+
+        if let Some(hint) = &self.text_hint {
+            content = content.push(text(tf("Cover text: {}", &[&hint])));
+        }
+
+        for warning in &self.warnings {
+            content = content.push(text(tf("Warning: {}", &[&warning])).size(14));
+        }
+
+        if self.processed_image.is_some() && self.upscaled {
+            content = content.push(
+                container(text(t("UPSCALED")).size(14))
+                    .padding([2, 8])
+                    .style(iced::theme::Container::Box),
+            );
+        }
+
+        match &self.preview {
+            // Clicking opens the large preview
+            Some(preview::Preview::Image { handle, .. }) => {
+                content = content.push(
+                    mouse_area(
+                        Image::new(handle.clone())
+                            .width(Length::Fixed(300.0))
+                            .height(Length::Fixed(300.0))
+                            .content_fit(iced::ContentFit::Contain),
+                    )
+                    .on_press(Message::PreviewToggled),
+                );
+            }
+            Some(preview::Preview::Unavailable(reason)) => {
+                content = content.push(
+                    container(text(reason).size(14))
+                        .width(Length::Fixed(300.0))
+                        .height(Length::Fixed(300.0))
+                        .center_x()
+                        .center_y()
+                        .style(iced::theme::Container::Box),
+                );
+            }
+            None => {}
+        }
+        if self.preview.is_some() {
+            // Bitmaps only: drawings and booklets are rendered at the output size
+            let croppable = !self.is_processing
+                && self
+                    .metadata
+                    .as_ref()
+                    .is_some_and(|metadata| metadata.dimensions.is_some());
+            content = content.push(
+                row![
+                    button(t("Large preview (Ctrl+P)")).on_press(Message::PreviewToggled),
+                    button(t("Crop..."))
+                        .on_press_maybe(croppable.then_some(Message::OpenCropEditor)),
+                ]
+                .spacing(10),
+            );
+        }
+
+        // The options alone are taller than most windows
+        scrollable(container(content).width(Length::Fill).center_x())
+            .height(Length::Fill)
+            .into()
+    }
+
+    // Multi-size export: every enabled profile is written for each image,
+    // in the order set with the drag handles
+    fn view_profiles(&self) -> Element<'_, Message> {
+        let mut profiles = column![text(t("Export sizes (none: use the preset above)"))].spacing(5);
+
+        for (index, profile) in self.profiles.iter().enumerate() {
+            let handle = mouse_area(text("≡")).on_press(Message::ProfileDragStarted(index));
+            let label = if self.dragging == Some(index) {
+                tf("{} (moving)", &[&profile.name()])
+            } else {
+                profile.name()
+            };
+
+            profiles = profiles.push(
+                mouse_area(
+                    row![
+                        handle,
+                        checkbox(label, profile.enabled)
+                            .on_toggle(move |enabled| Message::ProfileToggled(index, enabled)),
+                    ]
+                    .spacing(10),
+                )
+                .on_enter(Message::ProfileDraggedOver(index)),
+            );
+        }
+
+        let rockbox = self.profiles.iter().any(|profile| {
+            profile.enabled && matches!(profile.kind, export::ProfileKind::Rockbox(_))
+        });
+        if rockbox {
+            profiles = profiles.push(
+                checkbox(
+                    t("Replace the cover files already in the album"),
+                    self.replace_art,
+                )
+                .on_toggle(Message::ReplaceArtToggled),
+            );
+        }
+
+        profiles.into()
+    }
+
+    // Batch progress, one line per file
+    fn view_results(&self) -> Element<'_, Message> {
+        let mut list = column![].spacing(5);
+        let failed = self
+            .results
+            .iter()
+            .filter(|result| matches!(result.status, FileStatus::Failed(_)))
+            .count();
+        // The failures of a finished batch, to try again in one go
+        if failed > 0 && !self.is_processing {
+            list = list.push(
+                row![
+                    text(tf("{} files failed", &[&failed])).size(14),
+                    button(text(t("Retry failed")).size(14)).on_press(Message::RetryFailed),
+                ]
+                .spacing(10)
+                .align_items(iced::Alignment::Center),
+            );
+        }
+        for result in &self.results {
+            let detail = match &result.status {
+                FileStatus::Queued => t("queued").to_string(),
+                FileStatus::Processing => t("processing...").to_string(),
+                FileStatus::Done(outputs) => {
+                    let names: Vec<String> = outputs
+                        .iter()
+                        .map(|output| output.display().to_string())
+                        .collect();
+                    format!("-> {}", names.join(", "))
+                }
+                FileStatus::Failed(error_message) => error_message.clone(),
+                FileStatus::Removed => t("output removed").to_string(),
+            };
+
+            let mut line = row![
+                text(result.status.icon()).size(14),
+                text(format!("{} {}", file_name(&result.source), detail)).size(14),
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center);
+            if matches!(result.status, FileStatus::Failed(_) | FileStatus::Removed) {
+                line = line.push(
+                    button(text(t("Retry")).size(14))
+                        .on_press(Message::RetryFile(result.source.clone())),
+                );
+            }
+            list = list.push(line);
+        }
+
+        scrollable(list).height(Length::Fixed(120.0)).into()
+    }
+
+    // Versions of the last source, each with the settings behind it
+    fn view_history(&self) -> Element<'_, Message> {
+        let mut list = column![text(t("Versions")).size(14)].spacing(5);
+        for (index, version) in self.history.iter().enumerate() {
+            list = list.push(
+                row![
+                    text(format!(
+                        "v{} {}: {}",
+                        version.number,
+                        file_name(&version.output),
+                        version.summary()
+                    ))
+                    .size(14),
+                    button(text(t("Show")).size(14)).on_press(Message::ShowVersion(index)),
+                    button(text(t("Use settings")).size(14))
+                        .on_press(Message::RestoreVersion(index)),
+                ]
+                .spacing(10)
+                .align_items(iced::Alignment::Center),
+            );
+        }
+        list.into()
+    }
+
+    // Non-blocking notice for repeated drops, each with an override
+    fn view_skipped(&self) -> Element<'_, Message> {
+        let mut notice = column![
+            row![
+                text(tf("{} files skipped", &[&self.skipped.len()])).size(14),
+                button(text(t("Dismiss")).size(14)).on_press(Message::DismissSkipped),
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center)
+        ]
+        .spacing(5);
+
+        for (path, reason) in &self.skipped {
+            notice = notice.push(
+                row![
+                    text(format!("{}: {}", file_name(path), reason)).size(14),
+                    button(text(t("Process anyway")).size(14))
+                        .on_press(Message::ProcessAnyway(path.clone())),
+                ]
+                .spacing(10)
+                .align_items(iced::Alignment::Center),
+            );
+        }
+
+        notice.into()
+    }
+
+    fn view_health(&self) -> Element<'_, Message> {
+        let problems = self
+            .health
+            .iter()
+            .filter(|check| !matches!(check.status, health::Status::Passed(_)))
+            .count();
+        let summary = match problems {
+            0 => t("Startup check passed").to_string(),
+            1 => t("Startup check: 1 problem").to_string(),
+            count => tf("Startup check: {} problems", &[&count]),
+        };
+
+        let mut notice = column![
+            row![
+                text(summary).size(14),
+                button(text(t("Check again")).size(14)).on_press(Message::CheckHealth),
+                button(text(t("Dismiss")).size(14)).on_press(Message::DismissHealth),
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center)
+        ]
+        .spacing(5);
+
+        for check in &self.health {
+            let (label, detail) = match &check.status {
+                health::Status::Passed(detail) => (t("OK"), detail),
+                health::Status::Warning(detail) => (t("Warning"), detail),
+                health::Status::Failed(detail) => (t("Failed"), detail),
+            };
+            notice =
+                notice.push(text(format!("{} - {}: {}", label, t(check.name), detail)).size(14));
+        }
+
+        notice.into()
+    }
+
+    fn view_log(&self) -> Element<'_, Message> {
+        let mut lines = column![].spacing(2);
+        for line in &self.log {
+            lines = lines.push(text(line).size(12));
+        }
+        let folder =
+            logging::log_dir().map_or(t("None").to_string(), |dir| dir.display().to_string());
+
+        column![
+            scrollable(lines).height(Length::Fixed(150.0)),
+            row![
+                text(tf("Log files: {}", &[&folder])).size(14),
+                button(text(t("Copy diagnostics")).size(14)).on_press(Message::CopyDiagnostics),
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center)
+        ]
+        .spacing(5)
+        .into()
+    }
+
+    fn view_help(&self) -> Element<'_, Message> {
+        let mut bindings = column![text(t("Keyboard shortcuts")).size(24)].spacing(10);
+        for binding in &shortcuts::BINDINGS {
+            bindings = bindings.push(
+                row![
+                    text(binding.keys).width(Length::Fixed(80.0)),
+                    text(t(binding.description)),
+                ]
+                .spacing(10),
+            );
+        }
+        bindings = bindings.push(button(t("Close")).on_press(Message::HelpToggled));
+
+        container(scrollable(bindings))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .padding(20)
+            .into()
+    }
+
+    // The result as large as the window allows, or zoomed and scrolled,
+    // for checking details against the source
+    fn view_large_preview<'a>(&'a self, result: &'a preview::Preview) -> Element<'a, Message> {
+        let shown = match (&self.original_preview, self.show_original) {
+            (Some(original), true) => original,
+            _ => result,
+        };
+        let nearest = iced::widget::image::FilterMethod::Nearest;
+        let image: Element<'_, Message> = match (shown, self.zoom) {
+            (preview::Preview::Unavailable(reason), _) => text(reason).into(),
+            // The grid is drawn at its own scale, so only when fitted
+            (preview::Preview::Image { handle, .. }, None) => {
+                let handle = match &self.pixel_grid {
+                    Some(grid) if !self.show_original => grid,
+                    _ => handle,
+                };
+                container(
+                    Image::new(handle.clone())
+                        .width(Length::Fill)
+                        .height(Length::Fill)
+                        .content_fit(iced::ContentFit::Contain)
+                        .filter_method(nearest),
+                )
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .center_x()
+                .center_y()
+                .into()
+            }
+            (
+                preview::Preview::Image {
+                    handle,
+                    width,
+                    height,
+                },
+                Some(zoom),
+            ) => scrollable(
+                Image::new(handle.clone())
+                    .width(Length::Fixed((width * zoom) as f32))
+                    .height(Length::Fixed((height * zoom) as f32))
+                    .filter_method(nearest),
+            )
+            .direction(scrollable::Direction::Both {
+                vertical: scrollable::Properties::default(),
+                horizontal: scrollable::Properties::default(),
+            })
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into(),
+        };
+        let grid_label = if self.pixel_grid.is_some() {
+            t("Hide pixel grid")
+        } else {
+            t("Show pixel grid")
+        };
+        let compare_label = if self.show_original {
+            t("Show result")
+        } else {
+            t("Show original")
+        };
+        let zoom_label = match self.zoom {
+            Some(zoom) => format!("{}%", zoom * 100),
+            None => t("Fit").to_string(),
+        };
+
+        let zoom = row![
+            button(t("Fit")).on_press_maybe(self.zoom.map(|_| Message::PreviewZoomed(None))),
+            button("100%").on_press(Message::PreviewZoomed(Some(1))),
+            button("-").on_press_maybe(self.zoom.map(|zoom| zoom_out(Some(zoom)))),
+            button("+").on_press_maybe((self.zoom < Some(MAX_ZOOM)).then(|| zoom_in(self.zoom))),
+            text(zoom_label).size(14),
+        ]
+        .spacing(5)
+        .align_items(iced::Alignment::Center);
+
+        container(
+            column![
+                image,
+                row![
+                    zoom,
+                    button(compare_label).on_press(Message::OriginalToggled),
+                    button(grid_label).on_press_maybe(
+                        (self.zoom.is_none() && !self.show_original)
+                            .then_some(Message::PixelGridToggled)
+                    ),
+                    button(t("Close")).on_press(Message::PreviewToggled),
+                    text(t("Esc closes, Ctrl+G toggles the grid")).size(14),
+                ]
+                .spacing(10)
+                .align_items(iced::Alignment::Center),
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center),
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .padding(10)
+        .into()
+    }
+
+    // The palette size only matters for palettes
+    fn view_color_mode(&self) -> Element<'_, Message> {
+        let mode = pick_list(
+            &ColorMode::ALL[..],
+            Some(self.options.color_mode),
+            Message::ColorModeSelected,
+        );
+        let mut setting = row![mode].spacing(10).align_items(iced::Alignment::Center);
+        if self.options.color_mode == ColorMode::Palette {
+            setting = setting.push(text(tf("Colors: {}", &[&self.options.palette_colors])));
+            setting = setting.push(slider(
+                2..=quantize::MAX_PALETTE_COLORS,
+                self.options.palette_colors,
+                Message::PaletteColorsChanged,
+            ));
+        }
+        setting.into()
+    }
+
+    // Fixed JPEG quality, or the SSIM target of the automatic search
+    fn view_quality(&self) -> Element<'_, Message> {
+        let auto = checkbox(t("Auto"), self.options.auto_quality.is_some())
+            .on_toggle(Message::AutoQualityToggled);
+
+        let setting: Element<'_, Message> = match self.options.auto_quality {
+            Some(target) => row![
+                text(tf("Target SSIM: {}", &[&format!("{:.3}", target)])),
+                slider(0.90..=0.995, target, Message::TargetSsimChanged).step(0.005),
+            ]
+            .spacing(10)
+            .into(),
+            None => row![
+                text(tf("JPEG quality: {}", &[&self.options.jpeg_quality])),
+                slider(
+                    50..=100,
+                    self.options.jpeg_quality,
+                    Message::JpegQualityChanged
+                ),
+            ]
+            .spacing(10)
+            .into(),
+        };
+
+        row![setting, auto]
+            .spacing(10)
+            .align_items(iced::Alignment::Center)
+            .into()
+    }
+
+    fn view_watch(&self) -> Element<'_, Message> {
+        let toggle = if self.watch.is_active {
+            button(t("Stop"))
+        } else {
+            button(t("Start"))
+        };
+
+        let controls = row![
+            text_input(t("Folder to watch"), &self.watch.folder)
+                .on_input(Message::WatchFolderChanged)
+                .on_submit(Message::WatchToggled),
+            toggle.on_press(Message::WatchToggled),
+        ]
+        .spacing(10);
+
+        let log = self
+            .watch
+            .log
+            .iter()
+            .rev()
+            .fold(column![].spacing(5), |log, entry| {
+                log.push(text(entry).size(14))
+            });
+
+        column![
+            controls,
+            text(t("New images are converted with the current settings")),
+            scrollable(log).height(Length::Fill),
+        ]
+        .spacing(15)
+        .into()
+    }
+
+    fn view_dedupe(&self) -> Element<'_, Message> {
+        let controls = row![
+            text_input(t("Art folder (or drop it here)"), &self.dedupe.folder)
+                .on_input(Message::DedupeFolderChanged)
+                .on_submit(Message::DedupeScan),
+            button(t("Scan")).on_press(Message::DedupeScan),
+        ]
+        .spacing(10);
+
+        let mut clusters = column![].spacing(15);
+        for (cluster_index, cluster) in self.dedupe.clusters.iter().enumerate() {
+            let mut files = column![text(tf("Group {}", &[&(cluster_index + 1)]))].spacing(5);
+            for (file_index, file) in cluster.files.iter().enumerate() {
+                let label = format!(
+                    "{} ({}x{}, {} KB)",
+                    file.path.display(),
+                    file.width,
+                    file.height,
+                    file.bytes / 1024
+                );
+                files = files.push(radio(label, file_index, Some(cluster.keep), move |file| {
+                    Message::DedupeKeepSelected(cluster_index, file)
+                }));
+            }
+            clusters = clusters.push(files);
+        }
+
+        let mut content = column![controls, text(&self.dedupe.status)].spacing(15);
+        for failure in &self.dedupe.failures {
+            content = content.push(text(failure).size(14));
+        }
+
+        if let Some(action) = self.dedupe.pending {
+            let count: usize = self
+                .dedupe
+                .clusters
+                .iter()
+                .map(|cluster| cluster.files.len() - 1)
+                .sum();
+            let question = match action {
+                dedupe::DuplicateAction::Delete => {
+                    tf("Delete {} files, keeping the selected ones?", &[&count])
+                }
+                dedupe::DuplicateAction::Link => tf(
+                    "Replace {} files with links to the selected ones?",
+                    &[&count],
+                ),
+            };
+            content = content.push(
+                row![
+                    text(question),
+                    button(t("Confirm")).on_press(Message::DedupeConfirm),
+                    button(t("Cancel")).on_press(Message::DedupeCancel),
+                ]
+                .spacing(10)
+                .align_items(iced::Alignment::Center),
+            );
+        } else if !self.dedupe.clusters.is_empty() {
+            content = content.push(
+                row![
+                    button(t("Keep selected, delete rest"))
+                        .on_press(Message::DedupeResolve(dedupe::DuplicateAction::Delete)),
+                    button(t("Keep selected, link rest"))
+                        .on_press(Message::DedupeResolve(dedupe::DuplicateAction::Link)),
+                ]
+                .spacing(10),
+            );
+        }
+        if !self.dedupe.clusters.is_empty() {
+            content = content.push(scrollable(clusters).height(Length::Fill));
+        }
+
+        content.into()
+    }
+
+    fn view_library(&self) -> Element<'_, Message> {
+        let controls = row![
+            text_input(t("Music folder (or drop it here)"), &self.library.folder)
+                .on_input(Message::LibraryFolderChanged)
+                .on_submit(Message::LibraryScan),
+            button(t("Scan")).on_press(Message::LibraryScan),
+        ]
+        .spacing(10);
+
+        let mut content = column![controls, text(&self.library.status)].spacing(15);
+
+        if !self.library.folder.trim().is_empty() {
+            content = content.push(
+                checkbox(
+                    tf(
+                        "Check and fix the covers every night at {}:00",
+                        &[&schedule::NIGHTLY_HOUR],
+                    ),
+                    self.library.nightly.is_some(),
+                )
+                .on_toggle(Message::NightlyToggled),
+            );
+        }
+
+        if self.library.is_busy && self.library.cancel.is_some() {
+            content = content.push(button(t("Cancel")).on_press(Message::LibraryCancel));
+        } else if !self.library.albums.is_empty() && !self.library.is_busy {
+            content = content.push(
+                row![
+                    button(text(tf(
+                        "Write {} into every album",
+                        &[&library::COVER_FILE_NAME],
+                    )))
+                    .on_press(Message::LibraryConvert),
+                    button(t("Find missing covers")).on_press(Message::LibraryAudit),
+                ]
+                .spacing(10),
+            );
+            let existing = self
+                .library
+                .albums
+                .iter()
+                .filter(|album| album.has_cover && album.source.is_some())
+                .count();
+            if existing > 0 {
+                content = content.push(
+                    checkbox(
+                        tf(
+                            "Replace the existing {} in {} albums",
+                            &[&library::COVER_FILE_NAME, &existing],
+                        ),
+                        self.library.replace_covers,
+                    )
+                    .on_toggle(Message::ReplaceCoversToggled),
+                );
+            }
+        }
+
+        if let Some(gaps) = &self.library.gaps
+            && !gaps.is_empty()
+        {
+            content = content.push(
+                row![
+                    checkbox(t("Look up the Cover Art Archive"), self.library.online)
+                        .on_toggle(Message::OnlineLookupToggled),
+                    button(text(tf("Fix {} albums", &[&gaps.len()])))
+                        .on_press_maybe((!self.library.is_busy).then_some(Message::FixCovers)),
+                ]
+                .spacing(10)
+                .align_items(iced::Alignment::Center),
+            );
+        }
+
+        if !self.library.albums.is_empty() {
+            content = content.push(self.view_device_copy());
+            let list = match (&self.library.plan, &self.library.gaps) {
+                (Some(plan), _) => self.view_device_plan(plan),
+                (None, Some(gaps)) => self.view_gaps(gaps),
+                (None, None) => self.view_library_tree(),
+            };
+            content = content.push(scrollable(list).height(Length::Fill));
+        }
+
+        content.into()
+    }
+
+    fn view_device_copy(&self) -> Element<'_, Message> {
+        let idle = !self.library.is_busy;
+        let device = row![
+            text(t("Player")),
+            pick_list(
+                self.library.devices.clone(),
+                self.library.device.clone(),
+                Message::DeviceSelected,
+            )
+            .placeholder(t("No iPod or Rockbox player mounted")),
+            button(t("Find players")).on_press(Message::FindDevices),
+        ]
+        .spacing(10)
+        .align_items(iced::Alignment::Center);
+
+        let has_changes = self.library.plan.as_ref().is_some_and(|plan| {
+            plan.copies
+                .iter()
+                .any(|copy| copy.action != ipod::Action::Unchanged)
+        });
+        let copy = row![
+            text_input(
+                t("Artwork folder on the player (empty: album folders)"),
+                &self.library.artwork_folder,
+            )
+            .on_input(Message::ArtworkFolderChanged),
+            button(t("Preview copy")).on_press_maybe(
+                (idle && self.library.device.is_some()).then_some(Message::PreviewDeviceCopy)
+            ),
+            button(t("Copy to player"))
+                .on_press_maybe((idle && has_changes).then_some(Message::CopyToDevice)),
+        ]
+        .spacing(10)
+        .align_items(iced::Alignment::Center);
+
+        column![device, copy].spacing(10).into()
+    }
+
+    // What the copy would write, before anything is written
+    fn view_device_plan<'a>(&self, plan: &'a ipod::Plan) -> Element<'a, Message> {
+        let mut list = column![].spacing(4);
+        for copy in &plan.copies {
+            let action = match copy.action {
+                ipod::Action::New => t("new"),
+                ipod::Action::Replace => t("replace"),
+                ipod::Action::Unchanged => t("unchanged"),
+            };
+            list = list.push(text(format!("{}: {}", action, copy.destination.display())).size(14));
+        }
+        for folder in &plan.unmatched {
+            list = list.push(text(tf("no matching album for {}", &[&folder.display()])).size(14));
+        }
+        list.into()
+    }
+
+    // Albums needing a cover, why, and where one was found once fixed
+    fn view_gaps<'a>(&'a self, gaps: &'a [audit::Gap]) -> Element<'a, Message> {
+        let mut list = column![].spacing(4);
+        for (gap, fix) in gaps.iter().zip(&self.library.fixes) {
+            let folder = gap
+                .album
+                .folder
+                .strip_prefix(&self.library.root)
+                .unwrap_or(&gap.album.folder)
+                .display();
+            let detail = match fix {
+                Some(Ok(Some(audit::Found::Artwork(source)))) => {
+                    tf("✓ from {}", &[&file_name(source)])
+                }
+                Some(Ok(Some(audit::Found::Embedded(track)))) => {
+                    tf("✓ from the tags of {}", &[&file_name(track)])
+                }
+                Some(Ok(Some(audit::Found::CoverArtArchive(_)))) => {
+                    t("✓ from the Cover Art Archive").to_string()
+                }
+                Some(Ok(None)) => t("– no cover found").to_string(),
+                Some(Err(error_message)) => format!("✗ {}", error_message),
+                None => match gap.finding {
+                    audit::Finding::Missing => tf("no {}", &[&library::COVER_FILE_NAME]),
+                    audit::Finding::Unreadable => tf("{} is damaged", &[&library::COVER_FILE_NAME]),
+                    audit::Finding::TooSmall(width, height) => {
+                        tf("cover only {}x{}", &[&width, &height])
+                    }
+                },
+            };
+            list = list.push(text(format!("{}: {}", folder, detail)).size(14));
+        }
+        list.into()
+    }
+
+    // Albums nested under their parent folders (artist, genre...), each
+    // with its chosen artwork or conversion outcome
+    fn view_library_tree(&self) -> Element<'_, Message> {
+        let indent = |depth: usize| Space::with_width(Length::Fixed(depth as f32 * 16.0));
+
+        let mut tree = column![].spacing(4);
+        let mut shown: Vec<String> = Vec::new();
+
+        for (album, result) in self.library.albums.iter().zip(&self.library.results) {
+            let relative = album
+                .folder
+                .strip_prefix(&self.library.root)
+                .unwrap_or(&album.folder);
+            let mut components: Vec<String> = relative
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy().into_owned())
+                .collect();
+            if components.is_empty() {
+                components.push(self.library.root.display().to_string());
+            }
+
+            // Parent folders shared with the previous album are not repeated
+            let common = shown
+                .iter()
+                .zip(&components)
+                .take_while(|(a, b)| a == b)
+                .count();
+            for (depth, name) in components.iter().enumerate().skip(common) {
+                tree = tree.push(row![indent(depth), text(format!("{}/", name)).size(14)]);
+            }
+            shown = components;
+
+            let outcome = match (result, &album.source) {
+                (Some(Ok(Some(_))), Some(source)) => tf(
+                    "✓ {} from {}",
+                    &[&library::COVER_FILE_NAME, &file_name(source)],
+                ),
+                (Some(Err(error_message)), _) => format!("✗ {}", error_message),
+                (_, Some(source)) if album.has_cover && self.library.replace_covers => tf(
+                    "{} – replaces its {}",
+                    &[&file_name(source), &library::COVER_FILE_NAME],
+                ),
+                (_, _) if album.has_cover => tf("– keeps its {}", &[&library::COVER_FILE_NAME]),
+                (_, None) => t("– no artwork found").to_string(),
+                (_, Some(source)) => file_name(source),
+            };
+            tree = tree.push(row![indent(shown.len()), text(outcome).size(14)]);
+        }
+
+        tree.into()
+    }
+
+    fn view_community(&self) -> Element<'_, Message> {
+        let controls = row![
+            text_input(t("Preset index URL or file"), &self.community.index)
+                .on_input(Message::CommunityIndexChanged)
+                .on_submit(Message::CommunityFetch),
+            button(t("Load")).on_press(Message::CommunityFetch),
+        ]
+        .spacing(10);
+
+        let mut entries = column![].spacing(10);
+        for (index, entry) in self.community.entries.iter().enumerate() {
+            let installed = self.presets.iter().any(|preset| preset.name == entry.name);
+            let install = if installed {
+                button(t("Reinstall"))
+            } else {
+                button(t("Install"))
+            };
+
+            entries = entries.push(
+                row![
+                    column![text(&entry.name), text(&entry.description).size(14)]
+                        .width(Length::Fill),
+                    install.on_press(Message::CommunityInstall(index)),
+                ]
+                .spacing(10)
+                .align_items(iced::Alignment::Center),
+            );
+        }
+
+        column![
+            controls,
+            text(t(
+                "Presets are checked against the SHA-256 of the index before import"
+            ))
+            .size(14),
+            text(&self.community.status),
+            scrollable(entries).height(Length::Fill),
+        ]
+        .spacing(15)
+        .into()
+    }
+
+    fn handle_file_drop(&mut self, path: PathBuf) -> Command<Message> {
+        // Folders dropped on the dedupe screen are scanned right away
+        if self.screen == Screen::Dedupe {
+            if path.is_dir() {
+                self.dedupe.folder = path.display().to_string();
+                return Command::perform(async {}, |_| Message::DedupeScan);
+            }
+            return Command::none();
+        }
+
+        // Same for music folders on the library screen
+        if self.screen == Screen::Library {
+            if path.is_dir() && !self.library.is_busy {
+                self.library.folder = path.display().to_string();
+                return Command::perform(async {}, |_| Message::LibraryScan);
+            }
+            return Command::none();
+        }
+
+        // Audio files give their embedded artwork
+        if artcover::is_supported_image(&path) || library::is_audio(&path) {
+            return Command::perform(async { path }, Message::FileDropped);
+        }
+
+        // .cube tables are assigned to the current preset
+        if path.extension().and_then(|s| s.to_str()) == Some("cube") {
+            return load_lut(path);
+        }
+
+        // Rockbox theme configs become presets
+        if path.extension().and_then(|s| s.to_str()) == Some("cfg") {
+            return Command::perform(
+                async move { rockbox::import_theme(&path).map_err(Arc::new) },
+                Message::ThemeImported,
+            );
+        }
+
+        self.message =
+            t("Error: only images, audio files, color LUTs and Rockbox themes are supported")
+                .to_string();
+        Command::none()
+    }
+
+    fn start_processing(&mut self, path: PathBuf) -> Command<Message> {
+        self.is_processing = true;
+        self.current = Some(path.clone());
+        self.last_source = Some(path.clone());
+        self.set_status(FileStatus::Processing);
+        self.show_result(None);
+        self.text_hint = None;
+        self.warnings.clear();
+        self.upscaled = false;
+        self.metadata = None;
+        self.message = t("Processing...").to_string();
+
+        self.progress = 0.0;
+
+        let cancel = CancelToken::default();
+        self.cancel = Some(cancel.clone());
+
+        // Runs on its own thread and streams progress back to the UI
+        let mut options = self.options.clone();
+        options.crop = self
+            .crop
+            .as_ref()
+            .filter(|(source, _)| *source == path)
+            .map(|(_, square)| *square);
+        let profiles = self.profiles.clone();
+        let replace_art = self.replace_art;
+        let (sender, receiver) = mpsc::unbounded();
+        std::thread::spawn(move || {
+            let mut reported = 0.0;
+            let mut report = |progress| {
+                // The decoder reads in small chunks, skip tiny steps
+                if progress - reported >= 0.01 {
+                    reported = progress;
+                    let _ = sender.unbounded_send(Message::Progress(progress));
+                }
+            };
+            // Shown while the conversion runs
+            if !library::is_audio(&path)
+                && let Ok(metadata) = probe::inspect(&path)
+            {
+                let _ = sender.unbounded_send(Message::Inspected(metadata));
+            }
+            if library::is_audio(&path) {
+                let result = embedded::extract_cover(&path, &options, false, &mut report, &cancel);
+                let _ = sender.unbounded_send(Message::ImageProcessed(result.map_err(Arc::new)));
+            } else if profiles.iter().any(|profile| profile.enabled) {
+                let results = export::export(
+                    &path,
+                    &options,
+                    &profiles,
+                    replace_art,
+                    &mut report,
+                    &cancel,
+                )
+                .into_iter()
+                .map(|output| (output.profile, output.result.map_err(Arc::new)))
+                .collect();
+                let _ = sender.unbounded_send(Message::ProfilesExported(results));
+            } else {
+                let result = artcover::process_image_with(&path, &options, &mut report, &cancel);
+                let _ = sender.unbounded_send(Message::ImageProcessed(result.map_err(Arc::new)));
+            }
+        });
+
+        Command::run(receiver, |message| message)
+    }
+
+    // Imported presets replace any earlier one with the same name
+    fn add_preset(&mut self, preset: preset::Preset) {
+        self.presets.retain(|existing| existing.name != preset.name);
+        self.presets.push(preset.clone());
+        self.profiles
+            .retain(|profile| profile.name() != preset.name);
+        self.profiles
+            .extend(export::profiles(std::slice::from_ref(&preset)));
+    }
+
+    // Add or replace the profile of the same name
+    fn keep_options_profile(&mut self, profile: settings::OptionsProfile) {
+        self.option_profiles
+            .retain(|existing| existing.name != profile.name);
+        self.option_profiles.push(profile);
+    }
+
+    // Options of `profile`, with its preset added to the list when it was
+    // made on another machine
+    fn use_options_profile(&mut self, profile: settings::OptionsProfile) {
+        if !self.presets.contains(&profile.options.preset) {
+            self.add_preset(profile.options.preset.clone());
+        }
+        self.options = profile.options;
+        self.selected_profile = Some(profile.name);
+    }
+
+    // Change the current preset along with its copies in the preset list
+    // and the export profiles
+    fn update_preset(&mut self, change: impl Fn(&mut preset::Preset)) {
+        let name = self.options.preset.name.clone();
+        change(&mut self.options.preset);
+        for preset in self.presets.iter_mut().filter(|preset| preset.name == name) {
+            change(preset);
+        }
+        for profile in &mut self.profiles {
+            if let export::ProfileKind::Single(preset) = &mut profile.kind
+                && preset.name == name
+            {
+                change(preset);
+            }
+        }
+    }
+
+    // Run the startup check against the folders and services in use
+    fn check_health(&self) -> Command<Message> {
+        let output_folders = [&self.watch.folder, &self.library.folder]
+            .into_iter()
+            .map(|folder| folder.trim())
+            .filter(|folder| !folder.is_empty())
+            .map(PathBuf::from)
+            .collect();
+        let ocr_languages = self.options.ocr.then(|| self.options.ocr_languages.clone());
+        let community_index =
+            Some(self.community.index.trim().to_string()).filter(|index| !index.is_empty());
+
+        let setup = health::Setup {
+            output_folders,
+            ocr_languages,
+            community_index,
+        };
+        Command::perform(async move { health::run(&setup) }, Message::HealthChecked)
+    }
+
+    fn announce(&mut self) {
+        self.announcement = Some(self.message.clone());
+    }
+
+    fn enqueue(&mut self, path: PathBuf) -> Command<Message> {
+        // A new batch starts with an empty report
+        if !self.is_processing && self.queue.is_empty() {
+            self.export_report.clear();
+            self.results.clear();
+            self.batch_summary = None;
+        }
+        self.queue_file(path)
+    }
+
+    // Retried files keep their place in the results list
+    fn queue_file(&mut self, path: PathBuf) -> Command<Message> {
+        match self.results.iter_mut().find(|result| result.source == path) {
+            Some(result) => result.status = FileStatus::Queued,
+            None => self.results.push(FileResult {
+                source: path.clone(),
+                status: FileStatus::Queued,
+            }),
+        }
+        if self.is_processing {
+            self.queue.push_back(path);
+            return Command::none();
+        }
+        self.start_processing(path)
+    }
+
+    fn run_shortcut(&mut self, action: shortcuts::Action) -> Command<Message> {
+        match action {
+            shortcuts::Action::Open => Command::perform(pick_files(), Message::FilesPicked),
+            shortcuts::Action::Paste => iced::clipboard::read(Message::Pasted),
+            shortcuts::Action::Undo => {
+                self.undo_last_output();
+                Command::none()
+            }
+            shortcuts::Action::Reprocess => match self.last_source.clone() {
+                Some(path) if !self.is_processing && self.screen == Screen::Convert => {
+                    self.queue_file(path)
+                }
+                _ => Command::none(),
+            },
+            // Closes the innermost view first
+            shortcuts::Action::Cancel => {
+                if self.show_help {
+                    self.show_help = false;
+                } else if self.large_preview {
+                    self.large_preview = false;
+                } else if self.crop_editor.is_some() {
+                    self.crop_editor = None;
+                } else {
+                    self.cancel_batch();
+                }
+                Command::none()
+            }
+            shortcuts::Action::Preview => Command::perform(async {}, |_| Message::PreviewToggled),
+            shortcuts::Action::PixelGrid if self.large_preview => {
+                Command::perform(async {}, |_| Message::PixelGridToggled)
+            }
+            shortcuts::Action::PixelGrid => Command::none(),
+            shortcuts::Action::Compare if self.large_preview => {
+                self.update(Message::OriginalToggled)
+            }
+            shortcuts::Action::ZoomIn if self.large_preview => self.update(zoom_in(self.zoom)),
+            shortcuts::Action::ZoomOut if self.large_preview => self.update(zoom_out(self.zoom)),
+            shortcuts::Action::ActualSize if self.large_preview => {
+                self.update(Message::PreviewZoomed(Some(1)))
+            }
+            shortcuts::Action::Compare
+            | shortcuts::Action::ZoomIn
+            | shortcuts::Action::ZoomOut
+            | shortcuts::Action::ActualSize => Command::none(),
+            shortcuts::Action::Help => {
+                self.show_help = !self.show_help;
+                Command::none()
+            }
+        }
+    }
+
+    // Output shown under the controls, or none
+    fn show_result(&mut self, path: Option<PathBuf>) {
+        self.preview = path.as_deref().map(preview::load);
+        self.show_original = false;
+        self.original_preview = None;
+        self.processed_image = path;
+    }
+
+    // Outputs that can be taken back; a source passed through onto
+    // itself is never one of them
+    fn remember_outputs(&mut self, outputs: Vec<PathBuf>) {
+        let Some(source) = self.current.clone() else {
+            return;
+        };
+        let outputs: Vec<PathBuf> = outputs
+            .into_iter()
+            .filter(|output| !artcover::is_same_file(output, &source))
+            .collect();
+        if !outputs.is_empty() {
+            self.undo.push((source, outputs));
+        }
+    }
+
+    fn undo_last_output(&mut self) {
+        if self.is_processing {
+            return;
+        }
+        let Some((source, outputs)) = self.undo.pop() else {
+            self.message = t("Nothing to undo").to_string();
+            return;
+        };
+
+        let mut removed = 0;
+        for output in &outputs {
+            match std::fs::remove_file(output) {
+                Ok(()) => removed += 1,
+                Err(e) => {
+                    self.message = tf("Error: {}", &[&describe(&ArtCoverError::io(output, e))]);
+                }
+            }
+            if self.processed_image.as_ref() == Some(output) {
+                self.show_result(None);
+                self.large_preview = false;
+            }
+        }
+        self.history.retain(|version| version.output.exists());
+
+        if removed > 0 {
+            self.message = tf(
+                "Removed {} output(s) of {}",
+                &[&removed, &file_name(&source)],
+            );
+            if let Some(result) = self
+                .results
+                .iter_mut()
+                .find(|result| result.source == source)
+            {
+                result.status = FileStatus::Removed;
+            }
+        }
+        self.announce();
+    }
+
+    // Stop the file being converted and drop the queued ones
+    fn cancel_batch(&mut self) {
+        for path in self.queue.drain(..) {
+            if let Some(result) = self.results.iter_mut().find(|result| result.source == path) {
+                result.status = FileStatus::Failed(t("Cancelled").to_string());
+            }
+        }
+        if let Some(cancel) = &self.cancel {
+            cancel.cancel();
+        }
+    }
+
+    // Everything worth attaching to a bug report. Kept in English.
+    fn diagnostics(&self) -> String {
+        let mut report = vec![
+            format!("artcover {}", env!("CARGO_PKG_VERSION")),
+            format!("{} {}", std::env::consts::OS, std::env::consts::ARCH),
+            format!("Language: {:?}", i18n::current()),
+        ];
+        if let Some(dir) = logging::log_dir() {
+            report.push(format!("Log files: {}", dir.display()));
+        }
+        for check in &self.health {
+            report.push(format!("Check {}: {:?}", check.name, check.status));
+        }
+        report.push(String::new());
+        report.extend(logging::recent());
+        report.join("\n")
+    }
+
+    // Theme, language and option profiles are remembered in the settings
+    // file for the next launch
+    fn bundle(&self) -> settings::Bundle {
+        settings::Bundle {
+            appearance: self.appearance,
+            language: self.language,
+            option_profiles: self.option_profiles.clone(),
+            nightly_library: self
+                .library
+                .nightly
+                .is_some()
+                .then(|| PathBuf::from(self.library.folder.trim())),
+            ..settings::Bundle::new(&self.options, &self.presets, &self.profiles)
+        }
+    }
+
+    // Current settings less the overrides still in effect
+    fn own_bundle(&self) -> settings::Bundle {
+        overrides::own_values(
+            &self.own_settings,
+            &self.overridden_settings,
+            &self.bundle(),
+        )
+    }
+
+    // Unfinished work and last-used settings, written after every change
+    // so nothing is lost however the app ends
+    fn persist(&mut self) {
+        let session = session::Saved {
+            pending: self
+                .unfinished
+                .iter()
+                .chain(&self.current)
+                .chain(&self.queue)
+                .cloned()
+                .collect(),
+            finished: self
+                .results
+                .iter()
+                .filter_map(|result| match &result.status {
+                    FileStatus::Done(outputs) => Some((result.source.clone(), outputs.clone())),
+                    _ => None,
+                })
+                .collect(),
+            undo: self.undo.clone(),
+        };
+        // Remembered even when writing fails, not to retry on every message
+        if session != self.written_session {
+            if let Err(error) = session::save(&session) {
+                self.message = tf("Error: {}", &[&describe(&error)]);
+            }
+            self.written_session = session;
+        }
+
+        let bundle = self.own_bundle();
+        let json = serde_json::to_string(&bundle).unwrap_or_default();
+        if json != self.written_settings {
+            if let Err(error) = settings::save(&bundle) {
+                self.message = tf("Error: {}", &[&describe(&error)]);
+            }
+            self.written_settings = json;
+        }
+    }
+
+    // Status of the file being processed
+    fn set_status(&mut self, status: FileStatus) {
+        if let Some(path) = &self.current
+            && let Some(result) = self
+                .results
+                .iter_mut()
+                .find(|result| result.source == *path)
+        {
+            result.status = status;
+        }
+    }
+
+    fn skip(&mut self, path: PathBuf, reason: String) {
+        if !self.skipped.iter().any(|(skipped, _)| *skipped == path) {
+            self.skipped.push((path, reason));
+        }
+    }
+
+    // Dropped or watched images wait here while another one is processed
+    fn process_next(&mut self) -> Command<Message> {
+        self.current = None;
+        self.cancel = None;
+        match self.queue.pop_front() {
+            Some(path) => self.start_processing(path),
+            None => self.finish_batch(),
+        }
+    }
+
+    // A single file needs no summary, its outcome is already on screen
+    fn finish_batch(&mut self) -> Command<Message> {
+        if self.results.len() < 2 {
+            return Command::none();
+        }
+        let count = |done: bool| {
+            self.results
+                .iter()
+                .filter(|result| match result.status {
+                    FileStatus::Done(_) => done,
+                    FileStatus::Failed(_) => !done,
+                    _ => false,
+                })
+                .count()
+        };
+        let (done, failed) = (count(true), count(false));
+        self.batch_summary = Some((done, failed));
+
+        if self.focused {
+            return Command::none();
+        }
+        Command::perform(notification::batch_finished(done, failed), |_| {
+            Message::NotificationShown
+        })
+    }
+
+    fn current_name(&self) -> String {
+        self.current.as_deref().map(file_name).unwrap_or_default()
+    }
+
+    fn log_watched(&mut self, entry: String) {
+        if let Some(path) = &self.current
+            && self.watch.sources.contains(path)
+        {
+            self.watch.log.push(entry);
+        }
+    }
+}
+
+fn tab_button(label: &str, screen: Screen, current: Screen) -> Element<'_, Message> {
+    let style = if screen == current {
+        iced::theme::Button::Primary
+    } else {
+        iced::theme::Button::Secondary
+    };
+
+    button(text(label))
+        .style(style)
+        .on_press(Message::ScreenSelected(screen))
+        .into()
+}
+
+async fn pick_files() -> Vec<PathBuf> {
+    rfd::AsyncFileDialog::new()
+        .set_title(t("Open images"))
+        .add_filter(t("Images"), &artcover::IMAGE_EXTENSIONS)
+        .add_filter(t("Audio files"), &library::AUDIO_EXTENSIONS)
+        .add_filter(t("Rockbox themes"), &["cfg"])
+        .add_filter(t("Color LUTs"), &["cube"])
+        .pick_files()
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|file| file.path().to_path_buf())
+        .collect()
+}
+
+async fn pick_lut() -> Option<PathBuf> {
+    let file = rfd::AsyncFileDialog::new()
+        .set_title(t("Choose a color LUT"))
+        .add_filter(t("Color LUTs"), &["cube"])
+        .pick_file()
+        .await?;
+    Some(file.path().to_path_buf())
+}
+
+// Parse the table once so a broken file is reported right away
+fn load_lut(path: PathBuf) -> Command<Message> {
+    Command::perform(
+        async move { lut::Lut::load(&path).map(|_| path).map_err(Arc::new) },
+        Message::LutLoaded,
+    )
+}
+
+// None when the dialog is dismissed
+async fn save_bundle(bundle: settings::Bundle) -> Option<Result<PathBuf, Arc<ArtCoverError>>> {
+    let file = rfd::AsyncFileDialog::new()
+        .set_title(t("Export settings"))
+        .set_file_name("artcover-settings.json")
+        .add_filter(t("Settings"), &["json"])
+        .save_file()
+        .await?;
+
+    let path = file.path().to_path_buf();
+    Some(
+        settings::export_bundle(&path, &bundle)
+            .map(|_| path)
+            .map_err(Arc::new),
+    )
+}
+
+async fn open_bundle() -> Option<Result<settings::Bundle, Arc<ArtCoverError>>> {
+    let file = rfd::AsyncFileDialog::new()
+        .set_title(t("Import settings"))
+        .add_filter(t("Settings"), &["json"])
+        .pick_file()
+        .await?;
+
+    Some(settings::import_bundle(file.path()).map_err(Arc::new))
+}
+
+async fn save_profile(
+    profile: settings::OptionsProfile,
+) -> Option<Result<PathBuf, Arc<ArtCoverError>>> {
+    let file = rfd::AsyncFileDialog::new()
+        .set_title(t("Export profile"))
+        .set_file_name("artcover-profile.json")
+        .add_filter(t("Profile"), &["json"])
+        .save_file()
+        .await?;
+
+    let path = file.path().to_path_buf();
+    Some(
+        settings::export_profile(&path, &profile)
+            .map(|_| path)
+            .map_err(Arc::new),
+    )
+}
+
+async fn open_profile() -> Option<Result<settings::OptionsProfile, Arc<ArtCoverError>>> {
+    let file = rfd::AsyncFileDialog::new()
+        .set_title(t("Import profile"))
+        .add_filter(t("Profile"), &["json"])
+        .pick_file()
+        .await?;
+
+    Some(settings::import_profile(file.path()).map_err(Arc::new))
+}
+
+// Light when the OS does not say
+fn system_is_dark() -> bool {
+    matches!(dark_light::detect(), Ok(dark_light::Mode::Dark))
+}
+
+// The source with the square to keep over it
+fn view_crop_editor(editing: &CropEditing) -> Element<'_, Message> {
+    let editor: Element<'_, Message> = match &editing.preview {
+        preview::Preview::Image { handle, .. } => crop::crop_editor(
+            handle.clone(),
+            editing.size,
+            editing.selection,
+            480.0,
+            Message::CropChanged,
+        )
+        .into(),
+        preview::Preview::Unavailable(reason) => text(reason).into(),
+    };
+    let Square { x, y, side } = editing.selection;
+
+    container(
+        column![
+            text(t("Drag the square over the part of the cover to keep")),
+            editor,
+            text(tf("{}x{} pixels from {},{}", &[&side, &side, &x, &y])).size(14),
+            row![
+                button(t("Apply crop")).on_press(Message::ApplyCrop),
+                button(t("Automatic crop")).on_press(Message::AutomaticCrop),
+                button(t("Cancel")).on_press(Message::CloseCropEditor),
+            ]
+            .spacing(10),
+        ]
+        .spacing(10)
+        .align_items(iced::Alignment::Center),
+    )
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .center_x()
+    .center_y()
+    .padding(20)
+    .into()
+}
+
+// Format, size, colors and profile of the file being converted
+fn view_metadata(metadata: &probe::Metadata) -> Element<'_, Message> {
+    let unknown = || t("Unknown").to_string();
+    let mut color = metadata.color.map_or_else(unknown, color_name);
+    if metadata.encoding.cmyk_jpeg {
+        color = "CMYK".to_string();
+    }
+
+    let fields = [
+        (
+            t("Format"),
+            metadata
+                .format
+                .map_or_else(unknown, |format| format!("{:?}", format).to_uppercase()),
+        ),
+        (
+            t("Dimensions"),
+            metadata
+                .dimensions
+                .map_or_else(unknown, |(width, height)| format!("{}x{}", width, height)),
+        ),
+        (t("Color"), color),
+        (
+            t("Resolution"),
+            match metadata.dpi {
+                Some((x, y)) if x.round() == y.round() => tf("{} DPI", &[&x.round()]),
+                Some((x, y)) => tf("{}x{} DPI", &[&x.round(), &y.round()]),
+                None => t("Not set").to_string(),
+            },
+        ),
+        (
+            t("Color profile"),
+            match metadata.icc_profile.as_deref() {
+                Some("") => t("Embedded, unnamed").to_string(),
+                Some(name) => name.to_string(),
+                None => t("None").to_string(),
+            },
+        ),
+        (t("File size"), format!("{} KB", metadata.file_size / 1024)),
+    ];
+
+    let mut panel = column![].spacing(2);
+    for (label, value) in fields {
+        panel = panel.push(row![
+            text(label).size(14).width(Length::Fixed(110.0)),
+            text(value).size(14),
+        ]);
+    }
+    container(panel)
+        .padding(8)
+        .style(iced::theme::Container::Box)
+        .into()
+}
+
+// "RGB, 8 bits per channel"
+fn color_name(color: image::ColorType) -> String {
+    let kind = match (color.has_color(), color.has_alpha()) {
+        (true, false) => "RGB",
+        (true, true) => "RGBA",
+        (false, false) => t("Grayscale"),
+        (false, true) => t("Grayscale with alpha"),
+    };
+    let bits = color.bits_per_pixel() / color.channel_count().max(1) as u16;
+    tf("{}, {} bits per channel", &[&kind, &bits])
+}
+
+// Largest zoom of the full-window preview, in multiples of the pixels
+const MAX_ZOOM: u32 = 16;
+
+// Each step doubles the zoom, starting from the actual size
+fn zoom_in(zoom: Option<u32>) -> Message {
+    Message::PreviewZoomed(Some(zoom.map_or(1, |zoom| zoom * 2)))
+}
+
+// Below the actual size the preview fits the window again
+fn zoom_out(zoom: Option<u32>) -> Message {
+    Message::PreviewZoomed(zoom.filter(|zoom| *zoom > 1).map(|zoom| zoom / 2))
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+// Log the whole cause chain, show only the friendly part
+fn describe(error: &ArtCoverError) -> String {
+    match error {
+        ArtCoverError::Cancelled => tracing::info!("cancelled"),
+        _ => tracing::error!("{}", error.report()),
+    }
+    error.user_message()
+}
@@ -0,0 +1,20 @@
+use artcover_image_conversor::i18n::{t, tf};
+
+// Desktop notification for a finished batch. Blocks while the notification
+// service answers, so it is sent from a command.
+pub async fn batch_finished(done: usize, failed: usize) {
+    let body = if failed == 0 {
+        tf("{} converted", &[&done])
+    } else {
+        tf("{} converted, {} failed", &[&done, &failed])
+    };
+    let result = notify_rust::Notification::new()
+        .appname("ArtCover")
+        .summary(t("Batch finished"))
+        .body(&body)
+        .show();
+    // A desktop without a notification service still has the title
+    if let Err(e) = result {
+        tracing::warn!("notification not shown: {}", e);
+    }
+}
@@ -0,0 +1,90 @@
+use artcover_image_conversor::i18n::tf;
+use artcover_image_conversor::{ArtCoverError, pipeline};
+use iced::widget::image::Handle;
+use image::{ImageFormat, Rgba, RgbaImage, imageops};
+use std::path::Path;
+
+// Size the enlarged preview aims for, the scale stays a whole number
+const TARGET_SIDE: u32 = 1200;
+
+// Larger images are shown as a thumbnail: decoding and uploading them
+// whole on every redraw takes seconds and hundreds of megabytes
+const DIRECT_MAX_SIDE: u32 = 4096;
+
+// What the preview shows for an output, or for its source
+#[derive(Debug, Clone)]
+pub enum Preview {
+    // Size in pixels of the handle, for showing it at a given zoom
+    Image {
+        handle: Handle,
+        width: u32,
+        height: u32,
+    },
+    // Nothing can draw it; the reason is shown instead of a blank area
+    Unavailable(String),
+}
+
+// The file itself when iced draws it well, otherwise a thumbnail made by
+// the conversion core, which reads more formats
+pub fn load(path: &Path) -> Preview {
+    let native = matches!(
+        ImageFormat::from_path(path),
+        Ok(ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::Gif | ImageFormat::Bmp)
+    );
+    if native
+        && let Ok((width, height)) = image::image_dimensions(path)
+        && width.max(height) <= DIRECT_MAX_SIDE
+    {
+        return Preview::Image {
+            handle: Handle::from_path(path),
+            width,
+            height,
+        };
+    }
+
+    match thumbnail(path) {
+        Ok(preview) => preview,
+        Err(e) => Preview::Unavailable(tf("No preview: {}", &[&e.user_message()])),
+    }
+}
+
+fn thumbnail(path: &Path) -> Result<Preview, ArtCoverError> {
+    let img = pipeline::open_bitmap(path)?;
+    let small = img.thumbnail(TARGET_SIDE, TARGET_SIDE).to_rgba8();
+    let (width, height) = small.dimensions();
+    Ok(Preview::Image {
+        handle: Handle::from_pixels(width, height, small.into_raw()),
+        width,
+        height,
+    })
+}
+
+const GRID_COLOR: Rgba<u8> = Rgba([64, 64, 64, 255]);
+
+// The image enlarged with square pixels and a line between them, so every
+// source pixel can be told apart
+pub fn with_pixel_grid(path: &Path) -> Option<Handle> {
+    // Pixels of larger images cannot be told apart on screen anyway
+    let (width, height) = image::image_dimensions(path).ok()?;
+    if width.max(height) > TARGET_SIDE {
+        return None;
+    }
+    let img = image::open(path).ok()?.to_rgba8();
+    let scale = (TARGET_SIDE / width.max(height).max(1)).max(2);
+
+    let mut enlarged: RgbaImage = imageops::resize(
+        &img,
+        width * scale,
+        height * scale,
+        imageops::FilterType::Nearest,
+    );
+
+    for (x, y, pixel) in enlarged.enumerate_pixels_mut() {
+        if x % scale == 0 || y % scale == 0 {
+            *pixel = GRID_COLOR;
+        }
+    }
+
+    let (width, height) = enlarged.dimensions();
+    Some(Handle::from_pixels(width, height, enlarged.into_raw()))
+}
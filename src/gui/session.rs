@@ -0,0 +1,98 @@
+use artcover_image_conversor::{ArtCoverError, CancelToken, lock, settings};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+// Work of the last run, written whenever it changes so closing the window
+// or a crash mid-batch loses nothing, and offered again on the next launch
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Saved {
+    // Not converted yet, the one being converted first
+    pub pending: Vec<PathBuf>,
+    // Converted sources and their outputs, in the order of the batch
+    pub finished: Vec<(PathBuf, Vec<PathBuf>)>,
+    // Outputs Ctrl+Z can still take back, newest last
+    pub undo: Vec<(PathBuf, Vec<PathBuf>)>,
+}
+
+// Next to the saved settings
+fn saved_path() -> Option<PathBuf> {
+    Some(settings::saved_path()?.with_file_name("session.json"))
+}
+
+// Empty on first launch, or when the file cannot be used. Files deleted
+// in the meantime are left out.
+pub fn load() -> Saved {
+    let mut saved: Saved = saved_path()
+        .and_then(|path| fs::read(path).ok())
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default();
+    saved.pending.retain(|path| path.is_file());
+    for (_, outputs) in saved.finished.iter_mut().chain(saved.undo.iter_mut()) {
+        outputs.retain(|output| output.is_file());
+    }
+    saved.undo.retain(|(_, outputs)| !outputs.is_empty());
+    saved
+}
+
+pub fn save(saved: &Saved) -> Result<(), ArtCoverError> {
+    let Some(path) = saved_path() else {
+        return Ok(());
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| ArtCoverError::io(dir, e))?;
+    }
+    let _lock = lock::acquire(&path, &CancelToken::default())?;
+    let json =
+        serde_json::to_string_pretty(saved).map_err(|e| ArtCoverError::io(&path, e.into()))?;
+    fs::write(&path, json).map_err(|e| ArtCoverError::io(&path, e))
+}
+
+// Files queued or converted during this session, by path and content, so
+// overlapping drops are not converted twice
+#[derive(Debug, Default)]
+pub struct DropGuard {
+    files: HashMap<PathBuf, u64>,
+}
+
+impl DropGuard {
+    pub fn contains_path(&self, path: &Path) -> bool {
+        self.files.contains_key(&key(path))
+    }
+
+    // Earlier file with the same content, if any
+    pub fn find_content(&self, hash: u64) -> Option<&Path> {
+        self.files
+            .iter()
+            .find(|(_, known)| **known == hash)
+            .map(|(path, _)| path.as_path())
+    }
+
+    pub fn remember(&mut self, path: &Path, hash: u64) {
+        self.files.insert(key(path), hash);
+    }
+
+    // Failed conversions may be dropped again
+    pub fn forget(&mut self, path: &Path) {
+        self.files.remove(&key(path));
+    }
+}
+
+// Only meant to compare files within one run of the app
+pub async fn content_hash(path: PathBuf) -> (PathBuf, Option<u64>) {
+    let hash = std::fs::read(&path).ok().map(|bytes| {
+        let mut hasher = DefaultHasher::new();
+        bytes.hash(&mut hasher);
+        hasher.finish()
+    });
+    (path, hash)
+}
+
+// The same file reached through another relative path or link
+fn key(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
@@ -0,0 +1,134 @@
+use iced::keyboard::key::Named;
+use iced::keyboard::{Key, Modifiers};
+use std::path::PathBuf;
+
+// What a shortcut does, handled in `update`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Open,
+    Paste,
+    Undo,
+    Reprocess,
+    Cancel,
+    Preview,
+    PixelGrid,
+    Compare,
+    ZoomIn,
+    ZoomOut,
+    ActualSize,
+    Help,
+}
+
+pub struct Binding {
+    pub keys: &'static str,
+    pub description: &'static str,
+}
+
+// Listed in the help overlay, in this order
+pub const BINDINGS: [Binding; 11] = [
+    Binding {
+        keys: "Ctrl+O",
+        description: "Open images",
+    },
+    Binding {
+        keys: "Ctrl+V",
+        description: "Convert the files or paths in the clipboard",
+    },
+    Binding {
+        keys: "Ctrl+Z",
+        description: "Delete the last output",
+    },
+    Binding {
+        keys: "Enter",
+        description: "Convert the last image again with the current settings",
+    },
+    Binding {
+        keys: "Esc",
+        description: "Close the preview or this help, or cancel the batch",
+    },
+    Binding {
+        keys: "Ctrl+P",
+        description: "Large preview",
+    },
+    Binding {
+        keys: "Ctrl+G",
+        description: "Pixel grid, in the large preview",
+    },
+    Binding {
+        keys: "Ctrl+B",
+        description: "Original or result, in the large preview",
+    },
+    Binding {
+        keys: "Ctrl+ +/-",
+        description: "Zoom in or out, in the large preview",
+    },
+    Binding {
+        keys: "Ctrl+0",
+        description: "Actual size, in the large preview",
+    },
+    Binding {
+        keys: "F1",
+        description: "Show or hide this help",
+    },
+];
+
+// Only keys no widget used reach this, so typing in a text field never
+// triggers a shortcut
+pub fn action(key: Key, modifiers: Modifiers) -> Option<Action> {
+    match key.as_ref() {
+        Key::Character(character) if modifiers.command() => match character {
+            "o" => Some(Action::Open),
+            "v" => Some(Action::Paste),
+            "z" => Some(Action::Undo),
+            "p" => Some(Action::Preview),
+            "g" => Some(Action::PixelGrid),
+            "b" => Some(Action::Compare),
+            // "=" is the unshifted "+" key on most layouts
+            "+" | "=" => Some(Action::ZoomIn),
+            "-" => Some(Action::ZoomOut),
+            "0" => Some(Action::ActualSize),
+            _ => None,
+        },
+        Key::Named(Named::Enter) => Some(Action::Reprocess),
+        Key::Named(Named::Escape) => Some(Action::Cancel),
+        Key::Named(Named::F1) => Some(Action::Help),
+        _ => None,
+    }
+}
+
+// Paths in pasted text: one per line, plain or as file:// URIs (what file
+// managers put on the clipboard when copying files)
+pub fn pasted_paths(text: &str) -> Vec<PathBuf> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| match line.strip_prefix("file://") {
+            Some(uri) => PathBuf::from(percent_decode(uri)),
+            None => PathBuf::from(line.trim_matches('"')),
+        })
+        .filter(|path| path.exists())
+        .collect()
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = (bytes[index] == b'%')
+            .then(|| text.get(index + 1..index + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
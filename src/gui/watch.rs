@@ -0,0 +1,91 @@
+use artcover_image_conversor::i18n::tf;
+use artcover_image_conversor::{is_supported_image, naming};
+use iced::Subscription;
+use iced::futures::channel::mpsc;
+use iced::futures::{SinkExt, StreamExt};
+use notify::event::{EventKind, ModifyKind};
+use notify::{RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// How long a new file must keep the same size before it is picked up
+const SETTLE_DELAY: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    NewImage(PathBuf),
+    Failed(String),
+}
+
+// Report images created in (or moved into) `folder` while subscribed
+pub fn folder(folder: PathBuf) -> Subscription<Event> {
+    iced::subscription::channel(folder.clone(), 100, move |mut output| async move {
+        let (sender, mut events) = mpsc::unbounded();
+
+        // The watcher callback runs on notify's own thread
+        let watcher = notify::recommended_watcher(move |result| {
+            let _ = sender.unbounded_send(result);
+        })
+        .and_then(|mut watcher| {
+            watcher.watch(&folder, RecursiveMode::NonRecursive)?;
+            Ok(watcher)
+        });
+
+        match watcher {
+            Ok(_watcher) => {
+                while let Some(result) = events.next().await {
+                    let event = match result {
+                        Ok(event) => event,
+                        Err(e) => {
+                            let _ = output.send(Event::Failed(e.to_string())).await;
+                            continue;
+                        }
+                    };
+
+                    if !matches!(
+                        event.kind,
+                        EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(_))
+                    ) {
+                        continue;
+                    }
+
+                    for path in event.paths {
+                        if is_new_input(&path) && wait_until_settled(&path).await {
+                            let _ = output.send(Event::NewImage(path)).await;
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                let message = tf("Folder cannot be watched: {}", &[&e]);
+                let _ = output.send(Event::Failed(message)).await;
+            }
+        }
+
+        // Stay idle until the subscription is dropped
+        std::future::pending().await
+    })
+}
+
+// Our own outputs land in the same folder and must not be converted again
+fn is_new_input(path: &Path) -> bool {
+    path.is_file() && !naming::is_output_name(path) && is_supported_image(path)
+}
+
+// Downloads are written in chunks, wait until the size stops changing
+async fn wait_until_settled(path: &Path) -> bool {
+    let mut last_size = None;
+
+    for _ in 0..20 {
+        let Ok(metadata) = std::fs::metadata(path) else {
+            return false;
+        };
+        if last_size == Some(metadata.len()) && metadata.len() > 0 {
+            return true;
+        }
+        last_size = Some(metadata.len());
+        tokio::time::sleep(SETTLE_DELAY).await;
+    }
+
+    false
+}
@@ -0,0 +1,82 @@
+use crate::i18n::t;
+use crate::{ArtCoverError, CancelToken, dedupe, lock, phash};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Kept next to the outputs it describes
+pub const INDEX_FILE: &str = ".artcover-hashes.json";
+
+// What happens to a source that looks like a cover converted before
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DuplicateCheck {
+    Off,
+    // Converted anyway, with a warning
+    #[default]
+    Flag,
+    // Not converted; the earlier output is reported instead
+    Skip,
+}
+
+impl DuplicateCheck {
+    pub const ALL: [DuplicateCheck; 3] = [
+        DuplicateCheck::Off,
+        DuplicateCheck::Flag,
+        DuplicateCheck::Skip,
+    ];
+}
+
+impl fmt::Display for DuplicateCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            DuplicateCheck::Off => "Convert duplicates",
+            DuplicateCheck::Flag => "Warn about duplicates",
+            DuplicateCheck::Skip => "Skip duplicates",
+        };
+        write!(f, "{}", t(label))
+    }
+}
+
+// One converted cover: the dHash of its source and where it went
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    pub hash: u64,
+    pub source: PathBuf,
+    pub output: PathBuf,
+}
+
+// Earlier conversion into the folder of `output` of a cover looking like
+// `hash`. Conversions of `source` itself are not duplicates.
+pub fn find_similar(output: &Path, hash: u64, source: &Path) -> Option<Entry> {
+    load(&index_path(output))
+        .into_iter()
+        .filter(|entry| entry.source != source && entry.output.exists())
+        .filter(|entry| phash::distance(entry.hash, hash) <= dedupe::DEFAULT_THRESHOLD)
+        .min_by_key(|entry| phash::distance(entry.hash, hash))
+}
+
+// Remember a conversion, replacing earlier ones of the same source
+pub fn record(entry: &Entry) -> Result<(), ArtCoverError> {
+    let path = index_path(&entry.output);
+    let _lock = lock::acquire(&path, &CancelToken::default())?;
+    let mut entries = load(&path);
+    entries.retain(|known| known.source != entry.source);
+    entries.push(entry.clone());
+
+    let json =
+        serde_json::to_string_pretty(&entries).map_err(|e| ArtCoverError::io(&path, e.into()))?;
+    fs::write(&path, json).map_err(|e| ArtCoverError::io(&path, e))
+}
+
+fn index_path(file: &Path) -> PathBuf {
+    file.with_file_name(INDEX_FILE)
+}
+
+// A missing or damaged index starts over
+fn load(path: &Path) -> Vec<Entry> {
+    fs::read(path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
@@ -0,0 +1,165 @@
+use crate::i18n::{t, tf};
+use crate::{booklet, community, embedded, ocr};
+use lofty::config::WriteOptions;
+use lofty::picture::{Picture, PictureType};
+use lofty::tag::{Tag, TagExt, TagType};
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+// Folders players are mounted under
+const MOUNT_ROOTS: [&str; 4] = ["/media", "/run/media", "/Volumes", "/mnt"];
+
+// Outcome of one startup check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    Passed(String),
+    // Works, but some inputs or outputs will not be available
+    Warning(String),
+    Failed(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: Status,
+}
+
+// Integrations in use. Checks for the ones left out do not run.
+#[derive(Debug, Clone, Default)]
+pub struct Setup {
+    pub output_folders: Vec<PathBuf>,
+    pub ocr_languages: Option<String>,
+    pub community_index: Option<String>,
+}
+
+// Quick checks meant for launch, so a broken setup shows before a long
+// batch instead of in the middle of it
+pub fn run(setup: &Setup) -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    for folder in &setup.output_folders {
+        checks.push(Check {
+            name: "Output folder",
+            status: match writable(folder) {
+                Ok(()) => Status::Passed(tf("{} is writable", &[&folder.display()])),
+                Err(e) => Status::Failed(format!("{}: {}", folder.display(), e)),
+            },
+        });
+    }
+
+    checks.push(Check {
+        name: "Tag reading",
+        status: match read_sample_tag() {
+            Ok(()) => Status::Passed(t("embedded artwork can be read").to_string()),
+            Err(message) => Status::Failed(message),
+        },
+    });
+
+    if let Some(languages) = &setup.ocr_languages {
+        checks.push(Check {
+            name: "OCR",
+            status: match ocr::check(languages) {
+                Ok(detail) => Status::Passed(detail),
+                Err(message) => Status::Failed(message),
+            },
+        });
+    }
+
+    checks.push(Check {
+        name: "PDF booklets",
+        status: match booklet::available() {
+            Ok(()) => Status::Passed(t("PDFium found").to_string()),
+            Err(e) => Status::Warning(e.user_message()),
+        },
+    });
+
+    if let Some(index) = &setup.community_index {
+        checks.push(Check {
+            name: "Preset index",
+            status: match community::fetch_index(index) {
+                Ok(index) => Status::Passed(tf("{} presets", &[&index.presets.len()])),
+                Err(e) => Status::Failed(e.to_string()),
+            },
+        });
+    }
+
+    let devices = find_devices();
+    checks.push(Check {
+        name: "Device",
+        status: if devices.is_empty() {
+            Status::Warning(t("no iPod or Rockbox player mounted").to_string())
+        } else {
+            let names: Vec<String> = devices.iter().map(|d| d.display().to_string()).collect();
+            Status::Passed(names.join(", "))
+        },
+    });
+
+    checks
+}
+
+// Mounted players, recognized by their firmware folders
+pub fn find_devices() -> Vec<PathBuf> {
+    let mut roots: Vec<PathBuf> = MOUNT_ROOTS.iter().map(PathBuf::from).collect();
+    // Linux mounts removable media per user
+    if let Ok(user) = std::env::var("USER") {
+        roots.push(Path::new("/media").join(&user));
+        roots.push(Path::new("/run/media").join(&user));
+    }
+    // Windows drive letters
+    roots.extend(('D'..='Z').map(|letter| PathBuf::from(format!("{}:\\", letter))));
+
+    let mut devices = Vec::new();
+    for root in roots {
+        // Drive letters are the volume, mount folders contain them
+        let mut volumes = vec![root.clone()];
+        if let Ok(entries) = fs::read_dir(&root) {
+            volumes.extend(entries.flatten().map(|entry| entry.path()));
+        }
+        for volume in volumes {
+            let is_player =
+                volume.join("iPod_Control").is_dir() || volume.join(".rockbox").is_dir();
+            if is_player && !devices.contains(&volume) {
+                devices.push(volume);
+            }
+        }
+    }
+    devices
+}
+
+fn writable(folder: &Path) -> std::io::Result<()> {
+    let probe = folder.join(".artcover-write-test");
+    fs::write(&probe, b"")?;
+    fs::remove_file(&probe)
+}
+
+// Round-trip a cover through an in-memory MP3 tag
+fn read_sample_tag() -> Result<(), String> {
+    let picture = vec![0x89, b'P', b'N', b'G'];
+    let mut tag = Tag::new(TagType::Id3v2);
+    tag.push_picture(
+        Picture::unchecked(picture.clone())
+            .pic_type(PictureType::CoverFront)
+            .build(),
+    );
+
+    let mut bytes = Vec::new();
+    tag.dump_to(&mut bytes, WriteOptions::default())
+        .map_err(|e| e.to_string())?;
+    // A few silent MPEG-1 Layer III frames (128 kbps, 44.1 kHz)
+    for _ in 0..3 {
+        bytes.extend([0xFF, 0xFB, 0x90, 0x00]);
+        bytes.extend([0; 413]);
+    }
+
+    let tagged = lofty::probe::Probe::new(Cursor::new(bytes))
+        .guess_file_type()
+        .map_err(|e| e.to_string())?
+        .read()
+        .map_err(|e| e.to_string())?;
+
+    match embedded::front_cover(&tagged) {
+        Some(data) if data == picture => Ok(()),
+        _ => Err(t("the test picture was not read back").to_string()),
+    }
+}
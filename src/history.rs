@@ -0,0 +1,92 @@
+use crate::i18n::{t, tf};
+use crate::quantize::ColorMode;
+use crate::{ArtCoverError, CancelToken, ProcessOptions, lock};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Kept next to the versioned outputs it describes
+pub const HISTORY_FILE: &str = ".artcover-history.json";
+
+// One versioned output and the settings that produced it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Version {
+    pub source: PathBuf,
+    pub output: PathBuf,
+    pub number: u32,
+    // Seconds since the Unix epoch
+    pub created: u64,
+    pub options: ProcessOptions,
+}
+
+impl Version {
+    pub fn new(source: &Path, output: &Path, number: u32, options: &ProcessOptions) -> Self {
+        Self {
+            source: source.to_path_buf(),
+            output: output.to_path_buf(),
+            number,
+            created: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or(0),
+            options: options.clone(),
+        }
+    }
+
+    // Short description of the settings, for the history list
+    pub fn summary(&self) -> String {
+        let options = &self.options;
+        let mut parts = vec![options.preset.name.clone(), options.resize_mode.to_string()];
+        match options.auto_quality {
+            Some(ssim) => parts.push(format!("SSIM {:.3}", ssim)),
+            None => parts.push(tf("quality {}", &[&options.jpeg_quality])),
+        }
+        if options.auto_trim {
+            parts.push(tf("trim {}", &[&options.trim_tolerance]));
+        }
+        if options.preset.lut.is_some() {
+            parts.push("LUT".to_string());
+        }
+        match options.color_mode {
+            ColorMode::Full => {}
+            ColorMode::Grayscale => parts.push(t("grayscale").to_string()),
+            ColorMode::Palette => parts.push(tf("{} colors", &[&options.palette_colors])),
+        }
+        parts.join(", ")
+    }
+}
+
+pub fn record(version: &Version) -> Result<(), ArtCoverError> {
+    let path = history_path(&version.output);
+    // Read, add and write back without another process in between
+    let _lock = lock::acquire(&path, &CancelToken::default())?;
+    let mut versions = load(&path);
+    versions.push(version.clone());
+
+    let json =
+        serde_json::to_string_pretty(&versions).map_err(|e| ArtCoverError::io(&path, e.into()))?;
+    fs::write(&path, json).map_err(|e| ArtCoverError::io(&path, e))
+}
+
+// Versions of `source` whose output still exists, oldest first
+pub fn versions_of(source: &Path) -> Vec<Version> {
+    let mut versions: Vec<Version> = load(&history_path(source))
+        .into_iter()
+        .filter(|version| version.source == source && version.output.exists())
+        .collect();
+    versions.sort_by_key(|version| version.number);
+    versions
+}
+
+fn history_path(file: &Path) -> PathBuf {
+    file.with_file_name(HISTORY_FILE)
+}
+
+// A missing or damaged history starts over
+fn load(path: &Path) -> Vec<Version> {
+    fs::read(path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
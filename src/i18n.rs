@@ -0,0 +1,652 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+// UI language. System follows the locale of the OS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    System,
+    English,
+    Spanish,
+}
+
+impl Language {
+    pub const ALL: [Language; 3] = [Language::System, Language::English, Language::Spanish];
+
+    // Language of a locale such as "es-ES" or "es_MX.UTF-8"; English for
+    // the ones without translations
+    pub fn from_locale(locale: &str) -> Language {
+        match locale.get(..2).map(str::to_ascii_lowercase).as_deref() {
+            Some("es") => Language::Spanish,
+            _ => Language::English,
+        }
+    }
+
+    fn resolve(self) -> Language {
+        match self {
+            Language::System => sys_locale::get_locale()
+                .map_or(Language::English, |locale| Language::from_locale(&locale)),
+            language => language,
+        }
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Each language is listed by its own name
+        let label = match self {
+            Language::System => t("System language"),
+            Language::English => "English",
+            Language::Spanish => "Español",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+// Resolved language in use, as Language discriminant
+static CURRENT: AtomicU8 = AtomicU8::new(UNSET);
+const UNSET: u8 = u8::MAX;
+
+pub fn set_language(language: Language) {
+    CURRENT.store(language.resolve() as u8, Ordering::Relaxed);
+}
+
+// The system language until set_language is called
+pub fn current() -> Language {
+    match CURRENT.load(Ordering::Relaxed) {
+        UNSET => {
+            set_language(Language::System);
+            current()
+        }
+        value if value == Language::Spanish as u8 => Language::Spanish,
+        _ => Language::English,
+    }
+}
+
+// `text` in the current language. The English text is the key, so text
+// without a translation shows as it is.
+pub fn t(text: &'static str) -> &'static str {
+    let table = match current() {
+        Language::Spanish => SPANISH,
+        _ => return text,
+    };
+    table
+        .iter()
+        .find(|(english, _)| *english == text)
+        .map_or(text, |(_, translated)| translated)
+}
+
+// Same as t, filling each "{}" with the next of `args`. Translations keep
+// the placeholders in the English order.
+pub fn tf(text: &'static str, args: &[&dyn fmt::Display]) -> String {
+    let mut args = args.iter();
+    let mut parts = t(text).split("{}");
+    let mut filled = parts.next().unwrap_or_default().to_string();
+    for part in parts {
+        if let Some(arg) = args.next() {
+            filled.push_str(&arg.to_string());
+        }
+        filled.push_str(part);
+    }
+    filled
+}
+
+const SPANISH: &[(&str, &str)] = &[
+    ("ASCII file names", "Nombres de archivo ASCII"),
+    (
+        "Actual size, in the large preview",
+        "Tamaño real, en la vista previa grande",
+    ),
+    (
+        "Already fits, kept as it is",
+        "Ya cumple, se dejó como estaba",
+    ),
+    ("Always re-encode", "Recodificar siempre"),
+    (
+        "Animation has {} frames, the last one was used",
+        "La animación tiene {} fotogramas, se usó el último",
+    ),
+    (
+        "Arithmetic-coded JPEG re-encoded with Huffman coding",
+        "JPEG con codificación aritmética recodificado con Huffman",
+    ),
+    (
+        "Art folder (or drop it here)",
+        "Carpeta de carátulas (o suéltala aquí)",
+    ),
+    (
+        "Artwork folder on the player (empty: album folders)",
+        "Carpeta de carátulas en el reproductor (vacío: carpetas de los álbumes)",
+    ),
+    ("Audio files", "Archivos de audio"),
+    ("Apply crop", "Aplicar recorte"),
+    ("Auto", "Automática"),
+    ("Automatic crop", "Recorte automático"),
+    ("Auto-trim borders", "Recortar bordes automáticamente"),
+    ("Batch finished", "Lote terminado"),
+    (
+        "Booklet has {} pages, the last one was used",
+        "El libreto tiene {} páginas, se usó la última",
+    ),
+    ("CMYK JPEG converted to RGB", "JPEG CMYK convertido a RGB"),
+    ("Cancel", "Cancelar"),
+    ("Cancelled", "Cancelado"),
+    ("Cancelled after {} covers", "Cancelado tras {} carátulas"),
+    ("Check again", "Comprobar de nuevo"),
+    (
+        "Check and fix the covers every night at {}:00",
+        "Revisar y arreglar las carátulas cada noche a las {}:00",
+    ),
+    ("Checking covers...", "Comprobando carátulas..."),
+    ("Choose a color LUT", "Elegir una LUT de color"),
+    ("Choose...", "Elegir..."),
+    ("Clear", "Quitar"),
+    ("Close", "Cerrar"),
+    (
+        "Close the preview or this help, or cancel the batch",
+        "Cerrar la vista previa o esta ayuda, o cancelar el lote",
+    ),
+    ("Color", "Color"),
+    ("Color LUT", "LUT de color"),
+    ("Color LUTs", "LUT de color"),
+    ("Color profile", "Perfil de color"),
+    ("Colors: {}", "Colores: {}"),
+    (
+        "Comparing with the player...",
+        "Comparando con el reproductor...",
+    ),
+    ("Confirm", "Confirmar"),
+    ("Convert", "Convertir"),
+    ("Convert duplicates", "Convertir duplicados"),
+    (
+        "Convert the files or paths in the clipboard",
+        "Convertir los archivos o rutas del portapapeles",
+    ),
+    (
+        "Convert the last image again with the current settings",
+        "Volver a convertir la última imagen con los ajustes actuales",
+    ),
+    ("Converting...", "Convirtiendo..."),
+    (
+        "Copy conforming files",
+        "Copiar los archivos que ya cumplen",
+    ),
+    ("Cover text: {}", "Texto de la carátula: {}"),
+    (
+        "CoverArt Converter for iPod",
+        "Conversor de carátulas para iPod",
+    ),
+    ("Copy diagnostics", "Copiar diagnóstico"),
+    ("Copy to player", "Copiar al reproductor"),
+    ("Copying...", "Copiando..."),
+    ("Crop...", "Recortar..."),
+    ("Dark", "Oscuro"),
+    ("Deduplicate", "Duplicados"),
+    ("Delete the last output", "Borrar la última salida"),
+    (
+        "Delete {} files, keeping the selected ones?",
+        "¿Borrar {} archivos y conservar los seleccionados?",
+    ),
+    ("Device", "Dispositivo"),
+    (
+        "Diagnostics copied to the clipboard",
+        "Diagnóstico copiado al portapapeles",
+    ),
+    ("Dimensions", "Dimensiones"),
+    ("Discard", "Descartar"),
+    ("Dismiss", "Descartar"),
+    (
+        "Duplicate index not saved: {}",
+        "Índice de duplicados no guardado: {}",
+    ),
+    (
+        "Duplicate of an earlier cover, skipped",
+        "Duplicado de una carátula anterior, omitido",
+    ),
+    ("Downloading {}...", "Descargando {}..."),
+    ("Drag an image here", "Arrastra una imagen aquí"),
+    (
+        "Drag the square over the part of the cover to keep",
+        "Arrastra el cuadrado sobre la parte de la carátula que quieres conservar",
+    ),
+    ("Embedded, unnamed", "Incrustado, sin nombre"),
+    (
+        "Error: only images, audio files, color LUTs and Rockbox themes are supported",
+        "Error: solo se admiten imágenes, archivos de audio, LUT de color y temas de Rockbox",
+    ),
+    (
+        "Error: the clipboard holds no file paths",
+        "Error: el portapapeles no contiene rutas de archivo",
+    ),
+    ("Error: {}", "Error: {}"),
+    (
+        "Esc closes, Ctrl+G toggles the grid",
+        "Esc cierra, Ctrl+G muestra u oculta la cuadrícula",
+    ),
+    (
+        "Every album has a large enough cover",
+        "Todos los álbumes tienen una carátula suficientemente grande",
+    ),
+    ("Export profile", "Exportar perfil"),
+    ("Export profile...", "Exportar perfil..."),
+    ("Export settings", "Exportar ajustes"),
+    ("Export settings...", "Exportar ajustes..."),
+    (
+        "Export sizes (none: use the preset above)",
+        "Tamaños de exportación (ninguno: usar el preajuste de arriba)",
+    ),
+    ("Failed", "Fallo"),
+    ("File size", "Tamaño del archivo"),
+    (
+        "Folder cannot be watched: {}",
+        "No se puede vigilar la carpeta: {}",
+    ),
+    ("Find missing covers", "Buscar carátulas que faltan"),
+    ("Find players", "Buscar reproductores"),
+    ("Fit", "Ajustar"),
+    ("Fix {} albums", "Arreglar {} álbumes"),
+    ("Folder to watch", "Carpeta a vigilar"),
+    ("Format", "Formato"),
+    ("Frame", "Fotograma"),
+    ("Full color", "Color completo"),
+    ("Grayscale", "Escala de grises"),
+    ("Grayscale with alpha", "Escala de grises con alfa"),
+    ("Group {}", "Grupo {}"),
+    ("Hide log", "Ocultar registro"),
+    ("Hide pixel grid", "Ocultar cuadrícula de píxeles"),
+    ("Image processed and saved", "Imagen procesada y guardada"),
+    (
+        "Image saved at JPEG quality {}",
+        "Imagen guardada con calidad JPEG {}",
+    ),
+    ("Images", "Imágenes"),
+    ("Import profile", "Importar perfil"),
+    ("Import profile...", "Importar perfil..."),
+    ("Import settings", "Importar ajustes"),
+    ("Import settings...", "Importar ajustes..."),
+    ("Imported {}", "Importado {}"),
+    ("Install", "Instalar"),
+    ("Installed {}", "Instalado {}"),
+    (
+        "Interlaced PNG saved non-interlaced",
+        "PNG entrelazado guardado sin entrelazar",
+    ),
+    ("JPEG quality: {}", "Calidad JPEG: {}"),
+    (
+        "Keep selected, delete rest",
+        "Conservar la elegida, borrar el resto",
+    ),
+    (
+        "Keep selected, link rest",
+        "Conservar la elegida, enlazar el resto",
+    ),
+    ("Keep small images", "Mantener imágenes pequeñas"),
+    ("Keep versions", "Conservar versiones"),
+    ("Keyboard shortcuts", "Atajos de teclado"),
+    ("Language", "Idioma"),
+    ("Large preview", "Vista previa grande"),
+    ("Large preview (Ctrl+P)", "Vista previa grande (Ctrl+P)"),
+    ("Library", "Biblioteca"),
+    ("Light", "Claro"),
+    ("Limited palette", "Paleta limitada"),
+    (
+        "Link conforming files",
+        "Enlazar los archivos que ya cumplen",
+    ),
+    ("Load", "Cargar"),
+    ("Log files: {}", "Archivos de registro: {}"),
+    ("Loading index...", "Cargando índice..."),
+    (
+        "Looks like {}, converted before into {}",
+        "Se parece a {}, convertida antes en {}",
+    ),
+    (
+        "Music folder (or drop it here)",
+        "Carpeta de música (o suéltala aquí)",
+    ),
+    (
+        "New images are converted with the current settings",
+        "Las imágenes nuevas se convierten con los ajustes actuales",
+    ),
+    (
+        "No album folders found",
+        "No se encontraron carpetas de álbumes",
+    ),
+    (
+        "Look up the Cover Art Archive",
+        "Consultar el Cover Art Archive",
+    ),
+    ("Looking for covers...", "Buscando carátulas..."),
+    ("No duplicates found", "No se encontraron duplicados"),
+    (
+        "No iPod or Rockbox player mounted",
+        "No hay ningún iPod ni reproductor Rockbox montado",
+    ),
+    ("No preview: {}", "Sin vista previa: {}"),
+    ("None", "Ninguna"),
+    ("Not set", "Sin indicar"),
+    (
+        "Note the source in the metadata",
+        "Anotar el origen en los metadatos",
+    ),
+    ("Nothing to undo", "Nada que deshacer"),
+    ("OCR skipped: {}", "OCR omitido: {}"),
+    ("OK", "Correcto"),
+    ("Open images", "Abrir imágenes"),
+    ("Open...", "Abrir..."),
+    ("Optimize PNG", "Optimizar PNG"),
+    (
+        "Original or result, in the large preview",
+        "Original o resultado, en la vista previa grande",
+    ),
+    ("Output folder", "Carpeta de salida"),
+    ("PDF booklets", "Libretos PDF"),
+    (
+        "PDF booklets need the PDFium library (pdfium) next to the program",
+        "Los libretos PDF necesitan la biblioteca PDFium (pdfium) junto al programa",
+    ),
+    ("PDFium found", "PDFium encontrado"),
+    ("Pad to square", "Rellenar hasta cuadrado"),
+    ("Pad with blurred copy", "Rellenar con copia difuminada"),
+    ("Pad with edge color", "Rellenar con el color del borde"),
+    ("Page", "Página"),
+    ("Permission denied for {}", "Permiso denegado para {}"),
+    (
+        "Pixel grid, in the large preview",
+        "Cuadrícula de píxeles, en la vista previa grande",
+    ),
+    ("Player", "Reproductor"),
+    ("Preset", "Preajuste"),
+    ("Preset index", "Índice de preajustes"),
+    (
+        "Preset index URL or file",
+        "URL o archivo del índice de preajustes",
+    ),
+    ("Presets", "Preajustes"),
+    (
+        "Presets are checked against the SHA-256 of the index before import",
+        "Los preajustes se comprueban con el SHA-256 del índice antes de importarlos",
+    ),
+    ("Preview copy", "Previsualizar copia"),
+    ("Process anyway", "Procesar de todos modos"),
+    ("Profile", "Perfil"),
+    ("Profile name", "Nombre del perfil"),
+    ("Profile saved to {}", "Perfil guardado en {}"),
+    ("Profile {} imported", "Perfil {} importado"),
+    ("Profile {} saved", "Perfil {} guardado"),
+    (
+        "Progressive JPEG saved as baseline",
+        "JPEG progresivo guardado como básico",
+    ),
+    ("Provenance not written: {}", "Origen no anotado: {}"),
+    ("Processing...", "Procesando..."),
+    (
+        "Read cover text (OCR)",
+        "Leer el texto de la carátula (OCR)",
+    ),
+    ("Reinstall", "Reinstalar"),
+    (
+        "Removed {} output(s) of {}",
+        "Se borraron {} salida(s) de {}",
+    ),
+    (
+        "Replace the cover files already in the album",
+        "Reemplazar los archivos de carátula que ya hay en el álbum",
+    ),
+    (
+        "Replace the existing {} in {} albums",
+        "Reemplazar el {} que ya hay en {} álbumes",
+    ),
+    (
+        "Replace {} files with links to the selected ones?",
+        "¿Reemplazar {} archivos por enlaces a los seleccionados?",
+    ),
+    ("Reproducible output", "Salida reproducible"),
+    ("Resize mode", "Modo de redimensionado"),
+    ("Resolution", "Resolución"),
+    ("Resume", "Reanudar"),
+    ("Retries: {}", "Reintentos: {}"),
+    ("Retry", "Reintentar"),
+    ("Retry failed", "Reintentar los fallidos"),
+    ("Rewrite progressive JPEGs", "Reescribir JPEG progresivos"),
+    ("Rockbox themes", "Temas de Rockbox"),
+    (
+        "Same cover as {}, {} was kept",
+        "Misma carátula que {}, se conservó {}",
+    ),
+    ("Save profile", "Guardar perfil"),
+    ("Scan", "Explorar"),
+    ("Scanning...", "Explorando..."),
+    ("Settings", "Ajustes"),
+    ("Settings imported", "Ajustes importados"),
+    (
+        "Settings of version {} restored",
+        "Ajustes de la versión {} restaurados",
+    ),
+    ("Settings saved to {}", "Ajustes guardados en {}"),
+    ("Shortcuts (F1)", "Atajos (F1)"),
+    ("Show", "Mostrar"),
+    ("Show log", "Mostrar registro"),
+    ("Show or hide this help", "Mostrar u ocultar esta ayuda"),
+    ("Show original", "Mostrar original"),
+    ("Show pixel grid", "Mostrar cuadrícula de píxeles"),
+    ("Show result", "Mostrar resultado"),
+    ("Skip duplicates", "Omitir duplicados"),
+    ("Square crop", "Recorte cuadrado"),
+    ("Start", "Iniciar"),
+    ("Startup check passed", "Comprobación inicial correcta"),
+    (
+        "Startup check: 1 problem",
+        "Comprobación inicial: 1 problema",
+    ),
+    (
+        "Startup check: {} problems",
+        "Comprobación inicial: {} problemas",
+    ),
+    ("Status", "Estado"),
+    ("Stop", "Detener"),
+    ("Stopped watching", "Vigilancia detenida"),
+    ("Stretch", "Estirar"),
+    ("System language", "Idioma del sistema"),
+    ("System theme", "Tema del sistema"),
+    ("Tag reading", "Lectura de etiquetas"),
+    ("Target SSIM: {}", "SSIM objetivo: {}"),
+    (
+        "The color LUT is invalid: {}",
+        "La LUT de color no es válida: {}",
+    ),
+    ("The download failed", "La descarga falló"),
+    (
+        "The image could not be resized",
+        "No se pudo redimensionar la imagen",
+    ),
+    (
+        "The tags of {} could not be read",
+        "No se pudieron leer las etiquetas de {}",
+    ),
+    (
+        "Theme does not display album art",
+        "El tema no muestra carátulas",
+    ),
+    ("Theme has no WPS file", "El tema no tiene archivo WPS"),
+    (
+        "This image format is not supported",
+        "Este formato de imagen no es compatible",
+    ),
+    (
+        "This is not a valid settings file",
+        "No es un archivo de ajustes válido",
+    ),
+    ("Tolerance: {}", "Tolerancia: {}"),
+    (
+        "Type a name for the profile",
+        "Escribe un nombre para el perfil",
+    ),
+    ("UPSCALED", "AMPLIADA"),
+    ("Unknown", "Desconocido"),
+    ("Upscale (2x pixels)", "Ampliar (píxeles 2x)"),
+    ("Upscale (smooth)", "Ampliar (suave)"),
+    (
+        "Upscaled from {}x{}, details may look soft",
+        "Ampliada desde {}x{}, los detalles pueden verse suaves",
+    ),
+    ("Use settings", "Usar ajustes"),
+    ("Use {}", "Usar {}"),
+    (
+        "Version history not saved: {}",
+        "Historial de versiones no guardado: {}",
+    ),
+    ("Versions", "Versiones"),
+    (
+        "WPS file not found: {}",
+        "No se encontró el archivo WPS: {}",
+    ),
+    ("Warn about duplicates", "Avisar de duplicados"),
+    ("Warning", "Aviso"),
+    ("Warning: {}", "Aviso: {}"),
+    ("Watch", "Vigilar"),
+    ("Watching {}", "Vigilando {}"),
+    ("Write {} into every album", "Escribir {} en cada álbum"),
+    (
+        "Zoom in or out, in the large preview",
+        "Acercar o alejar, en la vista previa grande",
+    ),
+    ("already dropped this session", "ya se soltó en esta sesión"),
+    (
+        "embedded artwork can be read",
+        "la carátula incrustada se puede leer",
+    ),
+    ("cover only {}x{}", "carátula de solo {}x{}"),
+    ("folder does not exist", "la carpeta no existe"),
+    ("frame", "fotograma"),
+    ("grayscale", "escala de grises"),
+    ("languages {}", "idiomas {}"),
+    ("missing language data: {}", "faltan datos de idioma: {}"),
+    (
+        "no iPod or Rockbox player mounted",
+        "no hay ningún iPod ni reproductor Rockbox montado",
+    ),
+    ("new", "nueva"),
+    ("no matching album for {}", "ningún álbum coincide con {}"),
+    ("no {}", "sin {}"),
+    ("output removed", "salida borrada"),
+    ("page", "página"),
+    ("processing...", "procesando..."),
+    ("quality {}", "calidad {}"),
+    ("queued", "en cola"),
+    ("replace", "reemplazar"),
+    ("same content as {}", "mismo contenido que {}"),
+    (
+        "tesseract could not be started: {}",
+        "no se pudo iniciar tesseract: {}",
+    ),
+    ("tesseract failed: {}", "tesseract falló: {}"),
+    (
+        "the test picture was not read back",
+        "la imagen de prueba no se pudo volver a leer",
+    ),
+    ("trim {}", "recorte {}"),
+    ("unchanged", "sin cambios"),
+    ("{} (moving)", "{} (moviendo)"),
+    (
+        "{} - CoverArt Converter for iPod",
+        "{} - Conversor de carátulas para iPod",
+    ),
+    ("{} -> {} ({} KB smaller)", "{} -> {} ({} KB menos)"),
+    ("{} DPI", "{} PPP"),
+    ("{} albums need a cover", "{} álbumes necesitan carátula"),
+    ("{} albums, {} with artwork", "{} álbumes, {} con carátula"),
+    ("{} already exists", "{} ya existe"),
+    ("{} colors", "{} colores"),
+    ("{} converted", "{} convertidos"),
+    ("{} converted, {} failed", "{} convertidos, {} fallidos"),
+    ("{} could not be accessed", "No se pudo acceder a {}"),
+    (
+        "{} could not be written in this format",
+        "No se pudo escribir {} en este formato",
+    ),
+    (
+        "{} covers copied to the player",
+        "{} carátulas copiadas al reproductor",
+    ),
+    (
+        "{} covers copied, {} failed: {}",
+        "{} carátulas copiadas, {} fallidas: {}",
+    ),
+    (
+        "{} covers written, {} failed",
+        "{} carátulas escritas, {} fallaron",
+    ),
+    (
+        "{} does not match its checksum and was not imported",
+        "{} no coincide con su suma de comprobación y no se importó",
+    ),
+    (
+        "{} covers found, {} albums without one, {} failed",
+        "{} carátulas encontradas, {} álbumes sin ninguna, {} fallos",
+    ),
+    ("{} duplicates cleaned up", "{} duplicados eliminados"),
+    (
+        "{} duplicates cleaned up, {} left in place",
+        "{} duplicados eliminados, {} se quedan como estaban",
+    ),
+    ("{} failed: {}", "{} falló: {}"),
+    ("{} files failed", "{} archivos fallidos"),
+    ("{} files skipped", "{} archivos omitidos"),
+    (
+        "{} groups of similar covers",
+        "{} grupos de carátulas parecidas",
+    ),
+    (
+        "{} has no embedded artwork",
+        "{} no tiene carátula incrustada",
+    ),
+    ("{} is already in use", "{} ya está en uso"),
+    (
+        "{} is being written by another process, try again later",
+        "Otro proceso está escribiendo {}, inténtalo más tarde",
+    ),
+    (
+        "{} is damaged or not an image",
+        "{} está dañado o no es una imagen",
+    ),
+    (
+        "{} is too large to convert ({}x{} pixels)",
+        "{} es demasiado grande para convertirla ({}x{} píxeles)",
+    ),
+    ("{} is damaged", "{} está dañado"),
+    ("{} is writable", "{} admite escritura"),
+    (
+        "{} new, {} replaced, {} unchanged, {} without a matching album",
+        "{} nuevas, {} reemplazadas, {} sin cambios, {} sin álbum coincidente",
+    ),
+    (
+        "{} now uses the color LUT {}",
+        "{} usa ahora la LUT de color {}",
+    ),
+    ("{} of {} files saved", "{} de {} archivos guardados"),
+    ("{} presets", "{} preajustes"),
+    ("{} presets available", "{} preajustes disponibles"),
+    (
+        "{} unfinished conversion(s) from the last session",
+        "{} conversión(es) sin terminar de la última sesión",
+    ),
+    ("{} was not found", "No se encontró {}"),
+    ("{} {} of {}", "{} {} de {}"),
+    ("{} – replaces its {}", "{} – reemplaza su {}"),
+    ("{}, {} bits per channel", "{}, {} bits por canal"),
+    ("{}, PNG optimized by {} KB", "{}, PNG optimizado en {} KB"),
+    ("{}x{} DPI", "{}x{} PPP"),
+    ("{}x{} pixels from {},{}", "{}x{} píxeles desde {},{}"),
+    ("– keeps its {}", "– conserva su {}"),
+    ("– no artwork found", "– no se encontró carátula"),
+    ("– no cover found", "– no se encontró carátula"),
+    (
+        "✓ from the Cover Art Archive",
+        "✓ desde el Cover Art Archive",
+    ),
+    ("✓ from the tags of {}", "✓ desde las etiquetas de {}"),
+    ("✓ from {}", "✓ desde {}"),
+    ("✓ {} from {}", "✓ {} desde {}"),
+];
@@ -0,0 +1,179 @@
+use crate::library::{self, Album};
+use crate::{ArtCoverError, naming};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Where the covers go on the device
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Destination {
+    // The device folder holding the same album, as cover.jpg: players
+    // filled by copying folders (Rockbox)
+    Albums,
+    // One folder of "Artist - Album.jpg" files
+    Folder(PathBuf),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    New,
+    Replace,
+    // The device already has the same file
+    Unchanged,
+}
+
+// One cover of the library and where it would be written
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoverCopy {
+    pub cover: PathBuf,
+    pub destination: PathBuf,
+    pub action: Action,
+}
+
+// What a copy would write, computed without touching the device
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Plan {
+    pub copies: Vec<CoverCopy>,
+    // Library albums with a cover but no matching folder on the device
+    pub unmatched: Vec<PathBuf>,
+}
+
+impl Plan {
+    pub fn count(&self, action: Action) -> usize {
+        self.copies
+            .iter()
+            .filter(|copy| copy.action == action)
+            .count()
+    }
+}
+
+// Copies of the cover.jpg of every album of `root` onto the player mounted
+// at `device`. Albums not converted yet are left out.
+pub fn plan(
+    root: &Path,
+    albums: &[Album],
+    device: &Path,
+    destination: &Destination,
+) -> Result<Plan, ArtCoverError> {
+    let mut device_albums = Vec::new();
+    if *destination == Destination::Albums {
+        album_folders(device, &mut device_albums).map_err(|e| ArtCoverError::io(device, e))?;
+    }
+
+    let mut plan = Plan::default();
+    for album in albums {
+        let cover = album.destination();
+        if !cover.is_file() {
+            continue;
+        }
+        let names = names(album.folder.strip_prefix(root).unwrap_or(&album.folder));
+
+        let target = match destination {
+            Destination::Albums => matching_folder(&names, &device_albums)
+                .map(|folder| folder.join(library::COVER_FILE_NAME)),
+            Destination::Folder(folder) if !names.is_empty() => {
+                let name = naming::sanitize(&names.join(" - "), false);
+                Some(folder.join(format!("{}.jpg", name)))
+            }
+            Destination::Folder(_) => None,
+        };
+        let Some(target) = target else {
+            plan.unmatched.push(album.folder.clone());
+            continue;
+        };
+
+        let action = if !target.exists() {
+            Action::New
+        } else if same_content(&cover, &target) {
+            Action::Unchanged
+        } else {
+            Action::Replace
+        };
+        plan.copies.push(CoverCopy {
+            cover,
+            destination: target,
+            action,
+        });
+    }
+    Ok(plan)
+}
+
+// Carry out one copy of a plan; unchanged files are not written again
+pub fn write(copy: &CoverCopy) -> Result<(), ArtCoverError> {
+    if copy.action == Action::Unchanged {
+        return Ok(());
+    }
+    if let Some(folder) = copy.destination.parent() {
+        fs::create_dir_all(folder).map_err(|e| ArtCoverError::io(folder, e))?;
+    }
+    fs::copy(&copy.cover, &copy.destination)
+        .map(|_| ())
+        .map_err(|e| ArtCoverError::io(&copy.destination, e))
+}
+
+// The last two folder names (artist, album) of a relative album path
+fn names(relative: &Path) -> Vec<String> {
+    let names: Vec<String> = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    names[names.len().saturating_sub(2)..].to_vec()
+}
+
+// Device folder with the same artist and album, ignoring case, or else the
+// only one with the same album name
+fn matching_folder(names: &[String], device_albums: &[PathBuf]) -> Option<PathBuf> {
+    let equal = |a: &[String], b: &[String]| {
+        a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.eq_ignore_ascii_case(b))
+    };
+    let album = names.last()?;
+
+    let candidates: Vec<&PathBuf> = device_albums
+        .iter()
+        .filter(|folder| {
+            folder
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().eq_ignore_ascii_case(album))
+        })
+        .collect();
+    let exact = candidates.iter().find(|folder| {
+        let count = folder.components().count();
+        let device_names: Vec<String> = folder
+            .components()
+            .skip(count.saturating_sub(names.len()))
+            .map(|component| component.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        equal(&device_names, names)
+    });
+
+    match (exact, candidates.as_slice()) {
+        (Some(folder), _) => Some(folder.to_path_buf()),
+        (None, [only]) => Some(only.to_path_buf()),
+        _ => None,
+    }
+}
+
+// Folders holding audio files. Hidden folders (.rockbox) are skipped;
+// the iPod's own iPod_Control/Music folders have names no album matches.
+fn album_folders(dir: &Path, folders: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    let mut has_audio = false;
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if path.is_dir() && !hidden {
+            album_folders(&path, folders)?;
+        } else if library::is_audio(&path) {
+            has_audio = true;
+        }
+    }
+    if has_audio {
+        folders.push(dir.to_path_buf());
+    }
+    Ok(())
+}
+
+fn same_content(a: &Path, b: &Path) -> bool {
+    let size = |path: &Path| fs::metadata(path).map(|m| m.len()).ok();
+    size(a) == size(b) && matches!((fs::read(a), fs::read(b)), (Ok(a), Ok(b)) if a == b)
+}
@@ -0,0 +1,175 @@
+// Conversion core: everything needed to turn a cover into device-ready art,
+// without any GUI types so other tools can reuse it. The GUI and its
+// dependencies sit behind the default `gui` feature.
+
+use std::path::{Path, PathBuf};
+
+pub mod audit;
+pub mod booklet;
+pub mod community;
+pub mod conform;
+pub mod coverart;
+pub mod daemon;
+pub mod dedupe;
+pub mod embedded;
+pub mod encode;
+pub mod error;
+pub mod export;
+pub mod hash_index;
+pub mod health;
+pub mod history;
+pub mod i18n;
+pub mod ipod;
+pub mod library;
+pub mod lock;
+pub mod lut;
+pub mod naming;
+pub mod ocr;
+pub mod overrides;
+pub mod phash;
+pub mod pipeline;
+pub mod preset;
+pub mod probe;
+pub mod provenance;
+pub mod quality;
+pub mod quantize;
+pub mod resize;
+pub mod retry;
+pub mod rockbox;
+pub mod schedule;
+pub mod settings;
+pub mod svg;
+pub mod trim;
+
+use conform::Passthrough;
+pub use error::ArtCoverError;
+use hash_index::DuplicateCheck;
+pub use pipeline::{
+    CancelToken, EncodedImage, Stage, convert_bytes, convert_to, process_image, process_image_with,
+};
+use preset::Preset;
+use quantize::ColorMode;
+use resize::{ResizeMode, Square, UpscaleMode};
+use serde::{Deserialize, Serialize};
+
+// Input formats accepted by the pipeline
+pub const IMAGE_EXTENSIONS: [&str; 10] = [
+    "png", "jpg", "jpeg", "bmp", "webp", "gif", "tif", "tiff", "svg", "pdf",
+];
+
+// Options applied to every processed image. Missing fields of saved
+// settings fall back to the defaults.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProcessOptions {
+    pub preset: Preset,
+    pub auto_trim: bool,
+    pub trim_tolerance: u8,
+    pub resize_mode: ResizeMode,
+    pub upscale: UpscaleMode,
+    pub ocr: bool,
+    pub ocr_languages: String,
+    pub jpeg_quality: u8,
+    // Search the lowest JPEG quality reaching this SSIM instead of using
+    // jpeg_quality
+    pub auto_quality: Option<f32>,
+    // Byte-identical outputs for identical inputs and settings: pinned
+    // encoder parameters and nothing taken from the environment (OCR)
+    pub deterministic: bool,
+    // Progressive JPEG sources are never kept as they are. Encoded output
+    // is always baseline.
+    pub baseline_jpeg: bool,
+    // Sources that already conform are copied or linked, not re-encoded
+    pub passthrough: Passthrough,
+    // Lossless oxipng pass over PNG outputs
+    pub optimize_png: bool,
+    // Frame of animated inputs (GIF), or page of PDF booklets, used as the
+    // still cover
+    pub frame: usize,
+    // Every run writes a new "_vN" file next to the source instead of
+    // replacing the previous output
+    pub versioned: bool,
+    // Sources looking like a cover converted before into the same folder
+    pub duplicates: DuplicateCheck,
+    // Source name and hash, app version and preset written into the
+    // metadata of JPEG and PNG outputs
+    pub provenance: bool,
+    // Grayscale or a reduced palette, over the color depth of the preset
+    pub color_mode: ColorMode,
+    // Size of the palette of ColorMode::Palette, 2 to 256
+    pub palette_colors: u16,
+    // Attempts after the first when reading or writing fails for a passing
+    // reason
+    pub retries: u32,
+    // Part of the source chosen in the crop editor, in its pixels. It
+    // belongs to one file, so it is never saved.
+    #[serde(skip)]
+    pub crop: Option<Square>,
+}
+
+impl Default for ProcessOptions {
+    fn default() -> Self {
+        Self {
+            preset: Preset::default(),
+            auto_trim: false,
+            trim_tolerance: 24,
+            resize_mode: ResizeMode::default(),
+            upscale: UpscaleMode::default(),
+            ocr: false,
+            ocr_languages: ocr::DEFAULT_LANGUAGES.to_string(),
+            jpeg_quality: encode::DEFAULT_JPEG_QUALITY,
+            auto_quality: None,
+            deterministic: false,
+            baseline_jpeg: true,
+            passthrough: Passthrough::default(),
+            optimize_png: false,
+            frame: 0,
+            versioned: false,
+            duplicates: DuplicateCheck::default(),
+            provenance: false,
+            color_mode: ColorMode::default(),
+            palette_colors: quantize::DEFAULT_PALETTE_COLORS,
+            retries: retry::DEFAULT_RETRIES,
+            crop: None,
+        }
+    }
+}
+
+// Result of a successful conversion
+#[derive(Debug, Clone)]
+pub struct ProcessedImage {
+    pub path: PathBuf,
+    pub text_hint: Option<String>,
+    pub warnings: Vec<String>,
+    // Source was smaller than the preset and had to be enlarged
+    pub upscaled: bool,
+    // Quality chosen by the automatic search, for JPEG outputs
+    pub auto_quality: Option<u8>,
+    // The source already conformed and was copied as it is
+    pub passed_through: bool,
+    // Bytes removed by the PNG optimization, when it ran
+    pub png_saved: Option<u64>,
+    // Number of frames of an animated source, or pages of a booklet
+    pub frame_count: Option<usize>,
+    // Version number of a versioned output
+    pub version: Option<u32>,
+    // Source converted earlier into `path` when this one was skipped as its
+    // duplicate; nothing was written
+    pub duplicate_of: Option<PathBuf>,
+}
+
+pub fn is_supported_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|s| s.to_str())
+        .map(|extension| IMAGE_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+// Same file once links are resolved; paths that do not exist yet compare
+// as written
+pub fn is_same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
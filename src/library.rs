@@ -0,0 +1,163 @@
+use crate::pipeline::{self, CancelToken};
+use crate::preset::OutputFormat;
+use crate::{ArtCoverError, ProcessOptions, ProcessedImage};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Files that make a folder an album
+pub const AUDIO_EXTENSIONS: [&str; 9] = [
+    "mp3", "flac", "m4a", "mp4", "aac", "ogg", "opus", "wma", "wav",
+];
+
+// Written back into every album folder
+pub const COVER_FILE_NAME: &str = "cover.jpg";
+
+// Names players and rippers use for the front cover, best first
+const PREFERRED_STEMS: [&str; 3] = ["cover", "folder", "front"];
+
+// Subfolders of an album that usually hold its artwork scans
+const ARTWORK_FOLDERS: [&str; 5] = ["artwork", "scans", "covers", "art", "images"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Album {
+    pub folder: PathBuf,
+    // Best artwork found for the album, if any
+    pub source: Option<PathBuf>,
+    // The folder already has a cover.jpg that would be overwritten
+    pub has_cover: bool,
+}
+
+impl Album {
+    pub fn destination(&self) -> PathBuf {
+        self.folder.join(COVER_FILE_NAME)
+    }
+}
+
+// Find every album folder below `root`, sorted by path
+pub fn scan(root: &Path) -> Result<Vec<Album>, ArtCoverError> {
+    let mut albums = Vec::new();
+    collect_albums(root, &mut albums).map_err(|e| ArtCoverError::io(root, e))?;
+    albums.sort_by(|a, b| a.folder.cmp(&b.folder));
+    Ok(albums)
+}
+
+// Convert the album's artwork into its cover.jpg. An existing cover.jpg is
+// never used as the source, and is only replaced when `replace` is set;
+// Ok(None) when the album is left as it was.
+pub fn convert_album(
+    album: &Album,
+    options: &ProcessOptions,
+    replace: bool,
+    cancel: &CancelToken,
+) -> Result<Option<ProcessedImage>, ArtCoverError> {
+    let Some(source) = &album.source else {
+        return Ok(None);
+    };
+    if !replace && (album.has_cover || album.destination().exists()) {
+        return Ok(None);
+    }
+
+    let mut options = options.clone();
+    options.preset.format = OutputFormat::Jpeg;
+
+    pipeline::convert_to(source, &album.destination(), &options, &mut |_| {}, cancel).map(Some)
+}
+
+pub fn is_audio(path: &Path) -> bool {
+    has_extension(path, &AUDIO_EXTENSIONS)
+}
+
+// cover.*, then folder.*, then front.*, otherwise the largest image
+pub fn best_cover(images: &[PathBuf]) -> Option<PathBuf> {
+    let stem = |path: &PathBuf| {
+        path.file_stem()
+            .and_then(|s| s.to_str())
+            .map(|s| s.to_lowercase())
+            .unwrap_or_default()
+    };
+
+    for preferred in PREFERRED_STEMS {
+        if let Some(path) = images.iter().find(|path| stem(path) == preferred) {
+            return Some(path.clone());
+        }
+    }
+
+    images
+        .iter()
+        .max_by_key(|path| {
+            // Only the header is read; unreadable files rank last
+            image::image_dimensions(path)
+                .map(|(width, height)| width as u64 * height as u64)
+                .unwrap_or(0)
+        })
+        .cloned()
+}
+
+fn collect_albums(dir: &Path, albums: &mut Vec<Album>) -> std::io::Result<()> {
+    let mut images = Vec::new();
+    let mut has_audio = false;
+    let mut has_cover = false;
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        // Symlinked folders are not followed, a link to a parent would
+        // never end
+        if entry.file_type()?.is_dir() {
+            collect_albums(&path, albums)?;
+            if is_artwork_folder(&path) {
+                images.extend(images_in(&path)?);
+            }
+        } else if is_audio(&path) {
+            has_audio = true;
+        } else if is_cover_file(&path) {
+            has_cover = true;
+        } else if crate::is_supported_image(&path) {
+            images.push(path);
+        }
+    }
+
+    if has_audio {
+        // Same order on every platform, so the largest-image tie is stable
+        images.sort();
+        albums.push(Album {
+            folder: dir.to_path_buf(),
+            source: best_cover(&images),
+            has_cover,
+        });
+    }
+
+    Ok(())
+}
+
+fn images_in(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut images = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && crate::is_supported_image(&path) {
+            images.push(path);
+        }
+    }
+    Ok(images)
+}
+
+fn is_artwork_folder(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|s| s.to_str())
+        .map(|name| ARTWORK_FOLDERS.contains(&name.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+fn is_cover_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|s| s.to_str())
+        .map(|name| name.eq_ignore_ascii_case(COVER_FILE_NAME))
+        .unwrap_or(false)
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|s| s.to_str())
+        .map(|extension| extensions.contains(&extension.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
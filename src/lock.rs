@@ -0,0 +1,145 @@
+use crate::{ArtCoverError, CancelToken};
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime};
+
+// How long to wait for another process before giving up
+const WAIT: Duration = Duration::from_secs(30);
+const RETRY_EVERY: Duration = Duration::from_millis(50);
+
+// Locks not refreshed for this long were left by a crash
+const STALE_AFTER: Duration = Duration::from_secs(120);
+// Held locks are touched this often, however long the job takes
+const REFRESH_EVERY: Duration = Duration::from_secs(30);
+
+// Exclusive right to rewrite a file, shared with every process of the app
+// (GUI, daemon, another window) through a lock file next to it. Released
+// when dropped.
+#[derive(Debug)]
+pub struct Lock {
+    path: PathBuf,
+    // Dropped to stop the refreshing thread
+    stop: Option<mpsc::Sender<()>>,
+    refresh: Option<JoinHandle<()>>,
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        self.stop.take();
+        if let Some(refresh) = self.refresh.take() {
+            let _ = refresh.join();
+        }
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+// Lock `target`, waiting while another process holds it
+pub fn acquire(target: &Path, cancel: &CancelToken) -> Result<Lock, ArtCoverError> {
+    let path = lock_path(target);
+    let started = SystemTime::now();
+
+    loop {
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                // The holder's process id, to recognize locks of dead processes
+                let _ = write!(file, "{}", std::process::id());
+                return Ok(held(path));
+            }
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                if is_stale(&path) {
+                    take_over(&path);
+                    continue;
+                }
+            }
+            // A read-only folder and such: writing the file would fail too
+            Err(e) => return Err(ArtCoverError::io(&path, e)),
+        }
+
+        if started.elapsed().unwrap_or_default() > WAIT {
+            return Err(ArtCoverError::Locked(target.to_path_buf()));
+        }
+        if cancel.is_cancelled() {
+            return Err(ArtCoverError::Cancelled);
+        }
+        thread::sleep(RETRY_EVERY);
+    }
+}
+
+// Keep the lock fresh from a thread of its own until it is dropped, so
+// long PDF, OCR or PNG optimization jobs do not look abandoned
+fn held(path: PathBuf) -> Lock {
+    let (stop, stopped) = mpsc::channel::<()>();
+    let touched = path.clone();
+    let refresh = thread::spawn(move || {
+        while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(REFRESH_EVERY) {
+            if let Ok(file) = File::options().write(true).open(&touched) {
+                let _ = file.set_modified(SystemTime::now());
+            }
+        }
+    });
+    Lock {
+        path,
+        stop: Some(stop),
+        refresh: Some(refresh),
+    }
+}
+
+// Move a stale lock out of the way under a name of our own, so of two
+// processes finding it stale only one removes it. The lock renamed is
+// checked again: if another process replaced the stale one in between,
+// it is put back.
+fn take_over(path: &Path) {
+    static TAKEOVERS: AtomicU32 = AtomicU32::new(0);
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(
+        ".{}-{}",
+        std::process::id(),
+        TAKEOVERS.fetch_add(1, Ordering::Relaxed)
+    ));
+    let moved = path.with_file_name(name);
+
+    // Failing, it was taken over or released by someone else first
+    if fs::rename(path, &moved).is_ok() {
+        if !is_stale(&moved) {
+            let _ = fs::hard_link(&moved, path);
+        }
+        let _ = fs::remove_file(&moved);
+    }
+}
+
+// ".cover.jpg.artcover-lock" for "cover.jpg"
+fn lock_path(target: &Path) -> PathBuf {
+    let name = target
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    target.with_file_name(format!(".{}.artcover-lock", name))
+}
+
+fn is_stale(path: &Path) -> bool {
+    let age = fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .map(|modified| modified.elapsed().unwrap_or_default());
+    match age {
+        Ok(age) if age > STALE_AFTER => true,
+        Ok(_) => !holder_alive(path),
+        // Released in the meantime
+        Err(_) => false,
+    }
+}
+
+// Only Linux can tell cheaply; elsewhere a lock counts as held until stale
+fn holder_alive(path: &Path) -> bool {
+    if !cfg!(target_os = "linux") {
+        return true;
+    }
+    match fs::read_to_string(path) {
+        Ok(pid) if !pid.trim().is_empty() => Path::new("/proc").join(pid.trim()).exists(),
+        // Just created, the id is not written yet
+        _ => true,
+    }
+}
@@ -0,0 +1,106 @@
+use std::collections::VecDeque;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::prelude::*;
+
+// Lines kept in memory for the log pane
+const RECENT_LINES: usize = 500;
+
+// Daily files kept in the log folder
+const KEPT_FILES: usize = 7;
+
+static RECENT: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+// Log to a daily file in the data folder, and to memory for the log pane.
+// The file is flushed when the guard is dropped, so main keeps it.
+pub fn init() -> Option<WorkerGuard> {
+    let memory = tracing_subscriber::fmt::layer()
+        .with_ansi(false)
+        .with_target(false)
+        .with_writer(Recent::default)
+        .with_filter(LevelFilter::INFO);
+
+    let appender = log_dir().and_then(|dir| {
+        std::fs::create_dir_all(&dir).ok()?;
+        RollingFileAppender::builder()
+            .rotation(Rotation::DAILY)
+            .filename_prefix("artcover")
+            .filename_suffix("log")
+            .max_log_files(KEPT_FILES)
+            .build(dir)
+            .ok()
+    });
+    let (file, guard) = match appender {
+        Some(appender) => {
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            let layer = tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_writer(writer)
+                .with_filter(LevelFilter::DEBUG);
+            (Some(layer), Some(guard))
+        }
+        // No data folder: the log pane still works
+        None => (None, None),
+    };
+
+    tracing_subscriber::registry()
+        .with(memory)
+        .with(file)
+        .init();
+    guard
+}
+
+// Folder of the log files, in the per-user data folder
+pub fn log_dir() -> Option<PathBuf> {
+    let var = |name| std::env::var_os(name).map(PathBuf::from);
+    let data = if cfg!(windows) {
+        var("LOCALAPPDATA").or_else(|| var("APPDATA"))
+    } else if cfg!(target_os = "macos") {
+        var("HOME").map(|home| home.join("Library").join("Application Support"))
+    } else {
+        var("XDG_DATA_HOME").or_else(|| var("HOME").map(|home| home.join(".local").join("share")))
+    };
+    data.map(|dir| dir.join("artcover").join("logs"))
+}
+
+// Latest log lines, oldest first
+#[cfg(feature = "gui")]
+pub fn recent() -> Vec<String> {
+    RECENT
+        .lock()
+        .map(|recent| recent.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+// Collects one formatted event, stored when the formatter drops it
+#[derive(Default)]
+struct Recent(Vec<u8>);
+
+impl io::Write for Recent {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for Recent {
+    fn drop(&mut self) {
+        let Ok(mut recent) = RECENT.lock() else {
+            return;
+        };
+        for line in String::from_utf8_lossy(&self.0).lines() {
+            if recent.len() == RECENT_LINES {
+                recent.pop_front();
+            }
+            recent.push_back(line.to_string());
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use crate::ArtCoverError;
+use image::{DynamicImage, Rgba};
+use std::fs;
+use std::path::Path;
+
+// 3D color lookup table in the Adobe/Resolve .cube layout
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lut {
+    size: usize,
+    domain_min: [f32; 3],
+    domain_max: [f32; 3],
+    // size^3 entries, red changing fastest
+    table: Vec<[f32; 3]>,
+}
+
+impl Lut {
+    pub fn load(path: &Path) -> Result<Self, ArtCoverError> {
+        let text = fs::read_to_string(path).map_err(|e| ArtCoverError::io(path, e))?;
+        Self::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ArtCoverError> {
+        let invalid = |message: &str| ArtCoverError::InvalidLut(message.to_string());
+
+        let mut size = None;
+        let mut domain_min = [0.0; 3];
+        let mut domain_max = [1.0; 3];
+        let mut table = Vec::new();
+
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut words = line.split_whitespace();
+            let keyword = words.next().unwrap_or_default();
+            match keyword {
+                "LUT_3D_SIZE" => {
+                    size = words.next().and_then(|value| value.parse::<usize>().ok());
+                }
+                "LUT_1D_SIZE" => return Err(invalid("1D LUTs are not supported")),
+                "DOMAIN_MIN" => domain_min = triple(words).ok_or_else(|| invalid(line))?,
+                "DOMAIN_MAX" => domain_max = triple(words).ok_or_else(|| invalid(line))?,
+                "TITLE" | "LUT_3D_INPUT_RANGE" => {}
+                _ => table.push(triple(line.split_whitespace()).ok_or_else(|| invalid(line))?),
+            }
+        }
+
+        let size = size
+            .filter(|size| *size >= 2)
+            .ok_or_else(|| invalid("missing LUT_3D_SIZE"))?;
+        if table.len() != size * size * size {
+            return Err(invalid(&format!(
+                "expected {} entries, found {}",
+                size * size * size,
+                table.len()
+            )));
+        }
+
+        Ok(Self {
+            size,
+            domain_min,
+            domain_max,
+            table,
+        })
+    }
+
+    // Map every pixel through the table with trilinear interpolation;
+    // alpha is kept
+    pub fn apply(&self, img: &DynamicImage) -> DynamicImage {
+        let mut rgba = img.to_rgba8();
+        for pixel in rgba.pixels_mut() {
+            let Rgba([r, g, b, a]) = *pixel;
+            let [r, g, b] = self.lookup([r, g, b].map(|c| c as f32 / 255.0));
+            *pixel = Rgba([to_u8(r), to_u8(g), to_u8(b), a]);
+        }
+        DynamicImage::ImageRgba8(rgba)
+    }
+
+    fn lookup(&self, color: [f32; 3]) -> [f32; 3] {
+        let last = (self.size - 1) as f32;
+
+        // Position inside the lattice, per channel
+        let mut base = [0usize; 3];
+        let mut fraction = [0f32; 3];
+        for channel in 0..3 {
+            let range = self.domain_max[channel] - self.domain_min[channel];
+            let normalized = if range > 0.0 {
+                (color[channel] - self.domain_min[channel]) / range
+            } else {
+                0.0
+            };
+            let position = normalized.clamp(0.0, 1.0) * last;
+            base[channel] = (position.floor() as usize).min(self.size - 2);
+            fraction[channel] = position - base[channel] as f32;
+        }
+
+        let mut result = [0f32; 3];
+        for corner in 0..8 {
+            let offset = [corner & 1, (corner >> 1) & 1, (corner >> 2) & 1];
+            let weight: f32 = (0..3)
+                .map(|channel| {
+                    if offset[channel] == 1 {
+                        fraction[channel]
+                    } else {
+                        1.0 - fraction[channel]
+                    }
+                })
+                .product();
+
+            let entry = self.entry(
+                base[0] + offset[0],
+                base[1] + offset[1],
+                base[2] + offset[2],
+            );
+            for channel in 0..3 {
+                result[channel] += entry[channel] * weight;
+            }
+        }
+        result
+    }
+
+    fn entry(&self, r: usize, g: usize, b: usize) -> [f32; 3] {
+        self.table[r + g * self.size + b * self.size * self.size]
+    }
+}
+
+fn triple<'a>(mut words: impl Iterator<Item = &'a str>) -> Option<[f32; 3]> {
+    let mut values = [0.0; 3];
+    for value in &mut values {
+        *value = words.next()?.parse().ok()?;
+    }
+    Some(values)
+}
+
+fn to_u8(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
@@ -1,189 +1,105 @@
-use iced::widget::{Image, column, container, text};
-use iced::{
-    Application, Command, Element, Event, Length, Settings, Size, Subscription, event, executor,
-};
-use image::GenericImageView;
-use std::path::PathBuf;
+use artcover_image_conversor::daemon;
+use std::process::ExitCode;
+
+mod cli;
+#[cfg(feature = "gui")]
+mod gui;
+mod logging;
+
+// Principal entry. `convert <files>` converts from the command line and
+// `--daemon [socket]` serves conversions to other local tools, instead of
+// opening the window. Built without the "gui" feature, there is no window
+// and anything else prints the usage.
+pub fn main() -> ExitCode {
+    let _log = logging::init();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("--daemon") => {
+            return match cli::parse_daemon(&args[1..]) {
+                Ok(args) => run_daemon(&args),
+                Err(message) => usage_error(&message),
+            };
+        }
+        Some("convert") => {
+            return match cli::parse(&args[1..]) {
+                Ok(args) => cli::run(&args),
+                Err(message) => usage_error(&message),
+            };
+        }
+        _ => {}
+    }
+    run_window()
+}
+
+#[cfg(feature = "gui")]
+fn run_window() -> ExitCode {
+    use iced::{Application, Settings, Size};
 
-// Principal entry
-pub fn main() -> iced::Result {
-    ImageProcessor::run(Settings {
+    let result = gui::ImageProcessor::run(Settings {
         window: iced::window::Settings {
-            size: Size::new(400.0, 500.0),
+            size: Size::new(640.0, 720.0),
             ..Default::default()
         },
         ..Default::default()
-    })
-}
-
-// Define App status
-#[derive(Debug, Default)]
-struct ImageProcessor {
-    message: String,
-    processed_image: Option<PathBuf>,
-    is_processing: bool,
-}
-
-// Define Messages (Events)
-#[derive(Debug, Clone)]
-enum Message {
-    FileDropped(PathBuf),
-    ImageProcessed(Result<PathBuf, String>),
-    EventOccurred(Event),
-}
-
-// General Logic
-impl Application for ImageProcessor {
-    type Executor = executor::Default;
-    type Message = Message;
-    type Theme = iced::Theme;
-    type Flags = ();
-
-    fn new(_flags: ()) -> (Self, Command<Message>) {
-        (
-            Self {
-                message: "Drag an image here".to_string(),
-                processed_image: None,
-                is_processing: false,
-            },
-            Command::none(),
-        )
-    }
-
-    fn title(&self) -> String {
-        String::from("CoverArt Converter for iPod")
-    }
-
-    // Listen OS events
-    fn subscription(&self) -> Subscription<Message> {
-        event::listen().map(Message::EventOccurred)
-    }
-
-    // Manage messages
-    fn update(&mut self, message: Message) -> Command<Message> {
-        match message {
-            Message::EventOccurred(event) => {
-                if let Event::Window(_id, iced::window::Event::FileDropped(path)) = event {
-                    return self.handle_file_drop(path);
-                }
-                Command::none()
-            }
-
-            // Process message
-            Message::FileDropped(path) => {
-                self.is_processing = true;
-                self.processed_image = None;
-                self.message = "Processing...".to_string();
-
-                Command::perform(process_image(path), Message::ImageProcessed)
-            }
-
-            // Finish message
-            Message::ImageProcessed(Ok(path)) => {
-                self.is_processing = false;
-                self.message = "Image processed and saved".to_string();
-                self.processed_image = Some(path);
-                Command::none()
-            }
-
-            // Failure message
-            Message::ImageProcessed(Err(error_message)) => {
-                self.is_processing = false;
-                self.message = format!("Error: {}", error_message);
-                Command::none()
-            }
-        }
-    }
-
-    // Draw UI
-    fn view(&self) -> Element<Message> {
-        let mut content = column![text(&self.message).size(24),]
-            .spacing(20)
-            .align_items(iced::Alignment::Center);
-
-        if let Some(path) = &self.processed_image {
-            let image_handle = iced::widget::image::Handle::from_path(path.clone());
-
-            content = content.push(
-                Image::new(image_handle)
-                    .width(Length::Fixed(300.0))
-                    .height(Length::Fixed(300.0))
-                    .content_fit(iced::ContentFit::Contain),
-            );
+    });
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::FAILURE
         }
-
-        container(content)
-            .width(Length::Fill)
-            .height(Length::Fill)
-            .padding(20)
-            .center_x()
-            .center_y()
-            .into()
     }
 }
 
-// Auxiliar actions
+#[cfg(not(feature = "gui"))]
+fn run_window() -> ExitCode {
+    usage_error("built without the window, use convert or --daemon")
+}
 
-impl ImageProcessor {
-    fn handle_file_drop(&mut self, path: PathBuf) -> Command<Message> {
-        if !self.is_processing {
-            match path.extension().and_then(|s| s.to_str()) {
-                Some("png") | Some("jpg") | Some("jpeg") | Some("bmp") | Some("webp") => {
-                    return Command::perform(async { path }, Message::FileDropped);
-                }
-                _ => {
-                    self.message = "Error: only images are supported".to_string();
-                }
-            }
-        }
-        Command::none()
-    }
+fn usage_error(message: &str) -> ExitCode {
+    eprintln!("Error: {}\n\n{}", message, cli::usage());
+    ExitCode::from(2)
 }
 
-// IMAGE PROCESS
-async fn process_image(path: PathBuf) -> Result<PathBuf, String> {
-    // Load image into disk
-    let img = match image::open(&path) {
-        Ok(img) => img,
-        Err(e) => return Err(format!("Image cannot be oppened: {}", e)),
+// Requests use the options and presets saved by the GUI, with the
+// overrides applied
+fn run_daemon(args: &cli::DaemonArgs) -> ExitCode {
+    let address = args.address.as_deref();
+    let saved = match cli::load_settings(&args.overrides) {
+        Ok(saved) => saved,
+        Err(message) => {
+            eprintln!("Error: {}", message);
+            return ExitCode::from(2);
+        }
     };
 
-    // Apply redimension 
-    let (width, height) = img.dimensions();
-
-    let (target_width, target_height) = if width > 300 || height > 300 {
-        (300, 300)
-    } else if width <= 200 && height <= 200 {
-        (width, height)
-    } else {
-        (200, 200)
+    #[cfg(unix)]
+    let result = {
+        address
+            .map(|socket| Ok(std::path::PathBuf::from(socket)))
+            .unwrap_or_else(daemon::default_socket)
+            .and_then(|socket| {
+                eprintln!("Starting the daemon on {}", socket.display());
+                daemon::serve(&socket, saved.options, saved.presets)
+            })
+    };
+    #[cfg(not(unix))]
+    let result = {
+        let port = address
+            .and_then(|port| port.parse().ok())
+            .unwrap_or(daemon::DEFAULT_PORT);
+        eprintln!("Starting the daemon on 127.0.0.1:{}", port);
+        if let Some(token) = daemon::token_path() {
+            eprintln!("Requests need the token in {}", token.display());
+        }
+        daemon::serve(port, saved.options, saved.presets)
     };
 
-    let processed_img;
-    if (width, height) == (target_width, target_height) {
-        processed_img = img;
-    } else {
-        processed_img = img.resize_exact(
-            target_width,
-            target_height,
-            image::imageops::FilterType::Lanczos3,
-        );
-    }
-
-    // Prepare save path
-    let original_stem = path
-        .file_stem()
-        .unwrap_or_default()
-        .to_str()
-        .unwrap_or("image");
-
-    let extension = path.extension().and_then(|s| s.to_str()).unwrap_or("png");
-    let new_filename = format!("{}_processed.{}", original_stem, extension);
-    let new_path = path.with_file_name(new_filename);
-
-    // Save new image
-    match processed_img.save(&new_path) {
-        Ok(_) => Ok(new_path),
-        Err(e) => Err(format!("No se pudo guardar la imagen: {}", e)),
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e.user_message());
+            ExitCode::FAILURE
+        }
     }
 }
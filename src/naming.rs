@@ -0,0 +1,98 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Appended to the source name of every converted file
+pub const OUTPUT_SUFFIX: &str = "_processed";
+
+// Characters FAT32 does not allow in names
+const RESERVED: [char; 9] = ['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+
+// Output file next to the source, `stem` replacing the source name if given.
+// With `ascii` the name is transliterated ("Beyoncé" -> "Beyonce").
+pub fn output_path(source: &Path, stem: Option<&str>, extension: &str, ascii: bool) -> PathBuf {
+    let original_stem = source
+        .file_stem()
+        .unwrap_or_default()
+        .to_str()
+        .unwrap_or("image");
+
+    let new_filename = format!(
+        "{}{}.{}",
+        sanitize(stem.unwrap_or(original_stem), ascii),
+        OUTPUT_SUFFIX,
+        extension
+    );
+    source.with_file_name(new_filename)
+}
+
+// Make a name valid on FAT32 players: reserved and control characters
+// become "_", trailing dots and spaces are dropped
+pub fn sanitize(name: &str, ascii: bool) -> String {
+    let name = if ascii {
+        deunicode::deunicode(name)
+    } else {
+        name.to_string()
+    };
+
+    let cleaned: String = name
+        .chars()
+        .map(|c| {
+            if RESERVED.contains(&c) || c.is_control() {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+
+    let cleaned = cleaned.trim().trim_end_matches(['.', ' ']);
+    if cleaned.is_empty() {
+        "image".to_string()
+    } else {
+        cleaned.to_string()
+    }
+}
+
+// Next "<stem>_vN_processed.<ext>" for an unversioned output path. Numbers
+// are never reused, even after older versions were deleted.
+pub fn next_version(output: &Path) -> (PathBuf, u32) {
+    let stem = output
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("image");
+    let base = stem.strip_suffix(OUTPUT_SUFFIX).unwrap_or(stem);
+    let extension = output.extension().and_then(|s| s.to_str()).unwrap_or("png");
+    let prefix = format!("{}_v", base);
+
+    let folder = match output.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let latest = fs::read_dir(folder)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| {
+                    let path = entry.path();
+                    let stem = path.file_stem()?.to_str()?;
+                    stem.strip_prefix(&prefix)?
+                        .strip_suffix(OUTPUT_SUFFIX)?
+                        .parse::<u32>()
+                        .ok()
+                })
+                .max()
+                .unwrap_or(0)
+        })
+        .unwrap_or(0);
+
+    let version = latest + 1;
+    let name = format!("{}{}{}.{}", prefix, version, OUTPUT_SUFFIX, extension);
+    (output.with_file_name(name), version)
+}
+
+// Files produced by a previous conversion
+pub fn is_output_name(path: &Path) -> bool {
+    path.file_stem()
+        .and_then(|s| s.to_str())
+        .is_some_and(|stem| stem.ends_with(OUTPUT_SUFFIX))
+}
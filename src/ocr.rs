@@ -0,0 +1,95 @@
+use crate::i18n::tf;
+use std::path::Path;
+use std::process::Command;
+
+// Languages passed to tesseract when none are configured
+pub const DEFAULT_LANGUAGES: &str = "eng+spa+jpn";
+
+// Longest hint kept for file names
+const MAX_HINT_CHARS: usize = 60;
+
+// Read the text printed on a cover with the tesseract CLI.
+// `languages` uses tesseract syntax, e.g. "eng+spa".
+pub fn read_cover_text(path: &Path, languages: &str) -> Result<String, String> {
+    let languages = languages_or_default(languages);
+
+    let output = Command::new("tesseract")
+        .arg(path)
+        .arg("stdout")
+        .args(["-l", languages])
+        .output()
+        .map_err(|e| tf("tesseract could not be started: {}", &[&e]))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(tf("tesseract failed: {}", &[&stderr.trim()]));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// Whether tesseract starts and has the data of every language installed
+pub fn check(languages: &str) -> Result<String, String> {
+    let languages = languages_or_default(languages);
+    let output = Command::new("tesseract")
+        .arg("--list-langs")
+        .output()
+        .map_err(|e| tf("tesseract could not be started: {}", &[&e]))?;
+
+    // Older releases print the list to stderr, after a header line
+    let listing = String::from_utf8_lossy(&[output.stdout, output.stderr].concat()).into_owned();
+    let installed: Vec<&str> = listing.lines().skip(1).map(str::trim).collect();
+    let missing: Vec<&str> = languages
+        .split('+')
+        .filter(|language| !installed.contains(language))
+        .collect();
+
+    if missing.is_empty() {
+        Ok(tf("languages {}", &[&languages]))
+    } else {
+        Err(tf("missing language data: {}", &[&missing.join(", ")]))
+    }
+}
+
+fn languages_or_default(languages: &str) -> &str {
+    if languages.trim().is_empty() {
+        DEFAULT_LANGUAGES
+    } else {
+        languages.trim()
+    }
+}
+
+// Build an "Artist - Album" style hint from the most prominent lines
+pub fn naming_hint(text: &str) -> Option<String> {
+    let lines: Vec<String> = text
+        .lines()
+        .map(|line| {
+            line.chars()
+                .filter(|c| c.is_alphanumeric() || *c == ' ' || *c == '\'' || *c == '&')
+                .collect::<String>()
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .filter(|line| line.chars().filter(|c| c.is_alphanumeric()).count() >= 3)
+        .take(2)
+        .collect();
+
+    if lines.is_empty() {
+        return None;
+    }
+
+    Some(lines.join(" - ").chars().take(MAX_HINT_CHARS).collect())
+}
+
+// Names such as scan0001, IMG_1234, DSC00042 or untitled carry no metadata
+pub fn is_generic_name(stem: &str) -> bool {
+    let lower = stem.to_lowercase();
+    let prefix =
+        lower.trim_end_matches(|c: char| c.is_ascii_digit() || c == '_' || c == '-' || c == ' ');
+
+    matches!(
+        prefix,
+        "" | "scan" | "img" | "image" | "dsc" | "dscn" | "photo" | "untitled"
+    )
+}
@@ -0,0 +1,225 @@
+// Settings given as command-line flags or environment variables, for
+// containers and scripts where editing the settings file is awkward.
+// Precedence, highest first: flags, ARTCOVER_* variables, the profile
+// named by either (the flag's first), the saved settings, the defaults.
+
+use crate::conform::Passthrough;
+use crate::hash_index::DuplicateCheck;
+use crate::i18n::Language;
+use crate::preset;
+use crate::quantize::{self, ColorMode};
+use crate::resize::{ResizeMode, UpscaleMode};
+use crate::retry;
+use crate::settings::{Appearance, Bundle};
+use serde_json::Value;
+use std::fmt::Debug;
+
+pub const ENV_PREFIX: &str = "ARTCOVER_";
+
+// Every setting that can be overridden: its flag name (without "--") and
+// the values it takes
+pub const SETTINGS: [(&str, &str); 23] = [
+    // Always applied first, so the other settings apply over the profile
+    ("profile", "<name>"),
+    ("preset", "<name>"),
+    ("auto-trim", "true|false"),
+    ("trim-tolerance", "0-255"),
+    ("resize-mode", "stretch|crop|pad|pad-edge|pad-blur"),
+    ("upscale", "off|lanczos|integer"),
+    ("ocr", "true|false"),
+    ("ocr-languages", "<tesseract languages>"),
+    ("jpeg-quality", "1-100"),
+    ("auto-quality", "off|<ssim 0-1>"),
+    ("deterministic", "true|false"),
+    ("baseline-jpeg", "true|false"),
+    ("passthrough", "off|copy|hard-link"),
+    ("optimize-png", "true|false"),
+    ("frame", "<number>"),
+    ("versioned", "true|false"),
+    ("duplicates", "off|flag|skip"),
+    ("provenance", "true|false"),
+    ("color-mode", "full|grayscale|palette"),
+    ("palette-colors", "2-256"),
+    ("retries", "0-10"),
+    ("language", "system|english|spanish"),
+    ("appearance", "system|light|dark"),
+];
+
+pub fn is_setting(name: &str) -> bool {
+    SETTINGS.iter().any(|(setting, _)| *setting == name)
+}
+
+// "ARTCOVER_JPEG_QUALITY" for "jpeg-quality"
+pub fn env_name(setting: &str) -> String {
+    format!(
+        "{}{}",
+        ENV_PREFIX,
+        setting.replace('-', "_").to_ascii_uppercase()
+    )
+}
+
+// Set the ARTCOVER_* variables of `vars` and then `flags` (by flag name,
+// in the order given) on `bundle`. A profile replaces all the options, so
+// it goes first wherever it was given. Errors name the variable or flag.
+pub fn apply_all(
+    bundle: &mut Bundle,
+    vars: impl IntoIterator<Item = (String, String)>,
+    flags: &[(String, String)],
+) -> Result<(), String> {
+    let vars: Vec<(String, String)> = vars.into_iter().collect();
+    let env_value = |setting: &str| {
+        let name = env_name(setting);
+        vars.iter()
+            .find(|(var, _)| *var == name)
+            .map(|(_, value)| (name, value.as_str()))
+    };
+
+    let profile = flags
+        .iter()
+        .rev()
+        .find(|(name, _)| name == "profile")
+        .map(|(_, value)| ("--profile".to_string(), value.as_str()))
+        .or_else(|| env_value("profile"));
+    if let Some((name, value)) = profile {
+        apply(bundle, "profile", value).map_err(|e| format!("{}: {}", name, e))?;
+    }
+
+    for (setting, _) in SETTINGS.iter().filter(|(setting, _)| *setting != "profile") {
+        if let Some((name, value)) = env_value(setting) {
+            apply(bundle, setting, value).map_err(|e| format!("{}: {}", name, e))?;
+        }
+    }
+    for (name, value) in flags.iter().filter(|(name, _)| name != "profile") {
+        apply(bundle, name, value).map_err(|e| format!("--{}: {}", name, e))?;
+    }
+    Ok(())
+}
+
+// What to save of `current`, the settings in use after starting from `saved`
+// overridden into `overridden`: every value still as overridden goes back
+// to the saved one, so overrides never end up in the settings file. Values
+// changed since are the user's and are kept.
+pub fn own_values(saved: &Bundle, overridden: &Bundle, current: &Bundle) -> Bundle {
+    let value = |bundle: &Bundle| serde_json::to_value(bundle).unwrap_or_default();
+    let own = unoverride(&value(saved), &value(overridden), value(current));
+    serde_json::from_value(own).unwrap_or_else(|_| current.clone())
+}
+
+// Field by field, down into nested objects
+fn unoverride(saved: &Value, overridden: &Value, current: Value) -> Value {
+    if current == *overridden {
+        return saved.clone();
+    }
+    match (saved, overridden, current) {
+        (Value::Object(saved), Value::Object(overridden), Value::Object(current)) => current
+            .into_iter()
+            .map(
+                |(key, value)| match (saved.get(&key), overridden.get(&key)) {
+                    (Some(saved), Some(overridden)) => {
+                        let value = unoverride(saved, overridden, value);
+                        (key, value)
+                    }
+                    _ => (key, value),
+                },
+            )
+            .collect(),
+        (_, _, current) => current,
+    }
+}
+
+// Set one setting, by its flag name
+pub fn apply(bundle: &mut Bundle, setting: &str, value: &str) -> Result<(), String> {
+    let options = &mut bundle.options;
+    let value = value.trim();
+    match setting {
+        "profile" => {
+            let profile = bundle
+                .option_profiles
+                .iter()
+                .find(|profile| profile.name.eq_ignore_ascii_case(value))
+                .ok_or_else(|| format!("unknown profile {}", value))?;
+            *options = profile.options.clone();
+        }
+        "preset" => {
+            options.preset = preset::find(value, &bundle.presets)
+                .ok_or_else(|| format!("unknown preset {}", value))?;
+        }
+        "auto-trim" => options.auto_trim = flag(value)?,
+        "trim-tolerance" => options.trim_tolerance = number(value)?,
+        "resize-mode" => options.resize_mode = choice(&ResizeMode::ALL, value)?,
+        "upscale" => options.upscale = choice(&UpscaleMode::ALL, value)?,
+        "ocr" => options.ocr = flag(value)?,
+        "ocr-languages" => options.ocr_languages = value.to_string(),
+        "jpeg-quality" => match number(value)? {
+            quality @ 1..=100 => options.jpeg_quality = quality,
+            _ => return Err(format!("{} is not between 1 and 100", value)),
+        },
+        "auto-quality" => {
+            options.auto_quality = match value.to_ascii_lowercase().as_str() {
+                "off" | "false" | "none" => None,
+                ssim => match ssim.parse::<f32>() {
+                    Ok(ssim) if (0.0..=1.0).contains(&ssim) => Some(ssim),
+                    _ => return Err(format!("{} is not off or an SSIM from 0 to 1", value)),
+                },
+            }
+        }
+        "deterministic" => options.deterministic = flag(value)?,
+        "baseline-jpeg" => options.baseline_jpeg = flag(value)?,
+        "passthrough" => options.passthrough = choice(&Passthrough::ALL, value)?,
+        "optimize-png" => options.optimize_png = flag(value)?,
+        "frame" => options.frame = number(value)?,
+        "versioned" => options.versioned = flag(value)?,
+        "duplicates" => options.duplicates = choice(&DuplicateCheck::ALL, value)?,
+        "provenance" => options.provenance = flag(value)?,
+        "color-mode" => options.color_mode = choice(&ColorMode::ALL, value)?,
+        "palette-colors" => match number(value)? {
+            colors @ 2..=quantize::MAX_PALETTE_COLORS => options.palette_colors = colors,
+            _ => return Err(format!("{} is not between 2 and 256", value)),
+        },
+        "retries" => match number(value)? {
+            retries @ 0..=retry::MAX_RETRIES => options.retries = retries,
+            _ => return Err(format!("{} is not between 0 and 10", value)),
+        },
+        "language" => bundle.language = choice(&Language::ALL, value)?,
+        "appearance" => bundle.appearance = choice(&Appearance::ALL, value)?,
+        _ => return Err(format!("unknown setting {}", setting)),
+    }
+    Ok(())
+}
+
+fn flag(value: &str) -> Result<bool, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Ok(true),
+        "false" | "no" | "off" | "0" => Ok(false),
+        _ => Err(format!("{} is not true or false", value)),
+    }
+}
+
+fn number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} is not a valid number", value))
+}
+
+// Variant of `all` named like `value`: "hard-link" for HardLink, in any case
+fn choice<T: Copy + Debug>(all: &[T], value: &str) -> Result<T, String> {
+    let wanted = value.replace(['-', '_'], "").to_ascii_lowercase();
+    all.iter()
+        .copied()
+        .find(|variant| format!("{:?}", variant).to_ascii_lowercase() == wanted)
+        .ok_or_else(|| {
+            let names: Vec<String> = all.iter().map(|variant| kebab(variant)).collect();
+            format!("{} is not one of {}", value, names.join(", "))
+        })
+}
+
+fn kebab(variant: &impl Debug) -> String {
+    let mut name = String::new();
+    for c in format!("{:?}", variant).chars() {
+        if c.is_ascii_uppercase() && !name.is_empty() {
+            name.push('-');
+        }
+        name.push(c.to_ascii_lowercase());
+    }
+    name
+}
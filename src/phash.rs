@@ -0,0 +1,25 @@
+use image::DynamicImage;
+use image::imageops::FilterType;
+
+// 64-bit difference hash: compares neighbouring pixels of a 9x8 grayscale
+// thumbnail, so it survives resizing and recompression
+pub fn dhash(img: &DynamicImage) -> u64 {
+    let small = img.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+    let mut hash = 0u64;
+
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if small.get_pixel(x, y).0[0] < small.get_pixel(x + 1, y).0[0] {
+                hash |= 1;
+            }
+        }
+    }
+
+    hash
+}
+
+// Number of differing bits between two hashes
+pub fn distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
@@ -0,0 +1,805 @@
+use crate::encode::{self, EncodeSettings};
+use crate::error::file_name;
+use crate::hash_index::{self, DuplicateCheck};
+use crate::i18n::tf;
+use crate::lut::Lut;
+use crate::preset::Preset;
+use crate::probe::InputEncoding;
+use crate::provenance::{self, Provenance};
+use crate::quantize::ColorMode;
+use crate::{
+    ArtCoverError, ProcessOptions, ProcessedImage, booklet, conform, history, lock, naming, ocr,
+    phash, probe, quality, quantize, resize, retry, svg, trim,
+};
+use image::codecs::gif::GifDecoder;
+use image::error::{ParameterError, ParameterErrorKind};
+use image::{
+    AnimationDecoder, DynamicImage, ImageDecoder, ImageError, ImageFormat, ImageReader, Limits,
+};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Steps of a conversion, in order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Decode,
+    Resize,
+    Encode,
+}
+
+impl Stage {
+    // Overall progress once the stage is complete
+    pub fn completed_at(&self) -> f32 {
+        match self {
+            Stage::Decode => 0.40,
+            Stage::Resize => 0.70,
+            Stage::Encode => 0.95,
+        }
+    }
+
+    fn started_at(&self) -> f32 {
+        match self {
+            Stage::Decode => 0.0,
+            Stage::Resize => Stage::Decode.completed_at(),
+            Stage::Encode => Stage::Resize.completed_at(),
+        }
+    }
+}
+
+// Shared flag checked between stages and while reading the source
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    fn check(&self) -> Result<(), ArtCoverError> {
+        if self.is_cancelled() {
+            Err(ArtCoverError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+// IMAGE PROCESS
+pub fn process_image(
+    path: &Path,
+    options: &ProcessOptions,
+) -> Result<ProcessedImage, ArtCoverError> {
+    process_image_with(path, options, &mut |_| {}, &CancelToken::default())
+}
+
+// Same as process_image, reporting overall progress (0.0 to 1.0) and
+// stopping early once `cancel` is set
+pub fn process_image_with(
+    path: &Path,
+    options: &ProcessOptions,
+    progress: &mut dyn FnMut(f32),
+    cancel: &CancelToken,
+) -> Result<ProcessedImage, ArtCoverError> {
+    let mut processed = run(path, None, options, progress, cancel)?;
+
+    // Remember which settings produced each version
+    if let Some(number) = processed.version {
+        let version = history::Version::new(path, &processed.path, number, options);
+        if let Err(e) = history::record(&version) {
+            processed
+                .warnings
+                .push(tf("Version history not saved: {}", &[&e.user_message()]));
+        }
+    }
+    Ok(processed)
+}
+
+// Convert into a fixed file instead of deriving its name from the source;
+// the format follows the destination's extension
+pub fn convert_to(
+    path: &Path,
+    destination: &Path,
+    options: &ProcessOptions,
+    progress: &mut dyn FnMut(f32),
+    cancel: &CancelToken,
+) -> Result<ProcessedImage, ArtCoverError> {
+    run(path, Some(destination), options, progress, cancel)
+}
+
+fn run(
+    path: &Path,
+    destination: Option<&Path>,
+    options: &ProcessOptions,
+    progress: &mut dyn FnMut(f32),
+    cancel: &CancelToken,
+) -> Result<ProcessedImage, ArtCoverError> {
+    retry::with_retries(options.retries, cancel, || {
+        run_once(path, destination, options, progress, cancel)
+    })
+}
+
+fn run_once(
+    path: &Path,
+    destination: Option<&Path>,
+    options: &ProcessOptions,
+    progress: &mut dyn FnMut(f32),
+    cancel: &CancelToken,
+) -> Result<ProcessedImage, ArtCoverError> {
+    let _span = tracing::info_span!("convert", source = %path.display()).entered();
+
+    // Encodings that devices reject are always rewritten in a plain form
+    let encoding = probe::probe(path).map_err(|e| ArtCoverError::io(path, e))?;
+    let mut warnings = Vec::new();
+
+    // Drawings and booklet pages are rendered, not decoded
+    let rendered = svg::is_svg(path) || booklet::is_pdf(path);
+
+    // Read printed artist/album text from the full resolution source;
+    // tesseract only reads bitmaps
+    let mut text_hint = None;
+    if options.ocr && !options.deterministic && !rendered {
+        match ocr::read_cover_text(path, &options.ocr_languages) {
+            Ok(text) => text_hint = ocr::naming_hint(&text),
+            Err(e) => warnings.push(tf("OCR skipped: {}", &[&e])),
+        }
+    }
+
+    // Generic scanner names are replaced by the text found on the cover
+    let original_stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("image");
+    let stem = text_hint
+        .as_deref()
+        .filter(|_| ocr::is_generic_name(original_stem));
+
+    // Prepare save path. The lock on it is held until the output is written,
+    // so another process never writes the same file or version number.
+    let (new_path, version, _lock) = match destination {
+        Some(destination) => (
+            destination.to_path_buf(),
+            None,
+            lock::acquire(destination, cancel)?,
+        ),
+        None => {
+            let extension = options
+                .preset
+                .format
+                .extension(path.extension().and_then(|s| s.to_str()).unwrap_or("png"));
+            let output = naming::output_path(path, stem, extension, options.preset.ascii_names);
+            let lock = lock::acquire(&output, cancel)?;
+            if options.versioned {
+                let (versioned, number) = naming::next_version(&output);
+                (versioned, Some(number), lock)
+            } else {
+                (output, None, lock)
+            }
+        }
+    };
+
+    // The same cover converted before under another name. The index is
+    // state of the environment, so reproducible runs leave it alone.
+    let check_duplicates = options.duplicates != DuplicateCheck::Off && !options.deterministic;
+
+    // Nothing to change: keep the original bytes instead of re-encoding. The
+    // cover is still decoded for the duplicate check.
+    if conform::conforms(path, &new_path, &encoding, options) {
+        let mut hash = None;
+        if check_duplicates {
+            let (img, _) = decode(path, &encoding, options.frame, &mut |_| {}, cancel)?;
+            let found = phash::dhash(&img);
+            if let Some(earlier) = find_duplicate(&new_path, found, path, options, &mut warnings) {
+                progress(1.0);
+                return Ok(ProcessedImage {
+                    text_hint,
+                    ..earlier
+                });
+            }
+            hash = Some(found);
+        }
+        conform::pass_through(path, &new_path, options.passthrough)?;
+        if let Some(hash) = hash {
+            record_duplicate(hash, path, &new_path, &mut warnings);
+        }
+        tracing::info!(output = %new_path.display(), "already conforms, kept as it is");
+        progress(1.0);
+        return Ok(ProcessedImage {
+            path: new_path,
+            text_hint,
+            warnings,
+            upscaled: false,
+            auto_quality: None,
+            passed_through: true,
+            png_saved: None,
+            frame_count: None,
+            version,
+            duplicate_of: None,
+        });
+    }
+
+    let decode_span = tracing::debug_span!("decode").entered();
+    let (img, frame_count) = if svg::is_svg(path) {
+        (
+            svg::rasterize(path, &options.preset, options.resize_mode)?,
+            1,
+        )
+    } else if booklet::is_pdf(path) {
+        booklet::render_page(path, options.frame, &options.preset, options.resize_mode)?
+    } else {
+        decode(path, &encoding, options.frame, progress, cancel)?
+    };
+    tracing::debug!(
+        width = img.width(),
+        height = img.height(),
+        frame_count,
+        "decoded"
+    );
+    drop(decode_span);
+    progress(Stage::Decode.completed_at());
+    cancel.check()?;
+    // A square chosen by hand, over the automatic crop of the resize mode
+    let img = match options.crop {
+        Some(square) if !rendered => resize::crop_to(&img, square),
+        _ => img,
+    };
+    let img = shrink_large(img, &options.preset);
+
+    let mut warnings = [encoding.warnings(), warnings].concat();
+    if frame_count > 1 && options.frame >= frame_count {
+        let warning = if booklet::is_pdf(path) {
+            tf(
+                "Booklet has {} pages, the last one was used",
+                &[&frame_count],
+            )
+        } else {
+            tf(
+                "Animation has {} frames, the last one was used",
+                &[&frame_count],
+            )
+        };
+        warnings.push(warning);
+    }
+
+    let hash = phash::dhash(&img);
+    if check_duplicates
+        && let Some(earlier) = find_duplicate(&new_path, hash, path, options, &mut warnings)
+    {
+        progress(1.0);
+        return Ok(ProcessedImage {
+            text_hint,
+            ..earlier
+        });
+    }
+
+    let mut processed = convert_decoded(img, path, &new_path, warnings, options, progress, cancel)?;
+    if check_duplicates {
+        record_duplicate(hash, path, &processed.path, &mut processed.warnings);
+    }
+    Ok(ProcessedImage {
+        text_hint,
+        frame_count: (frame_count > 1).then_some(frame_count),
+        version,
+        ..processed
+    })
+}
+
+// An earlier conversion of a cover looking like `hash` is flagged in the
+// warnings; with DuplicateCheck::Skip it is returned instead of converting
+fn find_duplicate(
+    output: &Path,
+    hash: u64,
+    source: &Path,
+    options: &ProcessOptions,
+    warnings: &mut Vec<String>,
+) -> Option<ProcessedImage> {
+    let earlier = hash_index::find_similar(output, hash, source)?;
+    tracing::info!(earlier = %earlier.source.display(), "duplicate cover");
+    if options.duplicates != DuplicateCheck::Skip {
+        warnings.push(tf(
+            "Looks like {}, converted before into {}",
+            &[&file_name(&earlier.source), &file_name(&earlier.output)],
+        ));
+        return None;
+    }
+
+    warnings.push(tf(
+        "Same cover as {}, {} was kept",
+        &[&file_name(&earlier.source), &file_name(&earlier.output)],
+    ));
+    Some(ProcessedImage {
+        path: earlier.output,
+        text_hint: None,
+        warnings: std::mem::take(warnings),
+        upscaled: false,
+        auto_quality: None,
+        passed_through: false,
+        png_saved: None,
+        frame_count: None,
+        version: None,
+        duplicate_of: Some(earlier.source),
+    })
+}
+
+fn record_duplicate(hash: u64, source: &Path, output: &Path, warnings: &mut Vec<String>) {
+    let entry = hash_index::Entry {
+        hash,
+        source: source.to_path_buf(),
+        output: output.to_path_buf(),
+    };
+    if let Err(e) = hash_index::record(&entry) {
+        warnings.push(tf("Duplicate index not saved: {}", &[&e.user_message()]));
+    }
+}
+
+// Fit, encode and save an image that is already in memory (artwork taken
+// out of the `source` file), continuing the progress after the decode stage
+pub fn convert_decoded(
+    img: DynamicImage,
+    source: &Path,
+    destination: &Path,
+    mut warnings: Vec<String>,
+    options: &ProcessOptions,
+    progress: &mut dyn FnMut(f32),
+    cancel: &CancelToken,
+) -> Result<ProcessedImage, ArtCoverError> {
+    let provenance = || Provenance::of(source, &options.preset.name);
+    let encoded = fit_and_encode(
+        img,
+        destination,
+        provenance,
+        &mut warnings,
+        options,
+        progress,
+        cancel,
+    )?;
+
+    // Save new image
+    std::fs::write(destination, encoded.bytes).map_err(|e| ArtCoverError::io(destination, e))?;
+    for warning in &warnings {
+        tracing::warn!("{}", warning);
+    }
+    tracing::info!(output = %destination.display(), "saved");
+    progress(1.0);
+
+    Ok(ProcessedImage {
+        path: destination.to_path_buf(),
+        text_hint: None,
+        warnings,
+        upscaled: encoded.upscaled,
+        auto_quality: encoded.auto_quality,
+        passed_through: false,
+        png_saved: encoded.png_saved,
+        frame_count: None,
+        version: None,
+        duplicate_of: None,
+    })
+}
+
+// Output of a conversion held in memory
+#[derive(Debug, Clone)]
+pub struct EncodedImage {
+    pub bytes: Vec<u8>,
+    pub format: ImageFormat,
+    pub warnings: Vec<String>,
+}
+
+// Convert an image piped in rather than read from a file; nothing is
+// written. The format is told from the bytes, so drawings, booklets and
+// audio files are not accepted.
+pub fn convert_bytes(
+    input: &[u8],
+    options: &ProcessOptions,
+) -> Result<EncodedImage, ArtCoverError> {
+    convert_bytes_from(input, "stdin", options)
+}
+
+// Same as convert_bytes, `source` naming where the bytes came from in the
+// logs and the provenance
+pub fn convert_bytes_from(
+    input: &[u8],
+    source: &str,
+    options: &ProcessOptions,
+) -> Result<EncodedImage, ArtCoverError> {
+    let _span = tracing::info_span!("convert", source).entered();
+    let name = Path::new(source);
+    let encoding = probe::probe_bytes(input);
+    let cancel = CancelToken::default();
+
+    let reader = ImageReader::new(io::Cursor::new(input))
+        .with_guessed_format()
+        .map_err(|e| ArtCoverError::io(name, e))?;
+    let input_format = reader
+        .format()
+        .ok_or_else(|| ArtCoverError::UnsupportedFormat("unknown".to_string()))?;
+    let img = if input_format == ImageFormat::Gif {
+        decode_gif_frame(reader.into_inner(), options.frame, name, |e| {
+            decode_error(name, e, &encoding, &cancel)
+        })
+        .map(|(img, _)| img)?
+    } else {
+        decode_checked(reader, name, |e| decode_error(name, e, &encoding, &cancel))?
+    };
+    let img = shrink_large(img, &options.preset);
+
+    let extension = options
+        .preset
+        .format
+        .extension(input_format.extensions_str()[0]);
+    let destination = name.with_extension(extension);
+    let format =
+        ImageFormat::from_path(&destination).map_err(|e| ArtCoverError::encode(&destination, e))?;
+
+    let mut warnings = encoding.warnings();
+    let provenance = || Ok(Provenance::of_bytes(source, input, &options.preset.name));
+    let encoded = fit_and_encode(
+        img,
+        &destination,
+        provenance,
+        &mut warnings,
+        options,
+        &mut |_| {},
+        &cancel,
+    )?;
+    tracing::info!(bytes = encoded.bytes.len(), "converted");
+
+    Ok(EncodedImage {
+        bytes: encoded.bytes,
+        format,
+        warnings,
+    })
+}
+
+struct Encoded {
+    bytes: Vec<u8>,
+    upscaled: bool,
+    auto_quality: Option<u8>,
+    png_saved: Option<u64>,
+}
+
+// Fit and encode in the format of `destination`, which is not written
+fn fit_and_encode(
+    img: DynamicImage,
+    destination: &Path,
+    provenance: impl FnOnce() -> Result<Provenance, ArtCoverError>,
+    warnings: &mut Vec<String>,
+    options: &ProcessOptions,
+    progress: &mut dyn FnMut(f32),
+    cancel: &CancelToken,
+) -> Result<Encoded, ArtCoverError> {
+    let resize_span = tracing::debug_span!("resize").entered();
+    let (img, upscaled) = fit(img, options, warnings, cancel)?;
+    tracing::debug!(
+        width = img.width(),
+        height = img.height(),
+        upscaled,
+        "resized"
+    );
+    drop(resize_span);
+    progress(Stage::Resize.completed_at());
+    cancel.check()?;
+
+    let encode_span = tracing::debug_span!("encode").entered();
+    let (mut bytes, auto_quality) = encode(img, destination, options)?;
+
+    let mut png_saved = None;
+    if options.optimize_png && ImageFormat::from_path(destination).ok() == Some(ImageFormat::Png) {
+        cancel.check()?;
+        let optimized = encode::optimize_png(&bytes).unwrap_or_else(|| bytes.clone());
+        png_saved = Some((bytes.len() - optimized.len()) as u64);
+        bytes = optimized;
+    }
+    // After the optimization, which drops text chunks
+    if options.provenance
+        && let Ok(format) = ImageFormat::from_path(destination)
+    {
+        match provenance() {
+            Ok(provenance) => bytes = provenance::embed(bytes, format, &provenance),
+            Err(e) => warnings.push(tf("Provenance not written: {}", &[&e.user_message()])),
+        }
+    }
+    tracing::debug!(bytes = bytes.len(), ?auto_quality, ?png_saved, "encoded");
+    drop(encode_span);
+    progress(Stage::Encode.completed_at());
+    cancel.check()?;
+
+    Ok(Encoded {
+        bytes,
+        upscaled,
+        auto_quality,
+        png_saved,
+    })
+}
+
+// Load image into memory, reporting how much of the file has been read.
+// Animations give the still at `frame`, along with their frame count.
+fn decode(
+    path: &Path,
+    encoding: &InputEncoding,
+    frame: usize,
+    progress: &mut dyn FnMut(f32),
+    cancel: &CancelToken,
+) -> Result<(DynamicImage, usize), ArtCoverError> {
+    let file = File::open(path).map_err(|e| ArtCoverError::io(path, e))?;
+    let total = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let source = TrackedReader {
+        inner: file,
+        read: 0,
+        total,
+        progress,
+        cancel,
+    };
+
+    let mut reader = ImageReader::new(BufReader::new(source));
+    if let Ok(format) = ImageFormat::from_path(path) {
+        reader.set_format(format);
+    }
+    let reader = reader
+        .with_guessed_format()
+        .map_err(|e| ArtCoverError::io(path, e))?;
+
+    if reader.format() == Some(ImageFormat::Gif) {
+        return decode_gif_frame(reader.into_inner(), frame, path, |e| {
+            decode_error(path, e, encoding, cancel)
+        });
+    }
+    decode_checked(reader, path, |e| decode_error(path, e, encoding, cancel)).map(|img| (img, 1))
+}
+
+// Whole image of `path`, refusing sizes that would not fit in memory
+pub fn open_bitmap(path: &Path) -> Result<DynamicImage, ArtCoverError> {
+    let reader = ImageReader::open(path)
+        .map_err(|e| ArtCoverError::io(path, e))?
+        .with_guessed_format()
+        .map_err(|e| ArtCoverError::io(path, e))?;
+    decode_checked(reader, path, |e| ArtCoverError::decode(path, e))
+}
+
+// Artwork held in memory (tags of `path`), with the same limits as files
+// and shrunk the same way when very large
+pub(crate) fn decode_artwork(
+    bytes: &[u8],
+    path: &Path,
+    preset: &Preset,
+) -> Result<DynamicImage, ArtCoverError> {
+    let reader = ImageReader::new(io::Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(|e| ArtCoverError::io(path, e))?;
+    let img = decode_checked(reader, path, |e| ArtCoverError::decode(path, e))?;
+    Ok(shrink_large(img, preset))
+}
+
+fn decode_checked<R: BufRead + Seek>(
+    mut reader: ImageReader<R>,
+    path: &Path,
+    error: impl Fn(ImageError) -> ArtCoverError,
+) -> Result<DynamicImage, ArtCoverError> {
+    reader.limits(limits());
+    let decoder = reader.into_decoder().map_err(&error)?;
+    check_size(path, &decoder)?;
+    DynamicImage::from_decoder(decoder).map_err(error)
+}
+
+// Largest images decoded: a 600 megapixel scan in 16-bit color fits, a
+// header claiming more is more likely broken than a real scan
+const MAX_PIXELS: u64 = 1_000_000_000;
+const MAX_DECODED_BYTES: u64 = 6 << 30;
+
+// The decoders' own default stops at 512 MiB, below a large scan
+fn limits() -> Limits {
+    let mut limits = Limits::default();
+    limits.max_alloc = Some(MAX_DECODED_BYTES);
+    limits
+}
+
+// Refused from the header alone, before anything is allocated
+fn check_size(path: &Path, decoder: &impl ImageDecoder) -> Result<(), ArtCoverError> {
+    let (width, height) = decoder.dimensions();
+    if width as u64 * height as u64 > MAX_PIXELS || decoder.total_bytes() > MAX_DECODED_BYTES {
+        return Err(ArtCoverError::TooLarge {
+            path: path.to_path_buf(),
+            width,
+            height,
+        });
+    }
+    Ok(())
+}
+
+// Images above this are shrunk right after decoding
+const LARGE_PIXELS: u64 = 40_000_000;
+// Shrunk images stay this many times the output size, so trimming and the
+// final filter still have detail to work with
+const WORKING_SCALE: u32 = 4;
+
+// First step of a two-step downscale: a fast box filter brings scans far
+// larger than any cover near the output size, then the full-size buffer is
+// freed before trimming, color changes and the final resize copy it
+fn shrink_large(img: DynamicImage, preset: &Preset) -> DynamicImage {
+    let (width, height) = (img.width(), img.height());
+    let (max_width, max_height) = preset.max_size();
+    let scale = f64::max(
+        (max_width * WORKING_SCALE) as f64 / width as f64,
+        (max_height * WORKING_SCALE) as f64 / height as f64,
+    );
+    if (width as u64 * height as u64) <= LARGE_PIXELS || scale >= 1.0 {
+        return img;
+    }
+    let size = |side: u32| ((side as f64 * scale).round() as u32).max(1);
+    tracing::debug!(width, height, "shrinking large image first");
+    img.thumbnail_exact(size(width), size(height))
+}
+
+fn decode_error(
+    path: &Path,
+    error: ImageError,
+    encoding: &InputEncoding,
+    cancel: &CancelToken,
+) -> ArtCoverError {
+    if cancel.is_cancelled() {
+        return ArtCoverError::Cancelled;
+    }
+    match ArtCoverError::decode(path, error) {
+        ArtCoverError::Decode { .. } | ArtCoverError::UnsupportedFormat(_)
+            if encoding.arithmetic_jpeg =>
+        {
+            ArtCoverError::UnsupportedFormat("arithmetic-coded JPEG".to_string())
+        }
+        error => error,
+    }
+}
+
+// Frames are composited on the full canvas; an index past the end gives
+// the last frame. The canvas is held to the same limits as other images.
+fn decode_gif_frame(
+    reader: impl BufRead + Seek,
+    index: usize,
+    path: &Path,
+    error: impl Fn(ImageError) -> ArtCoverError,
+) -> Result<(DynamicImage, usize), ArtCoverError> {
+    let mut decoder = GifDecoder::new(reader).map_err(&error)?;
+    decoder.set_limits(limits()).map_err(&error)?;
+    check_size(path, &decoder)?;
+
+    let mut chosen = None;
+    let mut count = 0;
+    for frame in decoder.into_frames() {
+        let frame = frame.map_err(&error)?;
+        if count <= index {
+            chosen = Some(frame);
+        }
+        count += 1;
+    }
+
+    let frame = chosen.ok_or_else(|| {
+        error(ImageError::Parameter(ParameterError::from_kind(
+            ParameterErrorKind::NoMoreData,
+        )))
+    })?;
+    Ok((DynamicImage::ImageRgba8(frame.into_buffer()), count))
+}
+
+// Trim, resize and reduce colors for the target device
+fn fit(
+    mut img: DynamicImage,
+    options: &ProcessOptions,
+    warnings: &mut Vec<String>,
+    cancel: &CancelToken,
+) -> Result<(DynamicImage, bool), ArtCoverError> {
+    // Remove scan margins before fitting the artwork
+    if options.auto_trim {
+        img = trim::trim_borders(&img, options.trim_tolerance);
+        cancel.check()?;
+    }
+
+    if img.width() == 0 || img.height() == 0 {
+        return Err(ArtCoverError::Resize("image has no pixels".to_string()));
+    }
+
+    // Apply redimension
+    let preset = &options.preset;
+    let filter = resize::filter(options.deterministic);
+    let (source_width, source_height) = (img.width(), img.height());
+    let fitted = resize::fit(img, options.resize_mode, options.upscale, preset, filter);
+    let mut img = fitted.image;
+
+    if fitted.upscaled {
+        warnings.push(tf(
+            "Upscaled from {}x{}, details may look soft",
+            &[&source_width, &source_height],
+        ));
+    }
+
+    // Compensate the color cast of the screen, before colors are reduced
+    // to what it can show
+    if let Some(lut) = &preset.lut {
+        cancel.check()?;
+        img = Lut::load(lut)?.apply(&img);
+    }
+
+    // Colors asked for, then what the screen can show of them
+    if options.color_mode != ColorMode::Full {
+        cancel.check()?;
+        img = quantize::reduce_colors(img, options.color_mode, options.palette_colors);
+    }
+
+    // Match the color depth of the target screen
+    if let Some(depth) = preset.bit_depth {
+        cancel.check()?;
+        img = quantize::to_bit_depth(&img, depth);
+    }
+
+    Ok((img, fitted.upscaled))
+}
+
+// Encoded bytes, plus the quality picked when searching automatically
+fn encode(
+    mut img: DynamicImage,
+    new_path: &Path,
+    options: &ProcessOptions,
+) -> Result<(Vec<u8>, Option<u8>), ArtCoverError> {
+    // JPEG has no alpha channel
+    let is_jpeg = ImageFormat::from_path(new_path).ok() == Some(ImageFormat::Jpeg);
+    if is_jpeg && img.color().has_alpha() {
+        img = DynamicImage::ImageRgb8(img.to_rgb8());
+    }
+
+    let settings = if options.deterministic {
+        EncodeSettings::PINNED
+    } else {
+        EncodeSettings::with_quality(options.jpeg_quality)
+    };
+
+    // The search is deterministic too, but the pinned mode means pinned
+    match options.auto_quality {
+        Some(target) if is_jpeg && !options.deterministic => {
+            quality::find_jpeg_quality(&img, new_path, target, &settings)
+                .map(|(quality, bytes)| (bytes, Some(quality)))
+                .map_err(|e| ArtCoverError::encode(new_path, e))
+        }
+        _ => encode::encode(&img, new_path, &settings)
+            .map(|bytes| (bytes, None))
+            .map_err(|e| ArtCoverError::encode(new_path, e)),
+    }
+}
+
+// Reader reporting decode progress and aborting the decoder on cancel
+struct TrackedReader<'a, R> {
+    inner: R,
+    read: u64,
+    total: u64,
+    progress: &'a mut dyn FnMut(f32),
+    cancel: &'a CancelToken,
+}
+
+impl<R: Read> Read for TrackedReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.cancel.is_cancelled() {
+            // Not Interrupted: read_exact would retry that one forever
+            return Err(io::Error::other("cancelled"));
+        }
+
+        let count = self.inner.read(buf)?;
+        self.read += count as u64;
+
+        if self.total > 0 {
+            let share = (self.read as f32 / self.total as f32).min(1.0);
+            let stage = Stage::Decode;
+            (self.progress)(
+                stage.started_at() + share * (stage.completed_at() - stage.started_at()),
+            );
+        }
+
+        Ok(count)
+    }
+}
+
+impl<R: Seek> Seek for TrackedReader<'_, R> {
+    fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+        let offset = self.inner.seek(position)?;
+        self.read = offset;
+        Ok(offset)
+    }
+}
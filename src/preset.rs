@@ -0,0 +1,144 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::PathBuf;
+
+// Output size rule of a preset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TargetSize {
+    // Original iPod art: large covers to 300x300, smaller ones kept
+    Classic,
+    // Fit within the given bounds, smaller images are kept as they are
+    Max(u32, u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OutputFormat {
+    SameAsInput,
+    Jpeg,
+    Png,
+    Bmp,
+}
+
+impl OutputFormat {
+    pub fn extension<'a>(&self, input: &'a str) -> &'a str {
+        match self {
+            // Players do not show GIF, TIFF, SVG or PDF; animations, scans,
+            // drawings and booklet pages become PNG stills
+            OutputFormat::SameAsInput
+                if ["gif", "tif", "tiff", "svg", "pdf"]
+                    .iter()
+                    .any(|format| input.eq_ignore_ascii_case(format)) =>
+            {
+                "png"
+            }
+            OutputFormat::SameAsInput => input,
+            OutputFormat::Jpeg => "jpg",
+            OutputFormat::Png => "png",
+            OutputFormat::Bmp => "bmp",
+        }
+    }
+}
+
+// Named set of output requirements for a device or theme
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Preset {
+    pub name: String,
+    pub size: TargetSize,
+    pub format: OutputFormat,
+    // Colors are reduced to this LCD depth (bits per pixel) when set
+    pub bit_depth: Option<u8>,
+    // Output names are transliterated to ASCII for firmware that cannot
+    // show other scripts
+    #[serde(default)]
+    pub ascii_names: bool,
+    // .cube table pre-compensating the color cast of the device screen
+    #[serde(default)]
+    pub lut: Option<PathBuf>,
+}
+
+impl Preset {
+    pub fn target_size(&self, width: u32, height: u32) -> (u32, u32) {
+        match self.size {
+            // Anything up to 300x300 is shown as it is; squeezing a
+            // 250x250 cover into 200x200 only lost detail
+            TargetSize::Classic => {
+                if width > 300 || height > 300 {
+                    (300, 300)
+                } else {
+                    (width, height)
+                }
+            }
+            TargetSize::Max(max_width, max_height) => {
+                if width <= max_width && height <= max_height {
+                    (width, height)
+                } else {
+                    (max_width, max_height)
+                }
+            }
+        }
+    }
+}
+
+impl Preset {
+    // Bounds large images are brought down to
+    pub fn max_size(&self) -> (u32, u32) {
+        match self.size {
+            TargetSize::Classic => (300, 300),
+            TargetSize::Max(max_width, max_height) => (max_width, max_height),
+        }
+    }
+
+    // Size small images are brought up to when upscaling is enabled
+    pub fn upscale_size(&self) -> (u32, u32) {
+        match self.size {
+            TargetSize::Classic => (200, 200),
+            TargetSize::Max(max_width, max_height) => (max_width, max_height),
+        }
+    }
+}
+
+impl Default for Preset {
+    fn default() -> Self {
+        Self {
+            name: "iPod".to_string(),
+            size: TargetSize::Classic,
+            format: OutputFormat::SameAsInput,
+            bit_depth: None,
+            ascii_names: false,
+            lut: None,
+        }
+    }
+}
+
+impl fmt::Display for Preset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+// Preset named `name`, ignoring case. Imported presets shadow built-in ones
+// of the same name.
+pub fn find(name: &str, imported: &[Preset]) -> Option<Preset> {
+    imported
+        .iter()
+        .cloned()
+        .chain(builtin())
+        .find(|preset| preset.name.eq_ignore_ascii_case(name.trim()))
+}
+
+// Presets available without importing anything
+pub fn builtin() -> Vec<Preset> {
+    vec![
+        Preset::default(),
+        Preset {
+            name: "iPod (JPEG)".to_string(),
+            format: OutputFormat::Jpeg,
+            ..Preset::default()
+        },
+        Preset {
+            name: "iPod (PNG)".to_string(),
+            format: OutputFormat::Png,
+            ..Preset::default()
+        },
+    ]
+}
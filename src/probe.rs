@@ -0,0 +1,247 @@
+use crate::ArtCoverError;
+use crate::i18n::t;
+use image::{ColorType, ImageDecoder, ImageFormat, ImageReader};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+// Header bytes read when looking for the image parameters
+const HEADER_LIMIT: u64 = 256 * 1024;
+
+// Encoding details that older device decoders choke on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InputEncoding {
+    pub interlaced_png: bool,
+    pub cmyk_jpeg: bool,
+    pub arithmetic_jpeg: bool,
+    // Old iPod firmware only shows baseline JPEGs
+    pub progressive_jpeg: bool,
+}
+
+impl InputEncoding {
+    // Whether the source must be re-encoded even if its size already fits.
+    // Progressive JPEGs are only rewritten when `baseline_jpeg` is set.
+    pub fn needs_normalization(&self, baseline_jpeg: bool) -> bool {
+        self.interlaced_png
+            || self.cmyk_jpeg
+            || self.arithmetic_jpeg
+            || (baseline_jpeg && self.progressive_jpeg)
+    }
+
+    // What normalizing the source changes, for the per-file warnings
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if self.interlaced_png {
+            warnings.push(t("Interlaced PNG saved non-interlaced").to_string());
+        }
+        if self.cmyk_jpeg {
+            warnings.push(t("CMYK JPEG converted to RGB").to_string());
+        }
+        if self.arithmetic_jpeg {
+            warnings.push(t("Arithmetic-coded JPEG re-encoded with Huffman coding").to_string());
+        }
+        if self.progressive_jpeg {
+            warnings.push(t("Progressive JPEG saved as baseline").to_string());
+        }
+        warnings
+    }
+}
+
+// What a file holds, read from its headers without decoding the pixels.
+// Fields are None when the file does not say or is not a bitmap.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metadata {
+    pub format: Option<ImageFormat>,
+    pub dimensions: Option<(u32, u32)>,
+    pub color: Option<ColorType>,
+    // Horizontal and vertical dots per inch
+    pub dpi: Option<(f32, f32)>,
+    // Description of the embedded ICC profile: None without one, empty
+    // when it has no description
+    pub icc_profile: Option<String>,
+    pub file_size: u64,
+    pub encoding: InputEncoding,
+}
+
+pub fn inspect(path: &Path) -> Result<Metadata, ArtCoverError> {
+    let file_size = std::fs::metadata(path)
+        .map_err(|e| ArtCoverError::io(path, e))?
+        .len();
+    let header = read_header(path).map_err(|e| ArtCoverError::io(path, e))?;
+
+    let mut metadata = Metadata {
+        format: image::guess_format(&header).ok(),
+        dimensions: None,
+        color: None,
+        dpi: dpi(&header),
+        icc_profile: None,
+        file_size,
+        encoding: probe_bytes(&header),
+    };
+    // Only the headers are parsed; an undecodable file keeps its Nones
+    if let Ok(mut decoder) = ImageReader::open(path)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(image::ImageError::IoError)
+        .and_then(|reader| reader.into_decoder())
+    {
+        metadata.dimensions = Some(decoder.dimensions());
+        metadata.color = Some(decoder.color_type());
+        metadata.icc_profile = decoder
+            .icc_profile()
+            .ok()
+            .flatten()
+            .map(|profile| icc_description(&profile).unwrap_or_default());
+    }
+    Ok(metadata)
+}
+
+pub fn probe(path: &Path) -> io::Result<InputEncoding> {
+    Ok(probe_bytes(&read_header(path)?))
+}
+
+fn read_header(path: &Path) -> io::Result<Vec<u8>> {
+    let mut header = Vec::new();
+    File::open(path)?
+        .take(HEADER_LIMIT)
+        .read_to_end(&mut header)?;
+    Ok(header)
+}
+
+pub fn probe_bytes(bytes: &[u8]) -> InputEncoding {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        probe_png(bytes)
+    } else if bytes.starts_with(&[0xFF, 0xD8]) {
+        probe_jpeg(bytes)
+    } else {
+        InputEncoding::default()
+    }
+}
+
+// IHDR is always the first chunk; its last byte is the interlace method
+fn probe_png(bytes: &[u8]) -> InputEncoding {
+    InputEncoding {
+        interlaced_png: bytes.get(28).is_some_and(|method| *method != 0),
+        ..Default::default()
+    }
+}
+
+// Walk the marker segments up to the start of frame
+fn probe_jpeg(bytes: &[u8]) -> InputEncoding {
+    let mut encoding = InputEncoding::default();
+    let mut offset = 2;
+
+    while offset + 4 <= bytes.len() {
+        if bytes[offset] != 0xFF {
+            break;
+        }
+        let marker = bytes[offset + 1];
+        let length = u16::from_be_bytes([bytes[offset + 2], bytes[offset + 3]]) as usize;
+        // The length counts its own two bytes; less is a damaged file
+        if length < 2 {
+            break;
+        }
+        let segment = bytes
+            .get(offset + 4..(offset + 2 + length).min(bytes.len()))
+            .unwrap_or_default();
+
+        match marker {
+            // SOF0..SOF15 except DHT, JPG and DAC
+            0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => {
+                encoding.arithmetic_jpeg = marker >= 0xC9;
+                // SOF2, SOF6, SOF10 and SOF14 are the progressive variants
+                encoding.progressive_jpeg = matches!(marker, 0xC2 | 0xC6 | 0xCA | 0xCE);
+                // precision, height, width, then the component count
+                encoding.cmyk_jpeg = segment.get(5).is_some_and(|components| *components == 4);
+                break;
+            }
+            // Start of scan without a frame header: nothing more to learn
+            0xDA => break,
+            _ => {}
+        }
+
+        offset += 2 + length;
+    }
+
+    encoding
+}
+
+// JFIF density of JPEGs, pHYs chunk of PNGs
+fn dpi(bytes: &[u8]) -> Option<(f32, f32)> {
+    const INCH: f32 = 2.54;
+    let be16 = |at: usize| {
+        bytes
+            .get(at..at + 2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]))
+    };
+    let be32 = |at: usize| {
+        bytes
+            .get(at..at + 4)
+            .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    };
+
+    if bytes.starts_with(&[0xFF, 0xD8, 0xFF, 0xE0]) && bytes.get(6..11) == Some(b"JFIF\0") {
+        // Units: 0 only gives the aspect ratio, 1 dots per inch, 2 per cm
+        let (x, y) = (be16(14)? as f32, be16(16)? as f32);
+        return match bytes.get(13)? {
+            1 => Some((x, y)),
+            2 => Some((x * INCH, y * INCH)),
+            _ => None,
+        };
+    }
+
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        let mut offset = 8;
+        while let Some(length) = be32(offset) {
+            let kind = bytes.get(offset + 4..offset + 8)?;
+            if kind == b"IDAT" {
+                break;
+            }
+            // Pixels per unit, then the unit: 1 is the meter
+            if kind == b"pHYs" && bytes.get(offset + 16) == Some(&1) {
+                let per_meter = |at| be32(at).map(|ppm| ppm as f32 * INCH / 100.0);
+                return Some((per_meter(offset + 8)?, per_meter(offset + 12)?));
+            }
+            offset += 12 + length as usize;
+        }
+    }
+    None
+}
+
+// Profile description: the 'desc' tag, ASCII in version 2 profiles and
+// UTF-16 in version 4 ones
+fn icc_description(profile: &[u8]) -> Option<String> {
+    let be32 = |at: usize| {
+        profile
+            .get(at..at + 4)
+            .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as usize)
+    };
+
+    let tags = be32(128)?;
+    let (offset, size) = (0..tags.min(256))
+        .map(|index| 132 + index * 12)
+        .find(|at| profile.get(*at..*at + 4) == Some(b"desc"))
+        .and_then(|at| Some((be32(at + 4)?, be32(at + 8)?)))?;
+    let tag = profile.get(offset..offset.checked_add(size)?)?;
+
+    let text = match tag.get(..4)? {
+        b"desc" => {
+            let length = u32::from_be_bytes(tag.get(8..12)?.try_into().ok()?) as usize;
+            let ascii = tag.get(12..12 + length)?;
+            String::from_utf8_lossy(ascii).into_owned()
+        }
+        b"mluc" => {
+            // First record: language, country, length and offset
+            let length = u32::from_be_bytes(tag.get(20..24)?.try_into().ok()?) as usize;
+            let start = u32::from_be_bytes(tag.get(24..28)?.try_into().ok()?) as usize;
+            let units: Vec<u16> = tag
+                .get(start..start + length)?
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        _ => return None,
+    };
+    let text = text.trim_end_matches('\0').trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
@@ -0,0 +1,200 @@
+use crate::ArtCoverError;
+use image::ImageFormat;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+// Namespace of the XMP properties without a standard equivalent
+const XMP_NAMESPACE: &str = "urn:artcover:provenance:1.0#";
+
+// Where an output came from and how it was made. No timestamp, so
+// reproducible outputs stay byte-identical.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Provenance {
+    // File name only; the folders say more about the machine than the cover
+    pub source: String,
+    pub source_sha256: String,
+    pub app_version: &'static str,
+    pub preset: String,
+}
+
+impl Provenance {
+    pub fn of(source: &Path, preset: &str) -> Result<Self, ArtCoverError> {
+        // Streamed: sources can be whole audio files
+        let mut file = File::open(source).map_err(|e| ArtCoverError::io(source, e))?;
+        let mut hasher = Sha256::new();
+        let mut buffer = [0; 64 * 1024];
+        loop {
+            match file.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(count) => hasher.update(&buffer[..count]),
+                Err(e) => return Err(ArtCoverError::io(source, e)),
+            }
+        }
+
+        Ok(Self {
+            source: crate::error::file_name(source),
+            source_sha256: hex(&hasher.finalize()),
+            app_version: env!("CARGO_PKG_VERSION"),
+            preset: preset.to_string(),
+        })
+    }
+
+    // Source that is not a file, `name` standing for it
+    pub fn of_bytes(name: &str, bytes: &[u8], preset: &str) -> Self {
+        Self {
+            source: name.to_string(),
+            source_sha256: hex(&Sha256::digest(bytes)),
+            app_version: env!("CARGO_PKG_VERSION"),
+            preset: preset.to_string(),
+        }
+    }
+
+    // One line for the comment fields viewers show
+    pub fn comment(&self) -> String {
+        format!(
+            "Made by ArtCover {} from {} (SHA-256 {}) with the {} preset",
+            self.app_version, self.source, self.source_sha256, self.preset
+        )
+    }
+
+    fn xmp(&self) -> String {
+        format!(
+            concat!(
+                "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>",
+                "<x:xmpmeta xmlns:x=\"adobe:ns:meta/\">",
+                "<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">",
+                "<rdf:Description rdf:about=\"\"",
+                " xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\"",
+                " xmlns:dc=\"http://purl.org/dc/elements/1.1/\"",
+                " xmlns:artcover=\"{}\"",
+                " xmp:CreatorTool=\"ArtCover {}\"",
+                " dc:source=\"{}\"",
+                " artcover:SourceSHA256=\"{}\"",
+                " artcover:Preset=\"{}\"/>",
+                "</rdf:RDF></x:xmpmeta><?xpacket end=\"r\"?>"
+            ),
+            XMP_NAMESPACE,
+            escape(self.app_version),
+            escape(&self.source),
+            self.source_sha256,
+            escape(&self.preset)
+        )
+    }
+}
+
+// Encoded JPEG or PNG with the provenance written into it: an XMP packet
+// and a comment. Other formats have nowhere to put it and are returned
+// unchanged, as are files that do not parse.
+pub fn embed(bytes: Vec<u8>, format: ImageFormat, provenance: &Provenance) -> Vec<u8> {
+    match format {
+        ImageFormat::Jpeg => embed_jpeg(bytes, provenance),
+        ImageFormat::Png => embed_png(bytes, provenance),
+        _ => bytes,
+    }
+}
+
+// APP1 XMP and COM segments right after SOI, or after the JFIF APP0
+// segment that decoders expect first
+fn embed_jpeg(bytes: Vec<u8>, provenance: &Provenance) -> Vec<u8> {
+    if !bytes.starts_with(&[0xFF, 0xD8]) {
+        return bytes;
+    }
+    let mut at = 2;
+    if bytes.get(2..4) == Some(&[0xFF, 0xE0])
+        && let Some(length) = bytes.get(4..6)
+    {
+        at += 2 + u16::from_be_bytes([length[0], length[1]]) as usize;
+    }
+    if at > bytes.len() {
+        return bytes;
+    }
+
+    let mut xmp = b"http://ns.adobe.com/xap/1.0/\0".to_vec();
+    xmp.extend_from_slice(provenance.xmp().as_bytes());
+    let segments = [
+        jpeg_segment(0xE1, &xmp),
+        jpeg_segment(0xFE, provenance.comment().as_bytes()),
+    ];
+    let Some(segments) = segments.into_iter().collect::<Option<Vec<_>>>() else {
+        return bytes;
+    };
+
+    let mut out = Vec::with_capacity(bytes.len() + segments.iter().map(Vec::len).sum::<usize>());
+    out.extend_from_slice(&bytes[..at]);
+    for segment in segments {
+        out.extend_from_slice(&segment);
+    }
+    out.extend_from_slice(&bytes[at..]);
+    out
+}
+
+// None when the data does not fit the 16-bit segment length
+fn jpeg_segment(marker: u8, data: &[u8]) -> Option<Vec<u8>> {
+    let length = u16::try_from(data.len() + 2).ok()?;
+    let mut segment = vec![0xFF, marker];
+    segment.extend_from_slice(&length.to_be_bytes());
+    segment.extend_from_slice(data);
+    Some(segment)
+}
+
+// iTXt chunks (UTF-8, unlike tEXt) right after IHDR
+fn embed_png(bytes: Vec<u8>, provenance: &Provenance) -> Vec<u8> {
+    const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+    // Signature, then IHDR: length, type, 13 bytes of data and the CRC
+    const AFTER_IHDR: usize = 8 + 4 + 4 + 13 + 4;
+    if !bytes.starts_with(&SIGNATURE) || bytes.get(12..16) != Some(b"IHDR") {
+        return bytes;
+    }
+
+    let mut out = Vec::with_capacity(bytes.len() + 1024);
+    out.extend_from_slice(&bytes[..AFTER_IHDR]);
+    out.extend_from_slice(&png_itxt("XML:com.adobe.xmp", &provenance.xmp()));
+    out.extend_from_slice(&png_itxt("Comment", &provenance.comment()));
+    out.extend_from_slice(&bytes[AFTER_IHDR..]);
+    out
+}
+
+fn png_itxt(keyword: &str, text: &str) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend_from_slice(keyword.as_bytes());
+    // Keyword end, no compression, no language tag or translated keyword
+    data.extend_from_slice(&[0, 0, 0, 0, 0]);
+    data.extend_from_slice(text.as_bytes());
+
+    let mut chunk = Vec::with_capacity(data.len() + 12);
+    chunk.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    chunk.extend_from_slice(b"iTXt");
+    chunk.extend_from_slice(&data);
+    let crc = crc32(&chunk[4..]);
+    chunk.extend_from_slice(&crc.to_be_bytes());
+    chunk
+}
+
+// CRC-32 of PNG chunks (ISO 3309), over the type and data
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
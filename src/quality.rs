@@ -0,0 +1,102 @@
+use crate::encode::{self, EncodeSettings};
+use image::{DynamicImage, GrayImage};
+use std::path::Path;
+
+// Default perceptual target for automatic JPEG quality
+pub const DEFAULT_TARGET_SSIM: f32 = 0.97;
+
+// Range searched for the lowest acceptable quality
+const MIN_QUALITY: u8 = 30;
+const MAX_QUALITY: u8 = 95;
+
+// SSIM over 8x8 windows
+const WINDOW: u32 = 8;
+const STRIDE: u32 = 4;
+
+// Lowest JPEG quality whose decoded result still reaches `target` SSIM
+// against the original; returns the quality and the encoded bytes. Every
+// candidate is encoded with `settings` but for the quality.
+pub fn find_jpeg_quality(
+    img: &DynamicImage,
+    path: &Path,
+    target: f32,
+    settings: &EncodeSettings,
+) -> Result<(u8, Vec<u8>), image::ImageError> {
+    let reference = img.to_luma8();
+    let (mut low, mut high) = (MIN_QUALITY, MAX_QUALITY);
+    let mut best = None;
+    let with_quality = |jpeg_quality| EncodeSettings {
+        jpeg_quality,
+        ..*settings
+    };
+
+    while low <= high {
+        let quality = low + (high - low) / 2;
+        let bytes = encode::encode(img, path, &with_quality(quality))?;
+        let decoded = image::load_from_memory(&bytes)?.to_luma8();
+
+        if ssim(&reference, &decoded) >= target as f64 {
+            best = Some((quality, bytes));
+            if quality == MIN_QUALITY {
+                break;
+            }
+            high = quality - 1;
+        } else {
+            low = quality + 1;
+        }
+    }
+
+    match best {
+        Some(found) => Ok(found),
+        // Not even the top of the range reaches the target
+        None => Ok((
+            MAX_QUALITY,
+            encode::encode(img, path, &with_quality(MAX_QUALITY))?,
+        )),
+    }
+}
+
+// Mean structural similarity of two grayscale images of the same size
+pub fn ssim(a: &GrayImage, b: &GrayImage) -> f64 {
+    const C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+    const C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+    let (width, height) = a.dimensions();
+    if width < WINDOW || height < WINDOW || a.dimensions() != b.dimensions() {
+        return if a == b { 1.0 } else { 0.0 };
+    }
+
+    let mut total = 0.0;
+    let mut windows = 0;
+
+    for y in (0..=height - WINDOW).step_by(STRIDE as usize) {
+        for x in (0..=width - WINDOW).step_by(STRIDE as usize) {
+            let (mut sum_a, mut sum_b) = (0.0, 0.0);
+            let (mut sum_aa, mut sum_bb, mut sum_ab) = (0.0, 0.0, 0.0);
+
+            for dy in 0..WINDOW {
+                for dx in 0..WINDOW {
+                    let pa = a.get_pixel(x + dx, y + dy).0[0] as f64;
+                    let pb = b.get_pixel(x + dx, y + dy).0[0] as f64;
+                    sum_a += pa;
+                    sum_b += pb;
+                    sum_aa += pa * pa;
+                    sum_bb += pb * pb;
+                    sum_ab += pa * pb;
+                }
+            }
+
+            let n = (WINDOW * WINDOW) as f64;
+            let (mean_a, mean_b) = (sum_a / n, sum_b / n);
+            let var_a = sum_aa / n - mean_a * mean_a;
+            let var_b = sum_bb / n - mean_b * mean_b;
+            let covariance = sum_ab / n - mean_a * mean_b;
+
+            total += ((2.0 * mean_a * mean_b + C1) * (2.0 * covariance + C2))
+                / ((mean_a * mean_a + mean_b * mean_b + C1) * (var_a + var_b + C2));
+            windows += 1;
+        }
+    }
+
+    total / windows as f64
+}
@@ -0,0 +1,218 @@
+use crate::i18n::t;
+use image::{DynamicImage, GrayImage, RgbImage};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+pub const DEFAULT_PALETTE_COLORS: u16 = 16;
+pub const MAX_PALETTE_COLORS: u16 = 256;
+
+// Colors of the output, chosen by the user for screens that do better
+// with fewer of them (iPod mini, monochrome firmware)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ColorMode {
+    #[default]
+    Full,
+    Grayscale,
+    // The colors that best fit the image, as many as `palette_colors`
+    Palette,
+}
+
+impl ColorMode {
+    pub const ALL: [ColorMode; 3] = [ColorMode::Full, ColorMode::Grayscale, ColorMode::Palette];
+}
+
+impl fmt::Display for ColorMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            ColorMode::Full => "Full color",
+            ColorMode::Grayscale => "Grayscale",
+            ColorMode::Palette => "Limited palette",
+        };
+        write!(f, "{}", t(label))
+    }
+}
+
+// `img` in `mode`; palettes are dithered with Floyd-Steinberg
+pub fn reduce_colors(img: DynamicImage, mode: ColorMode, colors: u16) -> DynamicImage {
+    match mode {
+        ColorMode::Full => img,
+        ColorMode::Grayscale => img.grayscale(),
+        ColorMode::Palette => {
+            let rgb = img.to_rgb8();
+            let colors = colors.clamp(2, MAX_PALETTE_COLORS) as usize;
+            DynamicImage::ImageRgb8(dither_palette(&rgb, &palette(&rgb, colors)))
+        }
+    }
+}
+
+// Reduce colors to what an LCD of `depth` bits per pixel can show, with
+// Floyd-Steinberg dithering to hide the banding
+pub fn to_bit_depth(img: &DynamicImage, depth: u8) -> DynamicImage {
+    match depth {
+        1 => DynamicImage::ImageLuma8(dither_gray(&img.to_luma8(), 2)),
+        2 => DynamicImage::ImageLuma8(dither_gray(&img.to_luma8(), 4)),
+        8 => DynamicImage::ImageRgb8(dither_rgb(&img.to_rgb8(), [8, 8, 4])),
+        12 => DynamicImage::ImageRgb8(dither_rgb(&img.to_rgb8(), [16, 16, 16])),
+        16 => DynamicImage::ImageRgb8(dither_rgb(&img.to_rgb8(), [32, 64, 32])),
+        18 => DynamicImage::ImageRgb8(dither_rgb(&img.to_rgb8(), [64, 64, 64])),
+        _ => img.clone(),
+    }
+}
+
+// Dither every channel down to the given number of evenly spaced levels
+pub fn dither_rgb(img: &RgbImage, levels: [u32; 3]) -> RgbImage {
+    dither(img, |old| {
+        let mut new = [0f32; 3];
+        for channel in 0..3 {
+            new[channel] = nearest_level(old[channel], levels[channel]);
+        }
+        new
+    })
+}
+
+// Dither to the nearest colors of `palette`
+pub fn dither_palette(img: &RgbImage, palette: &[[u8; 3]]) -> RgbImage {
+    let palette: Vec<[f32; 3]> = palette.iter().map(|color| color.map(f32::from)).collect();
+    dither(img, |old| {
+        let distance = |color: &[f32; 3]| {
+            (0..3)
+                .map(|channel| (color[channel] - old[channel]).powi(2))
+                .sum::<f32>()
+        };
+        palette
+            .iter()
+            .copied()
+            .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+            .unwrap_or(old)
+    })
+}
+
+// Up to `colors` colors representing `img`, by median cut: the group of
+// pixels spanning the widest range of one channel is split at its median
+// until there are enough groups, and each group gives its average
+pub fn palette(img: &RgbImage, colors: usize) -> Vec<[u8; 3]> {
+    let mut groups: Vec<Vec<[u8; 3]>> = vec![img.pixels().map(|p| p.0).collect()];
+    while groups.len() < colors {
+        let widest = groups
+            .iter()
+            .enumerate()
+            .map(|(index, pixels)| {
+                let (channel, range) = widest_channel(pixels);
+                (index, channel, range)
+            })
+            .filter(|(_, _, range)| *range > 0)
+            .max_by_key(|(_, _, range)| *range);
+        // Every group is a single color: the image has no more
+        let Some((index, channel, _)) = widest else {
+            break;
+        };
+
+        let mut pixels = groups.swap_remove(index);
+        pixels.sort_unstable_by_key(|pixel| pixel[channel]);
+        let upper = pixels.split_off(pixels.len() / 2);
+        groups.push(pixels);
+        groups.push(upper);
+    }
+
+    groups
+        .iter()
+        .map(|pixels| {
+            let mut sum = [0u64; 3];
+            for pixel in pixels {
+                for channel in 0..3 {
+                    sum[channel] += pixel[channel] as u64;
+                }
+            }
+            let count = pixels.len().max(1) as u64;
+            sum.map(|total| (total / count) as u8)
+        })
+        .collect()
+}
+
+fn widest_channel(pixels: &[[u8; 3]]) -> (usize, u8) {
+    (0..3)
+        .map(|channel| {
+            let values = pixels.iter().map(|pixel| pixel[channel]);
+            let range = values.clone().max().unwrap_or(0) - values.min().unwrap_or(0);
+            (channel, range)
+        })
+        .max_by_key(|(_, range)| *range)
+        .unwrap_or((0, 0))
+}
+
+// Floyd-Steinberg with `nearest` picking the color each pixel becomes
+fn dither(img: &RgbImage, nearest: impl Fn([f32; 3]) -> [f32; 3]) -> RgbImage {
+    let (width, height) = img.dimensions();
+    let mut buffer: Vec<[f32; 3]> = img
+        .pixels()
+        .map(|p| [p.0[0] as f32, p.0[1] as f32, p.0[2] as f32])
+        .collect();
+
+    let mut output = RgbImage::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let index = (y * width + x) as usize;
+            let old = buffer[index];
+            let new = nearest(old);
+            output.put_pixel(x, y, image::Rgb(new.map(|v| v as u8)));
+
+            let error = [old[0] - new[0], old[1] - new[1], old[2] - new[2]];
+            spread_error(&mut buffer, width, height, x, y, |pixel, weight| {
+                for channel in 0..3 {
+                    pixel[channel] += error[channel] * weight;
+                }
+            });
+        }
+    }
+
+    output
+}
+
+pub fn dither_gray(img: &GrayImage, levels: u32) -> GrayImage {
+    let (width, height) = img.dimensions();
+    let mut buffer: Vec<f32> = img.pixels().map(|p| p.0[0] as f32).collect();
+
+    let mut output = GrayImage::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let index = (y * width + x) as usize;
+            let old = buffer[index];
+            let new = nearest_level(old, levels);
+            output.put_pixel(x, y, image::Luma([new as u8]));
+
+            let error = old - new;
+            spread_error(&mut buffer, width, height, x, y, |pixel, weight| {
+                *pixel += error * weight;
+            });
+        }
+    }
+
+    output
+}
+
+fn nearest_level(value: f32, levels: u32) -> f32 {
+    let step = 255.0 / (levels - 1) as f32;
+    ((value.clamp(0.0, 255.0) / step).round() * step).clamp(0.0, 255.0)
+}
+
+// Classic Floyd-Steinberg weights: 7/16 right, 3/16 down-left, 5/16 down,
+// 1/16 down-right
+fn spread_error<T>(
+    buffer: &mut [T],
+    width: u32,
+    height: u32,
+    x: u32,
+    y: u32,
+    mut add: impl FnMut(&mut T, f32),
+) {
+    let neighbours = [(1i64, 0i64, 7.0), (-1, 1, 3.0), (0, 1, 5.0), (1, 1, 1.0)];
+
+    for (dx, dy, weight) in neighbours {
+        let (nx, ny) = (x as i64 + dx, y as i64 + dy);
+        if nx < 0 || nx >= width as i64 || ny >= height as i64 {
+            continue;
+        }
+        let index = (ny as u32 * width + nx as u32) as usize;
+        add(&mut buffer[index], weight / 16.0);
+    }
+}
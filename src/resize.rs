@@ -0,0 +1,322 @@
+use crate::i18n::t;
+use crate::preset::Preset;
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImageView, Rgb, RgbImage, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// How non-square images are fitted into the target size
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ResizeMode {
+    #[default]
+    Stretch,
+    Crop,
+    // Black bars
+    Pad,
+    // Bars in the dominant color of the edges they touch
+    PadEdge,
+    // The image over a blurred copy stretched to the square
+    PadBlur,
+}
+
+impl ResizeMode {
+    pub const ALL: [ResizeMode; 5] = [
+        ResizeMode::Stretch,
+        ResizeMode::Crop,
+        ResizeMode::Pad,
+        ResizeMode::PadEdge,
+        ResizeMode::PadBlur,
+    ];
+
+    pub fn is_pad(self) -> bool {
+        matches!(
+            self,
+            ResizeMode::Pad | ResizeMode::PadEdge | ResizeMode::PadBlur
+        )
+    }
+}
+
+impl fmt::Display for ResizeMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            ResizeMode::Stretch => "Stretch",
+            ResizeMode::Crop => "Square crop",
+            ResizeMode::Pad => "Pad to square",
+            ResizeMode::PadEdge => "Pad with edge color",
+            ResizeMode::PadBlur => "Pad with blurred copy",
+        };
+        write!(f, "{}", t(label))
+    }
+}
+
+// Lanczos needs sin(), whose last bit can differ between platform math
+// libraries; Catmull-Rom is pure arithmetic and gives the same pixels
+// everywhere
+pub fn filter(deterministic: bool) -> FilterType {
+    if deterministic {
+        FilterType::CatmullRom
+    } else {
+        FilterType::Lanczos3
+    }
+}
+
+// How images smaller than the preset are brought up to size
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum UpscaleMode {
+    // Keep small images as they are
+    #[default]
+    Off,
+    Lanczos,
+    // Pixel doubling keeps hard edges crisp, the last step is a downscale
+    Integer,
+}
+
+impl UpscaleMode {
+    pub const ALL: [UpscaleMode; 3] =
+        [UpscaleMode::Off, UpscaleMode::Lanczos, UpscaleMode::Integer];
+}
+
+impl fmt::Display for UpscaleMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            UpscaleMode::Off => "Keep small images",
+            UpscaleMode::Lanczos => "Upscale (smooth)",
+            UpscaleMode::Integer => "Upscale (2x pixels)",
+        };
+        write!(f, "{}", t(label))
+    }
+}
+
+// Image brought to the preset size
+pub struct Fitted {
+    pub image: DynamicImage,
+    pub upscaled: bool,
+}
+
+// Bring the image to the preset size using the given mode
+pub fn fit(
+    img: DynamicImage,
+    mode: ResizeMode,
+    upscale: UpscaleMode,
+    preset: &Preset,
+    filter: FilterType,
+) -> Fitted {
+    let img = if mode == ResizeMode::Crop {
+        crop_square(&img)
+    } else {
+        img
+    };
+
+    let (width, height) = img.dimensions();
+
+    let (mut target_width, mut target_height) = if mode.is_pad() {
+        let side = width.max(height);
+        preset.target_size(side, side)
+    } else {
+        preset.target_size(width, height)
+    };
+
+    // Images the preset would leave untouched are small ones
+    let (min_width, min_height) = preset.upscale_size();
+    let upscaled = upscale != UpscaleMode::Off
+        && (target_width, target_height) == (width, height)
+        && width < min_width
+        && height < min_height;
+
+    let img = if upscaled {
+        (target_width, target_height) = (min_width, min_height);
+        if upscale == UpscaleMode::Integer {
+            double_until(img, min_width, min_height)
+        } else {
+            img
+        }
+    } else {
+        img
+    };
+
+    let image = if img.dimensions() == (target_width, target_height) {
+        img
+    } else if mode.is_pad() {
+        pad_to(&img, target_width, target_height, mode, filter)
+    } else {
+        img.resize_exact(target_width, target_height, filter)
+    };
+
+    Fitted { image, upscaled }
+}
+
+// Whether `fit` would return an image of this size unchanged
+pub fn is_unchanged(
+    width: u32,
+    height: u32,
+    mode: ResizeMode,
+    upscale: UpscaleMode,
+    preset: &Preset,
+) -> bool {
+    // Crop and pad always produce a square
+    if mode != ResizeMode::Stretch && width != height {
+        return false;
+    }
+
+    let (min_width, min_height) = preset.upscale_size();
+    let upscaled = upscale != UpscaleMode::Off && width < min_width && height < min_height;
+
+    preset.target_size(width, height) == (width, height) && !upscaled
+}
+
+// Size vector sources (SVG, PDF pages) are rendered at: what `fit` would
+// bring a large bitmap to. Stretched to the preset bounds, or keeping the
+// aspect ratio with the shorter side filling them for a square crop and
+// the longer one for padding.
+pub fn render_size(width: f32, height: f32, preset: &Preset, mode: ResizeMode) -> (u32, u32) {
+    let (max_width, max_height) = preset.max_size();
+    let scale_x = max_width as f32 / width;
+    let scale_y = max_height as f32 / height;
+    let scale = match mode {
+        ResizeMode::Stretch => return (max_width, max_height),
+        ResizeMode::Crop => scale_x.max(scale_y),
+        ResizeMode::Pad | ResizeMode::PadEdge | ResizeMode::PadBlur => scale_x.min(scale_y),
+    };
+
+    (
+        ((width * scale).round() as u32).max(1),
+        ((height * scale).round() as u32).max(1),
+    )
+}
+
+// Nearest-neighbour doubling until the image covers the target
+fn double_until(mut img: DynamicImage, width: u32, height: u32) -> DynamicImage {
+    while img.width() < width && img.height() < height {
+        img = img.resize_exact(img.width() * 2, img.height() * 2, FilterType::Nearest);
+    }
+    img
+}
+
+// Square part of an image, in its pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Square {
+    pub x: u32,
+    pub y: u32,
+    pub side: u32,
+}
+
+impl Square {
+    // The largest one, centered: what ResizeMode::Crop keeps
+    pub fn centered(width: u32, height: u32) -> Self {
+        let side = width.min(height);
+        Self {
+            x: (width - side) / 2,
+            y: (height - side) / 2,
+            side,
+        }
+    }
+
+    // Shrunk and moved as little as needed to lie inside the image
+    pub fn within(self, width: u32, height: u32) -> Self {
+        let side = self.side.clamp(1, width.min(height).max(1));
+        Self {
+            x: self.x.min(width.saturating_sub(side)),
+            y: self.y.min(height.saturating_sub(side)),
+            side,
+        }
+    }
+}
+
+// Keep the centered square of the image
+pub fn crop_square(img: &DynamicImage) -> DynamicImage {
+    let (width, height) = img.dimensions();
+    crop_to(img, Square::centered(width, height))
+}
+
+pub fn crop_to(img: &DynamicImage, square: Square) -> DynamicImage {
+    let (width, height) = img.dimensions();
+    let square = square.within(width, height);
+    img.crop_imm(square.x, square.y, square.side, square.side)
+}
+
+// Fit the image inside the target and fill the rest with the background
+// of a pad `mode`
+pub fn pad_to(
+    img: &DynamicImage,
+    width: u32,
+    height: u32,
+    mode: ResizeMode,
+    filter: FilterType,
+) -> DynamicImage {
+    let fitted = img.resize(width, height, filter);
+    let x = ((width - fitted.width()) / 2) as i64;
+    let y = ((height - fitted.height()) / 2) as i64;
+
+    let mut canvas = match mode {
+        ResizeMode::PadBlur => blurred(img, width, height).to_rgba8(),
+        ResizeMode::PadEdge => {
+            let [r, g, b] = edge_color(&fitted.to_rgb8(), fitted.width() >= fitted.height());
+            RgbaImage::from_pixel(width, height, Rgba([r, g, b, 255]))
+        }
+        _ => RgbaImage::from_pixel(width, height, Rgba([0, 0, 0, 255])),
+    };
+    image::imageops::overlay(&mut canvas, &fitted.to_rgba8(), x, y);
+
+    if fitted.color().has_alpha() {
+        DynamicImage::ImageRgba8(canvas)
+    } else {
+        DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(canvas).to_rgb8())
+    }
+}
+
+// Most common color of the edges next to the bars: the top and bottom rows
+// of a wide image, the side columns of a tall one. Colors are counted in
+// coarse buckets so noise and JPEG artifacts do not split them, and the
+// winning bucket is averaged.
+pub fn edge_color(img: &RgbImage, wide: bool) -> [u8; 3] {
+    let (width, height) = img.dimensions();
+    if width == 0 || height == 0 {
+        return [0, 0, 0];
+    }
+    let edge: Vec<Rgb<u8>> = if wide {
+        (0..width)
+            .flat_map(|x| [*img.get_pixel(x, 0), *img.get_pixel(x, height - 1)])
+            .collect()
+    } else {
+        (0..height)
+            .flat_map(|y| [*img.get_pixel(0, y), *img.get_pixel(width - 1, y)])
+            .collect()
+    };
+
+    let bucket = |Rgb([r, g, b]): Rgb<u8>| (r >> 5, g >> 5, b >> 5);
+    let mut counts = std::collections::HashMap::new();
+    for pixel in &edge {
+        *counts.entry(bucket(*pixel)).or_insert(0u32) += 1;
+    }
+    // Ties go to the darker bucket, so the result does not depend on the
+    // hash map order
+    let Some(dominant) = counts
+        .into_iter()
+        .max_by_key(|&(key, count)| (count, std::cmp::Reverse(key)))
+        .map(|(key, _)| key)
+    else {
+        return [0, 0, 0];
+    };
+
+    let members: Vec<Rgb<u8>> = edge
+        .into_iter()
+        .filter(|pixel| bucket(*pixel) == dominant)
+        .collect();
+    let mean = |channel: usize| {
+        let sum: u32 = members.iter().map(|pixel| pixel.0[channel] as u32).sum();
+        (sum / members.len() as u32) as u8
+    };
+    [mean(0), mean(1), mean(2)]
+}
+
+// The whole image stretched to the target and blurred, by shrinking it to a
+// few pixels and enlarging it again with a triangle filter; pure arithmetic,
+// so deterministic outputs stay the same everywhere
+fn blurred(img: &DynamicImage, width: u32, height: u32) -> DynamicImage {
+    let small = img.resize_exact(
+        (width / 24).max(1),
+        (height / 24).max(1),
+        FilterType::Triangle,
+    );
+    small.resize_exact(width, height, FilterType::Triangle)
+}
@@ -0,0 +1,45 @@
+use crate::{ArtCoverError, CancelToken};
+use std::thread;
+use std::time::Duration;
+
+pub const DEFAULT_RETRIES: u32 = 2;
+pub const MAX_RETRIES: u32 = 10;
+
+// Wait before the first retry, doubled before each next one
+const FIRST_DELAY: Duration = Duration::from_millis(250);
+const CHECK_CANCEL_EVERY: Duration = Duration::from_millis(50);
+
+// Run `attempt` again up to `retries` times while it fails for a passing
+// reason (a network share dropping out, a file held by another program),
+// waiting longer each time
+pub fn with_retries<T>(
+    retries: u32,
+    cancel: &CancelToken,
+    mut attempt: impl FnMut() -> Result<T, ArtCoverError>,
+) -> Result<T, ArtCoverError> {
+    let mut delay = FIRST_DELAY;
+    let mut retried = 0;
+    loop {
+        match attempt() {
+            Err(e) if retried < retries && e.is_transient() => {
+                tracing::warn!("retrying in {:?}: {}", delay, e.report());
+                wait(delay, cancel)?;
+                retried += 1;
+                delay *= 2;
+            }
+            result => return result,
+        }
+    }
+}
+
+fn wait(delay: Duration, cancel: &CancelToken) -> Result<(), ArtCoverError> {
+    let mut waited = Duration::ZERO;
+    while waited < delay {
+        if cancel.is_cancelled() {
+            return Err(ArtCoverError::Cancelled);
+        }
+        thread::sleep(CHECK_CANCEL_EVERY);
+        waited += CHECK_CANCEL_EVERY;
+    }
+    Ok(())
+}
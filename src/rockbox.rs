@@ -0,0 +1,244 @@
+use crate::i18n::{t, tf};
+use crate::pipeline::{self, CancelToken};
+use crate::preset::{OutputFormat, Preset, TargetSize};
+use crate::{ArtCoverError, ProcessOptions, ProcessedImage};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Rockbox shows album art at its native size when the skin gives no bounds
+const DEFAULT_ART_SIZE: u32 = 100;
+
+// Settings of a theme .cfg that matter for album art
+#[derive(Debug, Default)]
+struct ThemeConfig {
+    wps: Option<String>,
+    has_colors: bool,
+}
+
+// Build a preset from a Rockbox theme .cfg: the album art bounds come from
+// the %Cl tag of its WPS, the depth from whether the theme sets colors
+// (color targets use 16-bit LCDs, the others 2-bit grayscale).
+pub fn import_theme(cfg_path: &Path) -> Result<Preset, ArtCoverError> {
+    let cfg_text = fs::read_to_string(cfg_path).map_err(|e| ArtCoverError::io(cfg_path, e))?;
+    let config = parse_config(&cfg_text);
+
+    let wps_setting = config
+        .wps
+        .ok_or_else(|| ArtCoverError::InvalidTheme(t("Theme has no WPS file").to_string()))?;
+    let wps_path = resolve_theme_file(cfg_path, &wps_setting).ok_or_else(|| {
+        ArtCoverError::InvalidTheme(tf("WPS file not found: {}", &[&wps_setting]))
+    })?;
+    let wps_text = fs::read_to_string(&wps_path).map_err(|e| ArtCoverError::io(&wps_path, e))?;
+
+    let (width, height) = parse_album_art_size(&wps_text).ok_or_else(|| {
+        ArtCoverError::InvalidTheme(t("Theme does not display album art").to_string())
+    })?;
+
+    let name = cfg_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("Rockbox theme");
+
+    Ok(Preset {
+        name: format!("Rockbox: {}", name),
+        size: TargetSize::Max(width, height),
+        format: OutputFormat::Bmp,
+        bit_depth: Some(if config.has_colors { 16 } else { 2 }),
+        ascii_names: false,
+        lut: None,
+    })
+}
+
+fn parse_config(text: &str) -> ThemeConfig {
+    let mut config = ThemeConfig::default();
+
+    for line in text.lines() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+
+        match key.trim().to_lowercase().as_str() {
+            "wps" => config.wps = Some(value.trim().to_string()),
+            "foreground color"
+            | "background color"
+            | "selector color"
+            | "line selector start color" => config.has_colors = true,
+            _ => {}
+        }
+    }
+
+    config
+}
+
+// "%Cl(x,y,[maxwidth],[maxheight],...)", or the pre-3.5 "%Cl|x|y|w|h|".
+// Either bound may start with its alignment ("c150", "b120"); missing or
+// "-" bounds fall back to the default art size. Lines starting with "#"
+// are comments.
+fn parse_album_art_size(wps: &str) -> Option<(u32, u32)> {
+    let rest = wps
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .find_map(|line| line.find("%Cl").map(|start| &line[start + 3..]))?;
+
+    let arguments = if let Some(rest) = rest.strip_prefix('(') {
+        rest.split(')').next()?.split(',').collect::<Vec<_>>()
+    } else if let Some(rest) = rest.strip_prefix('|') {
+        rest.split('|').collect::<Vec<_>>()
+    } else {
+        return None;
+    };
+
+    let bound = |index: usize| {
+        arguments
+            .get(index)
+            .and_then(|value| {
+                value
+                    .trim()
+                    .trim_start_matches(['l', 'c', 'r', 't', 'b'])
+                    .parse()
+                    .ok()
+            })
+            .unwrap_or(DEFAULT_ART_SIZE)
+    };
+
+    Some((bound(2), bound(3)))
+}
+
+// Theme paths are absolute on the player ("/.rockbox/wps/x.wps"); find the
+// folder above the .cfg acting as the player root
+fn resolve_theme_file(cfg_path: &Path, setting: &str) -> Option<PathBuf> {
+    let relative = setting.trim_start_matches('/');
+
+    // Rockbox allows a bare file name relative to the wps folder
+    let candidates = if relative.contains('/') {
+        vec![PathBuf::from(relative)]
+    } else {
+        vec![Path::new(".rockbox/wps").join(relative)]
+    };
+
+    cfg_path.ancestors().skip(1).find_map(|root| {
+        candidates
+            .iter()
+            .map(|candidate| root.join(candidate))
+            .find(|path| path.is_file())
+    })
+}
+
+// Album art sizes used by the usual themes of a Rockbox target
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Target {
+    pub name: &'static str,
+    pub art_sizes: &'static [(u32, u32)],
+}
+
+pub const TARGETS: [Target; 3] = [
+    Target {
+        name: "iPod Video/Classic",
+        art_sizes: &[(100, 100), (200, 200)],
+    },
+    Target {
+        name: "iPod Nano",
+        art_sizes: &[(75, 75), (100, 100)],
+    },
+    Target {
+        name: "Sansa Fuze/Clip",
+        art_sizes: &[(80, 80), (100, 100)],
+    },
+];
+
+// Plain cover.jpg, decoded and scaled by the player when no sized file fits
+const FALLBACK_ART_SIZE: u32 = 500;
+
+// "cover.jpg", or "cover.100x100.jpg" for a file made for one art size
+pub fn cover_file_name(size: Option<(u32, u32)>) -> String {
+    match size {
+        Some((width, height)) => format!("cover.{}x{}.jpg", width, height),
+        None => "cover.jpg".to_string(),
+    }
+}
+
+// Every file Rockbox looks for in the album directory of `source`: the
+// generic cover.jpg plus one pre-scaled file per art size of the target.
+// Rockbox only decodes baseline JPEG, so a progressive source is re-encoded
+// even when the other settings would copy it.
+// Existing files are kept unless `replace` is set, and `source` itself never
+// is: every size is made from the original into a hidden folder, and moved
+// into the album once all are done.
+pub fn export_album_art(
+    source: &Path,
+    target: &Target,
+    options: &ProcessOptions,
+    replace: bool,
+    progress: &mut dyn FnMut(f32),
+    cancel: &CancelToken,
+) -> Vec<Result<ProcessedImage, ArtCoverError>> {
+    let album = source.parent().unwrap_or(Path::new("."));
+    let staging = album.join(format!(".artcover-rockbox-{}", std::process::id()));
+    if let Err(e) = fs::create_dir_all(&staging) {
+        return vec![Err(ArtCoverError::io(&staging, e))];
+    }
+
+    let sizes: Vec<Option<(u32, u32)>> = std::iter::once(None)
+        .chain(target.art_sizes.iter().copied().map(Some))
+        .collect();
+
+    let mut results = Vec::new();
+    for (index, size) in sizes.iter().enumerate() {
+        let destination = album.join(cover_file_name(*size));
+        if crate::is_same_file(&destination, source) || (!replace && destination.exists()) {
+            results.push(Err(ArtCoverError::io(
+                &destination,
+                io::Error::from(io::ErrorKind::AlreadyExists),
+            )));
+            continue;
+        }
+
+        let (width, height) = size.unwrap_or((FALLBACK_ART_SIZE, FALLBACK_ART_SIZE));
+        let mut options = options.clone();
+        options.baseline_jpeg = true;
+        options.preset = Preset {
+            name: format!("Rockbox {}x{}", width, height),
+            size: TargetSize::Max(width, height),
+            format: OutputFormat::Jpeg,
+            bit_depth: None,
+            ascii_names: false,
+            lut: None,
+        };
+
+        let share = 1.0 / sizes.len() as f32;
+        let result = pipeline::convert_to(
+            source,
+            &staging.join(cover_file_name(*size)),
+            &options,
+            &mut |done| progress((index as f32 + done) * share),
+            cancel,
+        );
+
+        let cancelled = matches!(result, Err(ArtCoverError::Cancelled));
+        results.push(result.map(|image| (image, destination)));
+        if cancelled {
+            break;
+        }
+    }
+
+    // Sizes finished before a cancellation are still moved in
+    let results = results
+        .into_iter()
+        .map(|result| {
+            let (image, destination) = result?;
+            fs::rename(&image.path, &destination)
+                .map_err(|e| ArtCoverError::io(&destination, e))?;
+            Ok(ProcessedImage {
+                path: destination,
+                ..image
+            })
+        })
+        .collect();
+    let _ = fs::remove_dir_all(&staging);
+    results
+}
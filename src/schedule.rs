@@ -0,0 +1,24 @@
+use chrono::{DateTime, TimeDelta, TimeZone};
+
+// Hour of the night, local time, at which the library is checked and fixed
+pub const NIGHTLY_HOUR: u32 = 3;
+
+// First `hour`:00 strictly after `after`, in its time zone. When a clock
+// change skips that hour, the run happens an hour later instead.
+pub fn next_run<Tz: TimeZone>(after: &DateTime<Tz>, hour: u32) -> DateTime<Tz> {
+    let zone = after.timezone();
+    let mut day = after.date_naive();
+    loop {
+        let at = day.and_hms_opt(hour.min(23), 0, 0).unwrap_or_default();
+        let run = zone.from_local_datetime(&at).earliest().or_else(|| {
+            zone.from_local_datetime(&(at + TimeDelta::hours(1)))
+                .earliest()
+        });
+        if let Some(run) = run
+            && run > *after
+        {
+            return run;
+        }
+        day += TimeDelta::days(1);
+    }
+}
@@ -0,0 +1,215 @@
+use crate::export::ExportProfile;
+use crate::i18n::{Language, t};
+use crate::preset::Preset;
+use crate::{ArtCoverError, CancelToken, ProcessOptions, lock};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Bumped when a field changes meaning; older bundles are still read
+pub const BUNDLE_VERSION: u32 = 1;
+
+// Everything needed to set the app up on another machine, in one file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Bundle {
+    pub version: u32,
+    pub options: ProcessOptions,
+    // Imported presets (Rockbox themes...); built-in ones are not stored
+    pub presets: Vec<Preset>,
+    // Export profile order and toggles, by name
+    pub profiles: Vec<SavedProfile>,
+    pub appearance: Appearance,
+    pub language: Language,
+    pub option_profiles: Vec<OptionsProfile>,
+    // Music folder checked and fixed every night while the app runs
+    pub nightly_library: Option<PathBuf>,
+}
+
+// Conversion options saved under a name (size, quality, crop mode,
+// naming...), to switch between setups or share one as a file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OptionsProfile {
+    pub name: String,
+    pub options: ProcessOptions,
+}
+
+// Shared profile file: {"version": 1, "name": "...", "options": {...}}
+#[derive(Serialize, Deserialize)]
+struct ProfileFile {
+    version: u32,
+    #[serde(flatten)]
+    profile: OptionsProfile,
+}
+
+// Window theme. System follows the dark mode setting of the OS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Appearance {
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+impl Appearance {
+    pub const ALL: [Appearance; 3] = [Appearance::System, Appearance::Light, Appearance::Dark];
+}
+
+impl fmt::Display for Appearance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Appearance::System => "System theme",
+            Appearance::Light => "Light",
+            Appearance::Dark => "Dark",
+        };
+        write!(f, "{}", t(label))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedProfile {
+    pub name: String,
+    pub enabled: bool,
+}
+
+impl Default for Bundle {
+    fn default() -> Self {
+        Self {
+            version: BUNDLE_VERSION,
+            options: ProcessOptions::default(),
+            presets: Vec::new(),
+            profiles: Vec::new(),
+            appearance: Appearance::default(),
+            language: Language::default(),
+            option_profiles: Vec::new(),
+            nightly_library: None,
+        }
+    }
+}
+
+impl Bundle {
+    pub fn new(options: &ProcessOptions, presets: &[Preset], profiles: &[ExportProfile]) -> Self {
+        let builtin = crate::preset::builtin();
+        Self {
+            version: BUNDLE_VERSION,
+            options: options.clone(),
+            presets: presets
+                .iter()
+                .filter(|preset| !builtin.contains(preset))
+                .cloned()
+                .collect(),
+            profiles: profiles
+                .iter()
+                .map(|profile| SavedProfile {
+                    name: profile.name(),
+                    enabled: profile.enabled,
+                })
+                .collect(),
+            appearance: Appearance::default(),
+            language: Language::default(),
+            option_profiles: Vec::new(),
+            nightly_library: None,
+        }
+    }
+
+    // Reorder and toggle `profiles` as saved. Profiles unknown to the
+    // bundle keep their relative order after the saved ones.
+    pub fn apply_profiles(&self, profiles: &mut [ExportProfile]) {
+        let position = |profile: &ExportProfile| {
+            self.profiles
+                .iter()
+                .position(|saved| saved.name == profile.name())
+                .unwrap_or(usize::MAX)
+        };
+        profiles.sort_by_key(position);
+
+        for profile in profiles.iter_mut() {
+            if let Some(saved) = self
+                .profiles
+                .iter()
+                .find(|saved| saved.name == profile.name())
+            {
+                profile.enabled = saved.enabled;
+            }
+        }
+    }
+}
+
+pub fn export_bundle(path: &Path, bundle: &Bundle) -> Result<(), ArtCoverError> {
+    let json = serde_json::to_string_pretty(bundle)
+        .map_err(|e| ArtCoverError::InvalidBundle(e.to_string()))?;
+    fs::write(path, json).map_err(|e| ArtCoverError::io(path, e))
+}
+
+pub fn import_bundle(path: &Path) -> Result<Bundle, ArtCoverError> {
+    let json = fs::read_to_string(path).map_err(|e| ArtCoverError::io(path, e))?;
+    let bundle: Bundle =
+        serde_json::from_str(&json).map_err(|e| ArtCoverError::InvalidBundle(e.to_string()))?;
+
+    if bundle.version > BUNDLE_VERSION {
+        return Err(ArtCoverError::InvalidBundle(format!(
+            "made by a newer version (format {})",
+            bundle.version
+        )));
+    }
+    Ok(bundle)
+}
+
+pub fn export_profile(path: &Path, profile: &OptionsProfile) -> Result<(), ArtCoverError> {
+    let file = ProfileFile {
+        version: BUNDLE_VERSION,
+        profile: profile.clone(),
+    };
+    let json = serde_json::to_string_pretty(&file)
+        .map_err(|e| ArtCoverError::InvalidBundle(e.to_string()))?;
+    fs::write(path, json).map_err(|e| ArtCoverError::io(path, e))
+}
+
+pub fn import_profile(path: &Path) -> Result<OptionsProfile, ArtCoverError> {
+    let json = fs::read_to_string(path).map_err(|e| ArtCoverError::io(path, e))?;
+    let file: ProfileFile =
+        serde_json::from_str(&json).map_err(|e| ArtCoverError::InvalidBundle(e.to_string()))?;
+
+    if file.version > BUNDLE_VERSION {
+        return Err(ArtCoverError::InvalidBundle(format!(
+            "made by a newer version (format {})",
+            file.version
+        )));
+    }
+    if file.profile.name.trim().is_empty() {
+        return Err(ArtCoverError::InvalidBundle(
+            "profile without a name".to_string(),
+        ));
+    }
+    Ok(file.profile)
+}
+
+// Bundle kept in the user's config folder between runs
+pub fn saved_path() -> Option<PathBuf> {
+    let var = |name| std::env::var_os(name).map(PathBuf::from);
+    let config = if cfg!(windows) {
+        var("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        var("HOME").map(|home| home.join("Library").join("Application Support"))
+    } else {
+        var("XDG_CONFIG_HOME").or_else(|| var("HOME").map(|home| home.join(".config")))
+    };
+    config.map(|dir| dir.join("artcover").join("settings.json"))
+}
+
+// None on first launch, or when the file cannot be used
+pub fn load_saved() -> Option<Bundle> {
+    import_bundle(&saved_path()?).ok()
+}
+
+pub fn save(bundle: &Bundle) -> Result<(), ArtCoverError> {
+    let Some(path) = saved_path() else {
+        return Ok(());
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| ArtCoverError::io(dir, e))?;
+    }
+    let _lock = lock::acquire(&path, &CancelToken::default())?;
+    export_bundle(&path, bundle)
+}
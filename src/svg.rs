@@ -0,0 +1,68 @@
+use crate::ArtCoverError;
+use crate::preset::Preset;
+use crate::resize::{self, ResizeMode};
+use image::error::{DecodingError, ImageFormatHint};
+use image::{DynamicImage, ImageError, RgbaImage};
+use resvg::{tiny_skia, usvg};
+use std::fs;
+use std::path::Path;
+
+pub fn is_svg(path: &Path) -> bool {
+    path.extension()
+        .and_then(|s| s.to_str())
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("svg"))
+}
+
+// Render the drawing directly at the preset resolution, so the pipeline
+// gets a sharp bitmap instead of scaling a rasterized default size
+pub fn rasterize(
+    path: &Path,
+    preset: &Preset,
+    mode: ResizeMode,
+) -> Result<DynamicImage, ArtCoverError> {
+    let data = fs::read(path).map_err(|e| ArtCoverError::io(path, e))?;
+
+    // Relative image links are resolved next to the file, text uses the
+    // installed fonts
+    let mut options = usvg::Options {
+        resources_dir: path.parent().map(Path::to_path_buf),
+        ..Default::default()
+    };
+    options.fontdb_mut().load_system_fonts();
+
+    let tree = usvg::Tree::from_data(&data, &options).map_err(|e| invalid(path, e))?;
+    let (width, height) =
+        resize::render_size(tree.size().width(), tree.size().height(), preset, mode);
+
+    let mut pixmap = tiny_skia::Pixmap::new(width, height)
+        .ok_or_else(|| ArtCoverError::Resize("drawing has no size".to_string()))?;
+    let transform = tiny_skia::Transform::from_scale(
+        width as f32 / tree.size().width(),
+        height as f32 / tree.size().height(),
+    );
+    resvg::render(&tree, transform, &mut pixmap.as_mut());
+
+    // tiny-skia keeps premultiplied alpha
+    let pixels = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+    let img = RgbaImage::from_raw(width, height, pixels)
+        .ok_or_else(|| ArtCoverError::Resize("drawing has no size".to_string()))?;
+
+    Ok(DynamicImage::ImageRgba8(img))
+}
+
+fn invalid(path: &Path, error: usvg::Error) -> ArtCoverError {
+    ArtCoverError::Decode {
+        path: path.to_path_buf(),
+        source: ImageError::Decoding(DecodingError::new(
+            ImageFormatHint::Name("SVG".to_string()),
+            error,
+        )),
+    }
+}
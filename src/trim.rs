@@ -0,0 +1,100 @@
+use image::{DynamicImage, Rgba, RgbaImage};
+
+// Share of a row/column allowed to differ from the edge color (dust, scanner
+// noise) while still being treated as border
+const NOISE_ALLOWANCE: f32 = 0.02;
+
+// Never trim away more than this share of either dimension
+const MIN_KEPT: f32 = 0.1;
+
+// Remove uniform margins (white paper, black scanner bed) around the artwork.
+// Each side is compared against the average color of its outermost line.
+pub fn trim_borders(img: &DynamicImage, tolerance: u8) -> DynamicImage {
+    let rgba = img.to_rgba8();
+    let (width, height) = rgba.dimensions();
+
+    if width < 3 || height < 3 {
+        return img.clone();
+    }
+
+    let mut top = 0;
+    let top_color = average(row(&rgba, 0, 0, width));
+    while top < height - 1 && is_border(row(&rgba, top, 0, width), &top_color, tolerance) {
+        top += 1;
+    }
+
+    let mut bottom = height;
+    let bottom_color = average(row(&rgba, height - 1, 0, width));
+    while bottom > top + 1 && is_border(row(&rgba, bottom - 1, 0, width), &bottom_color, tolerance)
+    {
+        bottom -= 1;
+    }
+
+    let mut left = 0;
+    let left_color = average(column(&rgba, 0, top, bottom));
+    while left < width - 1 && is_border(column(&rgba, left, top, bottom), &left_color, tolerance) {
+        left += 1;
+    }
+
+    let mut right = width;
+    let right_color = average(column(&rgba, width - 1, top, bottom));
+    while right > left + 1
+        && is_border(
+            column(&rgba, right - 1, top, bottom),
+            &right_color,
+            tolerance,
+        )
+    {
+        right -= 1;
+    }
+
+    let (new_width, new_height) = (right - left, bottom - top);
+
+    // A blank or nearly uniform image has nothing worth keeping
+    if (new_width as f32) < width as f32 * MIN_KEPT
+        || (new_height as f32) < height as f32 * MIN_KEPT
+    {
+        return img.clone();
+    }
+
+    if (new_width, new_height) == (width, height) {
+        return img.clone();
+    }
+
+    img.crop_imm(left, top, new_width, new_height)
+}
+
+fn row(img: &RgbaImage, y: u32, from: u32, to: u32) -> Vec<Rgba<u8>> {
+    (from..to).map(|x| *img.get_pixel(x, y)).collect()
+}
+
+fn column(img: &RgbaImage, x: u32, from: u32, to: u32) -> Vec<Rgba<u8>> {
+    (from..to).map(|y| *img.get_pixel(x, y)).collect()
+}
+
+fn average(pixels: Vec<Rgba<u8>>) -> Rgba<u8> {
+    let count = pixels.len().max(1) as u64;
+    let mut sums = [0u64; 4];
+
+    for pixel in &pixels {
+        for (sum, channel) in sums.iter_mut().zip(pixel.0) {
+            *sum += channel as u64;
+        }
+    }
+
+    Rgba(sums.map(|sum| (sum / count) as u8))
+}
+
+fn is_border(pixels: Vec<Rgba<u8>>, reference: &Rgba<u8>, tolerance: u8) -> bool {
+    let outliers = pixels
+        .iter()
+        .filter(|pixel| {
+            pixel.0[..3]
+                .iter()
+                .zip(&reference.0[..3])
+                .any(|(a, b)| a.abs_diff(*b) > tolerance)
+        })
+        .count();
+
+    (outliers as f32) <= pixels.len() as f32 * NOISE_ALLOWANCE
+}
@@ -0,0 +1,86 @@
+use artcover_image_conversor::audit::{self, Finding, Found};
+use artcover_image_conversor::{CancelToken, ProcessOptions, library};
+use image::RgbImage;
+use std::fs;
+use std::path::Path;
+
+// An album folder with a track and the given images, as squares of `side`
+fn album(folder: &Path, images: &[(&str, u32)]) {
+    fs::create_dir_all(folder).unwrap();
+    fs::write(folder.join("01.mp3"), b"").unwrap();
+    for (name, side) in images {
+        RgbImage::from_pixel(*side, *side, image::Rgb([200, 40, 40]))
+            .save(folder.join(name))
+            .unwrap();
+    }
+}
+
+#[test]
+fn missing_and_small_covers_are_found() {
+    let dir = tempfile::tempdir().unwrap();
+    let music = dir.path();
+    album(
+        &music.join("Artist").join("Missing"),
+        &[("folder.png", 600)],
+    );
+    album(&music.join("Artist").join("Small"), &[("cover.jpg", 100)]);
+    album(&music.join("Artist").join("Fine"), &[("cover.jpg", 300)]);
+
+    let options = ProcessOptions::default();
+    let albums = library::scan(music).unwrap();
+    let gaps = audit::audit(&albums, &options.preset);
+
+    let findings: Vec<_> = gaps
+        .iter()
+        .map(|gap| {
+            (
+                gap.album.folder.file_name().unwrap().to_owned(),
+                gap.finding,
+            )
+        })
+        .collect();
+    assert_eq!(
+        findings,
+        [
+            ("Missing".into(), Finding::Missing),
+            ("Small".into(), Finding::TooSmall(100, 100)),
+        ]
+    );
+}
+
+#[test]
+fn gaps_are_filled_from_the_album_artwork_offline() {
+    let dir = tempfile::tempdir().unwrap();
+    let folder = dir.path().join("Artist").join("Missing");
+    album(&folder, &[("folder.png", 600)]);
+
+    let options = ProcessOptions::default();
+    let cancel = CancelToken::default();
+    let albums = library::scan(dir.path()).unwrap();
+    let gaps = audit::audit(&albums, &options.preset);
+    let found = audit::fix(&gaps[0], &options, false, &cancel).unwrap();
+
+    assert_eq!(found, Some(Found::Artwork(folder.join("folder.png"))));
+    let cover = folder.join(library::COVER_FILE_NAME);
+    assert_eq!(image::image_dimensions(&cover).unwrap(), (300, 300));
+    assert!(audit::audit(&albums, &options.preset).is_empty());
+}
+
+#[test]
+fn small_cover_without_a_better_source_is_left_alone() {
+    let dir = tempfile::tempdir().unwrap();
+    let folder = dir.path().join("Artist").join("Small");
+    album(&folder, &[("cover.jpg", 100)]);
+    let before = fs::read(folder.join(library::COVER_FILE_NAME)).unwrap();
+
+    let options = ProcessOptions::default();
+    let albums = library::scan(dir.path()).unwrap();
+    let gaps = audit::audit(&albums, &options.preset);
+    let found = audit::fix(&gaps[0], &options, false, &CancelToken::default()).unwrap();
+
+    assert_eq!(found, None);
+    assert_eq!(
+        fs::read(folder.join(library::COVER_FILE_NAME)).unwrap(),
+        before
+    );
+}
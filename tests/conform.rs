@@ -0,0 +1,62 @@
+use artcover_image_conversor::ProcessOptions;
+use artcover_image_conversor::conform::{self, Passthrough};
+use artcover_image_conversor::probe;
+use image::RgbImage;
+use std::path::Path;
+
+type Change = fn(&mut ProcessOptions);
+
+fn jpeg(path: &Path, side: u32) {
+    RgbImage::from_pixel(side, side, image::Rgb([30, 90, 160]))
+        .save(path)
+        .unwrap();
+}
+
+fn conforms(source: &Path, destination: &str, options: &ProcessOptions) -> bool {
+    let encoding = probe::probe(source).unwrap();
+    let destination = source.with_file_name(destination);
+    conform::conforms(source, &destination, &encoding, options)
+}
+
+#[test]
+fn fitting_source_conforms_unless_something_would_change_it() {
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("fits.jpg");
+    jpeg(&source, 300);
+    let options = ProcessOptions::default();
+    assert!(conforms(&source, "out.jpg", &options));
+
+    let changed: [(&str, Change); 6] = [
+        ("passthrough off", |o| o.passthrough = Passthrough::Off),
+        ("auto trim", |o| o.auto_trim = true),
+        ("bit depth", |o| o.preset.bit_depth = Some(8)),
+        ("provenance", |o| o.provenance = true),
+        ("deterministic", |o| o.deterministic = true),
+        ("crop", |o| {
+            o.crop = Some(artcover_image_conversor::resize::Square {
+                x: 0,
+                y: 0,
+                side: 200,
+            })
+        }),
+    ];
+    for (name, change) in changed {
+        let mut options = options.clone();
+        change(&mut options);
+        assert!(!conforms(&source, "out.jpg", &options), "{}", name);
+    }
+}
+
+#[test]
+fn other_format_or_size_does_not_conform() {
+    let dir = tempfile::tempdir().unwrap();
+    let options = ProcessOptions::default();
+
+    let fits = dir.path().join("fits.jpg");
+    jpeg(&fits, 300);
+    assert!(!conforms(&fits, "out.png", &options));
+
+    let large = dir.path().join("large.jpg");
+    jpeg(&large, 600);
+    assert!(!conforms(&large, "out.jpg", &options));
+}
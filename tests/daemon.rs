@@ -0,0 +1,79 @@
+#![cfg(unix)]
+
+use artcover_image_conversor::ProcessOptions;
+use artcover_image_conversor::daemon::{self, Request, Response};
+use image::RgbImage;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::time::Duration;
+
+fn connect(socket: &Path) -> UnixStream {
+    for _ in 0..100 {
+        if let Ok(stream) = UnixStream::connect(socket) {
+            return stream;
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    panic!("the daemon did not start");
+}
+
+fn ask(stream: &mut UnixStream, request: &Request) -> Response {
+    writeln!(stream, "{}", serde_json::to_string(request).unwrap()).unwrap();
+    let mut line = String::new();
+    BufReader::new(stream.try_clone().unwrap())
+        .read_line(&mut line)
+        .unwrap();
+    serde_json::from_str(&line).unwrap()
+}
+
+#[test]
+fn destinations_outside_the_source_folder_or_on_it_are_refused() {
+    let dir = tempfile::tempdir().unwrap();
+    let music = dir.path().join("music");
+    std::fs::create_dir(&music).unwrap();
+    let source = music.join("front.png");
+    RgbImage::new(400, 400).save(&source).unwrap();
+    let socket = dir.path().join("daemon.sock");
+
+    let served = socket.clone();
+    std::thread::spawn(move || daemon::serve(&served, ProcessOptions::default(), Vec::new()));
+    let mut stream = connect(&socket);
+
+    let outside = ask(
+        &mut stream,
+        &Request {
+            path: source.clone(),
+            destination: Some(dir.path().join("elsewhere.jpg")),
+            ..Request::default()
+        },
+    );
+    assert!(!outside.ok);
+    assert!(!dir.path().join("elsewhere.jpg").exists());
+    // Answered, so the daemon is past setting up its socket
+    let mode = std::fs::metadata(&socket).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    let itself = ask(
+        &mut stream,
+        &Request {
+            path: source.clone(),
+            destination: Some(music.join(".").join("front.png")),
+            ..Request::default()
+        },
+    );
+    assert!(!itself.ok);
+    assert_eq!(image::image_dimensions(&source).unwrap(), (400, 400));
+
+    let beside = ask(
+        &mut stream,
+        &Request {
+            path: source,
+            destination: Some(music.join("cover.jpg")),
+            ..Request::default()
+        },
+    );
+    assert!(beside.ok, "{:?}", beside.error);
+    assert!(music.join("cover.jpg").exists());
+}
@@ -0,0 +1,88 @@
+use artcover_image_conversor::dedupe::{self, Cluster, CoverFile, DuplicateAction};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn file(path: PathBuf) -> CoverFile {
+    CoverFile {
+        path,
+        width: 300,
+        height: 300,
+        bytes: 0,
+    }
+}
+
+// A cluster keeping the first of `names`, each file holding its own name
+fn cluster(dir: &Path, names: &[&str]) -> Cluster {
+    let files = names
+        .iter()
+        .map(|name| {
+            let path = dir.join(name);
+            fs::write(&path, name).unwrap();
+            file(path)
+        })
+        .collect();
+    Cluster { files, keep: 0 }
+}
+
+#[test]
+fn duplicates_are_deleted_and_the_kept_file_stays() {
+    let dir = tempfile::tempdir().unwrap();
+    let cluster = cluster(dir.path(), &["best.jpg", "copy.jpg", "other.jpg"]);
+
+    let resolution = dedupe::resolve(&cluster, DuplicateAction::Delete);
+
+    assert_eq!(resolution.handled, 2);
+    assert!(resolution.failed.is_empty());
+    assert!(dir.path().join("best.jpg").exists());
+    assert!(!dir.path().join("copy.jpg").exists());
+    assert!(!dir.path().join("other.jpg").exists());
+}
+
+#[test]
+fn links_take_the_kept_format_and_replace_the_duplicate() {
+    let dir = tempfile::tempdir().unwrap();
+    let cluster = cluster(dir.path(), &["best.png", "same.png", "copy.jpg"]);
+
+    let resolution = dedupe::resolve(&cluster, DuplicateAction::Link);
+
+    assert_eq!(resolution.handled, 2);
+    assert_eq!(fs::read(dir.path().join("same.png")).unwrap(), b"best.png");
+    assert_eq!(fs::read(dir.path().join("copy.png")).unwrap(), b"best.png");
+    assert!(!dir.path().join("copy.jpg").exists());
+    let mut names: Vec<_> = fs::read_dir(dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["best.png", "copy.png", "same.png"]);
+}
+
+#[test]
+fn links_onto_existing_files_are_refused_and_the_duplicate_kept() {
+    let dir = tempfile::tempdir().unwrap();
+    // best.jpg would become best.png, the kept file itself; taken.jpg would
+    // become taken.png, which is another file
+    let cluster = cluster(
+        dir.path(),
+        &["best.png", "best.jpg", "taken.jpg", "taken.png"],
+    );
+    let cluster = Cluster {
+        files: cluster.files[..3].to_vec(),
+        keep: 0,
+    };
+
+    let resolution = dedupe::resolve(&cluster, DuplicateAction::Link);
+
+    assert_eq!(resolution.handled, 0);
+    assert_eq!(resolution.failed.len(), 2);
+    assert_eq!(fs::read(dir.path().join("best.png")).unwrap(), b"best.png");
+    assert_eq!(fs::read(dir.path().join("best.jpg")).unwrap(), b"best.jpg");
+    assert_eq!(
+        fs::read(dir.path().join("taken.jpg")).unwrap(),
+        b"taken.jpg"
+    );
+    assert_eq!(
+        fs::read(dir.path().join("taken.png")).unwrap(),
+        b"taken.png"
+    );
+}
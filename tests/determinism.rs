@@ -0,0 +1,166 @@
+use artcover_image_conversor::preset::{OutputFormat, Preset, TargetSize};
+use artcover_image_conversor::quantize::ColorMode;
+use artcover_image_conversor::resize::ResizeMode;
+use artcover_image_conversor::{ProcessOptions, convert_bytes, process_image};
+use image::{ImageFormat, Rgb, RgbImage};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+// Busy pattern so resizing, dithering and compression all have work to do
+fn sample(width: u32, height: u32) -> RgbImage {
+    RgbImage::from_fn(width, height, |x, y| {
+        Rgb([
+            (x * 255 / width) as u8,
+            (y * 255 / height) as u8,
+            ((x ^ y) % 256) as u8,
+        ])
+    })
+}
+
+fn deterministic() -> ProcessOptions {
+    ProcessOptions {
+        deterministic: true,
+        ..ProcessOptions::default()
+    }
+}
+
+// Convert a fresh copy of the sample in its own folder
+fn convert(name: &str, options: &ProcessOptions) -> Vec<u8> {
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join(name);
+    sample(640, 480).save(&source).unwrap();
+
+    let processed = process_image(&source, options).unwrap();
+    fs::read(processed.path).unwrap()
+}
+
+// FNV-1a, stable across platforms and Rust releases
+fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[test]
+fn repeated_runs_are_byte_identical() {
+    for name in ["cover.png", "cover.jpg", "cover.bmp"] {
+        for resize_mode in ResizeMode::ALL {
+            let options = ProcessOptions {
+                resize_mode,
+                auto_trim: true,
+                ..deterministic()
+            };
+            assert_eq!(
+                convert(name, &options),
+                convert(name, &options),
+                "{} with {}",
+                name,
+                resize_mode
+            );
+        }
+    }
+}
+
+#[test]
+fn quantized_output_is_byte_identical() {
+    let options = ProcessOptions {
+        preset: Preset {
+            name: "Rockbox".to_string(),
+            size: TargetSize::Max(100, 100),
+            format: OutputFormat::Bmp,
+            bit_depth: Some(16),
+            ascii_names: false,
+            lut: None,
+        },
+        ..deterministic()
+    };
+
+    assert_eq!(
+        convert("cover.png", &options),
+        convert("cover.png", &options)
+    );
+}
+
+#[test]
+fn reduced_colors_are_byte_identical() {
+    for color_mode in [ColorMode::Grayscale, ColorMode::Palette] {
+        let options = ProcessOptions {
+            color_mode,
+            palette_colors: 12,
+            ..deterministic()
+        };
+        let output = convert("cover.png", &options);
+        assert_eq!(output, convert("cover.png", &options), "{}", color_mode);
+
+        let colors: HashSet<[u8; 3]> = image::load_from_memory(&output)
+            .unwrap()
+            .to_rgb8()
+            .pixels()
+            .map(|pixel| pixel.0)
+            .collect();
+        match color_mode {
+            ColorMode::Grayscale => assert!(colors.iter().all(|[r, g, b]| r == g && g == b)),
+            _ => assert!(colors.len() <= 12, "{} colors", colors.len()),
+        }
+    }
+}
+
+#[test]
+fn pinned_encoder_ignores_quality_setting() {
+    let low = ProcessOptions {
+        jpeg_quality: 60,
+        ..deterministic()
+    };
+    let high = ProcessOptions {
+        jpeg_quality: 100,
+        ..deterministic()
+    };
+
+    assert_eq!(convert("cover.jpg", &low), convert("cover.jpg", &high));
+}
+
+#[test]
+fn piped_input_converts_like_the_file() {
+    let options = deterministic();
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("cover.png");
+    sample(640, 480).save(&source).unwrap();
+
+    let piped = convert_bytes(&fs::read(&source).unwrap(), &options).unwrap();
+
+    assert_eq!(piped.format, ImageFormat::Png);
+    assert_eq!(piped.bytes, convert("cover.png", &options));
+}
+
+#[test]
+fn output_does_not_depend_on_file_name_or_location() {
+    let options = deterministic();
+    let dir = tempfile::tempdir().unwrap();
+    let nested = dir.path().join("some").join("album");
+    fs::create_dir_all(&nested).unwrap();
+
+    let source = nested.join("another name.png");
+    sample(640, 480).save(&source).unwrap();
+    let processed = process_image(Path::new(&source), &options).unwrap();
+
+    assert_eq!(
+        fs::read(processed.path).unwrap(),
+        convert("cover.png", &options)
+    );
+}
+
+// Reference values guard against platform or dependency differences
+#[test]
+fn outputs_match_reference_checksums() {
+    let options = deterministic();
+
+    assert_eq!(
+        checksum(&convert("cover.png", &options)),
+        0x6e9632245594f392
+    );
+    assert_eq!(
+        checksum(&convert("cover.jpg", &options)),
+        0x8c62434ffc9e3440
+    );
+}
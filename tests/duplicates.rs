@@ -0,0 +1,39 @@
+use artcover_image_conversor::hash_index::DuplicateCheck;
+use artcover_image_conversor::{ProcessOptions, process_image};
+use image::{Rgb, RgbImage};
+use std::path::Path;
+
+// Already fits the default preset, so it is copied instead of re-encoded
+fn cover(path: &Path) {
+    RgbImage::from_fn(300, 300, |x, y| Rgb([(x / 2) as u8, (y / 2) as u8, 90]))
+        .save(path)
+        .unwrap();
+}
+
+#[test]
+fn conforming_duplicates_are_flagged_or_skipped() {
+    let dir = tempfile::tempdir().unwrap();
+    let (first, second) = (dir.path().join("front.jpg"), dir.path().join("scan.jpg"));
+    cover(&first);
+    cover(&second);
+
+    let options = ProcessOptions::default();
+    assert!(process_image(&first, &options).unwrap().passed_through);
+
+    let flagged = process_image(&second, &options).unwrap();
+    assert!(flagged.passed_through);
+    assert!(
+        flagged
+            .warnings
+            .iter()
+            .any(|w| w.starts_with("Looks like front.jpg"))
+    );
+
+    let options = ProcessOptions {
+        duplicates: DuplicateCheck::Skip,
+        ..Default::default()
+    };
+    let skipped = process_image(&second, &options).unwrap();
+    assert_eq!(skipped.duplicate_of.as_deref(), Some(first.as_path()));
+    assert!(!skipped.passed_through);
+}
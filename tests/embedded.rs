@@ -0,0 +1,76 @@
+use artcover_image_conversor::embedded;
+use artcover_image_conversor::{ArtCoverError, CancelToken, ProcessOptions, library};
+use image::{ImageFormat, RgbImage};
+use lofty::config::WriteOptions;
+use lofty::picture::{Picture, PictureType};
+use lofty::tag::{Tag, TagExt, TagType};
+use std::fs;
+use std::io::{self, Cursor};
+use std::path::Path;
+
+// An MP3 of a few silent frames with a 600x600 front cover in its tag
+fn track(path: &Path) {
+    let mut cover = Vec::new();
+    RgbImage::from_pixel(600, 600, image::Rgb([180, 30, 90]))
+        .write_to(&mut Cursor::new(&mut cover), ImageFormat::Png)
+        .unwrap();
+    let mut tag = Tag::new(TagType::Id3v2);
+    tag.push_picture(
+        Picture::unchecked(cover)
+            .pic_type(PictureType::CoverFront)
+            .build(),
+    );
+
+    let mut bytes = Vec::new();
+    tag.dump_to(&mut bytes, WriteOptions::default()).unwrap();
+    for _ in 0..3 {
+        bytes.extend([0xFF, 0xFB, 0x90, 0x00]);
+        bytes.extend([0; 413]);
+    }
+    fs::write(path, bytes).unwrap();
+}
+
+#[test]
+fn embedded_cover_is_extracted_next_to_the_track() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("01.mp3");
+    track(&path);
+
+    let processed = embedded::extract_cover(
+        &path,
+        &ProcessOptions::default(),
+        false,
+        &mut |_| {},
+        &CancelToken::default(),
+    )
+    .unwrap();
+
+    assert_eq!(processed.path, dir.path().join(library::COVER_FILE_NAME));
+    assert_eq!(
+        image::image_dimensions(&processed.path).unwrap(),
+        (300, 300)
+    );
+}
+
+#[test]
+fn existing_cover_is_not_replaced() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("01.mp3");
+    track(&path);
+    let cover = dir.path().join(library::COVER_FILE_NAME);
+    fs::write(&cover, b"picked by hand").unwrap();
+
+    let result = embedded::extract_cover(
+        &path,
+        &ProcessOptions::default(),
+        false,
+        &mut |_| {},
+        &CancelToken::default(),
+    );
+
+    assert!(matches!(
+        result,
+        Err(ArtCoverError::Io { source, .. }) if source.kind() == io::ErrorKind::AlreadyExists
+    ));
+    assert_eq!(fs::read(&cover).unwrap(), b"picked by hand");
+}
@@ -0,0 +1,341 @@
+// The window logic driven without a window: events go through `update` and
+// the commands it returns are run to completion on the test thread, the
+// way the iced runtime would run them.
+
+#[allow(dead_code)]
+#[path = "../src/gui/mod.rs"]
+mod gui;
+#[allow(dead_code)]
+#[path = "../src/logging.rs"]
+mod logging;
+
+use artcover_image_conversor::i18n::Language;
+use artcover_image_conversor::resize::Square;
+use artcover_image_conversor::settings::{self, Bundle};
+use gui::shortcuts::Action as Shortcut;
+use gui::{FileStatus, ImageProcessor, Message};
+use iced::futures::StreamExt;
+use iced::futures::executor::block_on;
+use iced::{Application, Event, window};
+use iced_runtime::command::Action;
+use image::{Rgb, RgbImage};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+// Every test points the config folder at its own directory through the
+// environment, so they take turns
+static SERIAL: Mutex<()> = Mutex::new(());
+
+struct Harness {
+    app: ImageProcessor,
+    dir: tempfile::TempDir,
+    _serial: MutexGuard<'static, ()>,
+}
+
+impl Harness {
+    // A fresh app with empty, English settings
+    fn new() -> Self {
+        let serial = SERIAL
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let dir = tempfile::tempdir().unwrap();
+        // SAFETY: tests run one at a time and nothing else reads the
+        // environment meanwhile
+        unsafe {
+            std::env::set_var("XDG_CONFIG_HOME", dir.path().join("config"));
+            std::env::set_var("XDG_DATA_HOME", dir.path().join("data"));
+        }
+        settings::save(&Bundle {
+            language: Language::English,
+            ..Bundle::default()
+        })
+        .unwrap();
+
+        Self {
+            app: launch(),
+            dir,
+            _serial: serial,
+        }
+    }
+
+    fn image(&self, name: &str) -> PathBuf {
+        let path = self.dir.path().join(name);
+        RgbImage::from_fn(480, 360, |x, y| {
+            Rgb([(x % 256) as u8, (y % 256) as u8, 128])
+        })
+        .save(&path)
+        .unwrap();
+        path
+    }
+
+    fn drop_file(&mut self, path: &Path) {
+        self.drop_files(&[path]);
+    }
+
+    // One drop of several files: an event per file, all arriving before
+    // the first conversion ends
+    fn drop_files(&mut self, paths: &[&Path]) {
+        let events = paths.iter().map(|path| {
+            let event = Event::Window(
+                window::Id::MAIN,
+                window::Event::FileDropped(path.to_path_buf()),
+            );
+            Message::EventOccurred(event)
+        });
+        send(&mut self.app, events);
+    }
+
+    fn send(&mut self, message: Message) {
+        send(&mut self.app, [message]);
+    }
+
+    fn outputs(&self) -> Vec<PathBuf> {
+        self.app
+            .results
+            .iter()
+            .flat_map(|result| match &result.status {
+                FileStatus::Done(outputs) => outputs.clone(),
+                _ => Vec::new(),
+            })
+            .collect()
+    }
+}
+
+// Startup commands (the health check) are left out: they only look at
+// the machine
+fn launch() -> ImageProcessor {
+    let (app, _startup) = ImageProcessor::new(());
+    app
+}
+
+// Handle `messages` and everything their commands produce, until the app
+// is idle again
+fn send(app: &mut ImageProcessor, messages: impl IntoIterator<Item = Message>) {
+    let mut pending: VecDeque<Message> = messages.into_iter().collect();
+    while let Some(message) = pending.pop_front() {
+        for action in app.update(message).actions() {
+            match action {
+                Action::Future(future) => pending.push_back(block_on(future)),
+                Action::Stream(stream) => pending.extend(block_on(stream.collect::<Vec<_>>())),
+                // Clipboard, window and widget actions need a window
+                _ => {}
+            }
+        }
+    }
+}
+
+#[test]
+fn dropped_image_is_converted() {
+    let mut harness = Harness::new();
+    let source = harness.image("cover.png");
+
+    harness.drop_file(&source);
+
+    let app = &harness.app;
+    assert!(!app.is_processing);
+    assert_eq!(app.message, "Image processed and saved");
+    assert_eq!(app.results.len(), 1);
+    assert_eq!(app.results[0].source, source);
+
+    let outputs = harness.outputs();
+    assert_eq!(outputs.len(), 1);
+    assert_eq!(app.processed_image.as_ref(), Some(&outputs[0]));
+    let (width, height) = image::image_dimensions(&outputs[0]).unwrap();
+    assert_eq!((width, height), (300, 300));
+
+    let metadata = app.metadata.as_ref().unwrap();
+    assert_eq!(metadata.format, Some(image::ImageFormat::Png));
+    assert_eq!(metadata.dimensions, Some((480, 360)));
+    assert_eq!(metadata.color, Some(image::ColorType::Rgb8));
+    assert_eq!(metadata.file_size, source.metadata().unwrap().len());
+}
+
+#[test]
+fn dropped_files_are_converted_in_order() {
+    let mut harness = Harness::new();
+    let first = harness.image("first.png");
+    let second = harness.image("second.bmp");
+
+    harness.drop_files(&[&first, &second]);
+
+    let sources: Vec<&Path> = harness
+        .app
+        .results
+        .iter()
+        .map(|result| result.source.as_path())
+        .collect();
+    assert_eq!(sources, [first.as_path(), second.as_path()]);
+    assert_eq!(harness.outputs().len(), 2);
+    assert!(harness.outputs().iter().all(|output| output.is_file()));
+    assert!(harness.app.title().starts_with("[2 ✓ 0 ✗] "));
+}
+
+#[test]
+fn unsupported_file_is_refused() {
+    let mut harness = Harness::new();
+    let notes = harness.dir.path().join("notes.txt");
+    std::fs::write(&notes, "not an image").unwrap();
+
+    harness.drop_file(&notes);
+
+    assert!(harness.app.results.is_empty());
+    assert!(harness.app.message.starts_with("Error: "));
+}
+
+#[test]
+fn same_file_dropped_twice_is_converted_once() {
+    let mut harness = Harness::new();
+    let source = harness.image("cover.png");
+
+    harness.drop_file(&source);
+    harness.drop_file(&source);
+
+    assert_eq!(harness.app.results.len(), 1);
+    assert_eq!(harness.outputs().len(), 1);
+}
+
+#[test]
+fn undo_removes_the_last_output() {
+    let mut harness = Harness::new();
+    let source = harness.image("cover.png");
+    harness.drop_file(&source);
+    let output = harness.outputs().remove(0);
+
+    harness.send(Message::Shortcut(Shortcut::Undo));
+
+    assert!(!output.exists());
+    assert!(harness.app.undo.is_empty());
+    assert_eq!(harness.app.results[0].status, FileStatus::Removed);
+    assert_eq!(harness.app.processed_image, None);
+}
+
+#[test]
+fn finished_work_is_restored_on_launch() {
+    let mut harness = Harness::new();
+    let source = harness.image("cover.png");
+    harness.drop_file(&source);
+    let outputs = harness.outputs();
+
+    let restored = launch();
+
+    assert_eq!(restored.results.len(), 1);
+    assert_eq!(restored.results[0].source, source);
+    assert_eq!(
+        restored.results[0].status,
+        FileStatus::Done(outputs.clone())
+    );
+    assert_eq!(restored.undo, [(source, outputs)]);
+}
+
+#[test]
+fn saved_profile_is_kept_and_picked_again() {
+    let mut harness = Harness::new();
+
+    harness.send(Message::OptionsProfileNameChanged("Car stereo".to_string()));
+    harness.send(Message::SaveOptionsProfile);
+    assert_eq!(harness.app.message, "Profile Car stereo saved");
+
+    harness.send(Message::JpegQualityChanged(40));
+    harness.send(Message::OptionsProfileSelected("Car stereo".to_string()));
+
+    let saved = settings::load_saved().unwrap();
+    assert_eq!(saved.option_profiles.len(), 1);
+    assert_eq!(saved.option_profiles[0].name, "Car stereo");
+    assert_ne!(saved.option_profiles[0].options.jpeg_quality, 40);
+    assert_eq!(
+        saved.options.jpeg_quality,
+        saved.option_profiles[0].options.jpeg_quality
+    );
+}
+
+#[test]
+fn failed_files_are_retried_together() {
+    let mut harness = Harness::new();
+    let first = harness.dir.path().join("first.png");
+    let second = harness.dir.path().join("second.png");
+    std::fs::write(&first, b"first, not copied yet").unwrap();
+    std::fs::write(&second, b"second, not copied yet").unwrap();
+    harness.drop_files(&[&first, &second]);
+    assert!(
+        harness
+            .app
+            .results
+            .iter()
+            .all(|result| matches!(result.status, FileStatus::Failed(_)))
+    );
+
+    harness.image("first.png");
+    harness.image("second.png");
+    harness.send(Message::RetryFailed);
+    assert_eq!(harness.outputs().len(), 2);
+    assert!(harness.app.title().starts_with("[2 ✓ 0 ✗] "));
+}
+
+#[test]
+fn chosen_square_is_converted_instead_of_the_center() {
+    let mut harness = Harness::new();
+    let source = harness.image("cover.png");
+    harness.drop_file(&source);
+
+    harness.send(Message::OpenCropEditor);
+    harness.send(Message::CropChanged(Square {
+        x: 0,
+        y: 0,
+        side: 200,
+    }));
+    harness.send(Message::ApplyCrop);
+
+    let output = harness.outputs().remove(0);
+    assert_eq!(image::image_dimensions(&output).unwrap(), (200, 200));
+    let cropped = image::open(&output).unwrap().to_rgb8();
+    assert_eq!(cropped.get_pixel(199, 199).0[..2], [199, 199]);
+    assert_eq!(
+        harness.app.crop,
+        Some((
+            source,
+            Square {
+                x: 0,
+                y: 0,
+                side: 200
+            }
+        ))
+    );
+}
+
+#[test]
+fn nightly_library_check_is_remembered() {
+    let mut harness = Harness::new();
+    let music = harness.dir.path().join("music");
+
+    harness.send(Message::LibraryFolderChanged(music.display().to_string()));
+    harness.send(Message::NightlyToggled(true));
+    assert_eq!(
+        settings::load_saved().unwrap().nightly_library,
+        Some(music.clone())
+    );
+
+    // Launched again, the folder stays scheduled
+    harness.app = launch();
+    harness.send(Message::NightlyCheck);
+    assert_eq!(settings::load_saved().unwrap().nightly_library, Some(music));
+
+    harness.send(Message::NightlyToggled(false));
+    assert_eq!(settings::load_saved().unwrap().nightly_library, None);
+}
+
+#[test]
+fn overrides_are_not_saved() {
+    let mut harness = Harness::new();
+    let quality = settings::load_saved().unwrap().options.jpeg_quality;
+    // SAFETY: as in Harness::new
+    unsafe { std::env::set_var("ARTCOVER_JPEG_QUALITY", "33") };
+    harness.app = launch();
+    unsafe { std::env::remove_var("ARTCOVER_JPEG_QUALITY") };
+
+    harness.send(Message::AutoTrimToggled(true));
+
+    let saved = settings::load_saved().unwrap();
+    assert_eq!(saved.options.jpeg_quality, quality);
+    assert!(saved.options.auto_trim);
+}
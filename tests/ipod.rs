@@ -0,0 +1,76 @@
+use artcover_image_conversor::ipod::{self, Action, Destination};
+use artcover_image_conversor::library;
+use std::fs;
+use std::path::Path;
+
+// An album folder with a track, and a cover.jpg when `cover` is given
+fn album(folder: &Path, cover: Option<&[u8]>) {
+    fs::create_dir_all(folder).unwrap();
+    fs::write(folder.join("01.mp3"), b"").unwrap();
+    if let Some(cover) = cover {
+        fs::write(folder.join(library::COVER_FILE_NAME), cover).unwrap();
+    }
+}
+
+#[test]
+fn preview_writes_nothing_and_copy_fills_matching_albums() {
+    let dir = tempfile::tempdir().unwrap();
+    let music = dir.path().join("music");
+    let device = dir.path().join("device");
+    album(&music.join("Artist").join("Album"), Some(b"new cover"));
+    album(&music.join("Artist").join("Other"), Some(b"same cover"));
+    album(&music.join("Artist").join("Missing"), Some(b"cover"));
+    album(&music.join("Artist").join("Unconverted"), None);
+    album(&device.join("Music").join("artist").join("album"), None);
+    album(
+        &device.join("Music").join("Artist").join("Other"),
+        Some(b"same cover"),
+    );
+    album(
+        &device.join("Music").join("Artist").join("Unconverted"),
+        None,
+    );
+
+    let albums = library::scan(&music).unwrap();
+    let plan = ipod::plan(&music, &albums, &device, &Destination::Albums).unwrap();
+
+    let device_cover = device
+        .join("Music")
+        .join("artist")
+        .join("album")
+        .join(library::COVER_FILE_NAME);
+    assert_eq!(plan.copies.len(), 2);
+    assert_eq!(plan.copies[0].destination, device_cover);
+    assert_eq!(plan.copies[0].action, Action::New);
+    assert_eq!(plan.copies[1].action, Action::Unchanged);
+    assert_eq!(plan.unmatched, [music.join("Artist").join("Missing")]);
+    assert!(!device_cover.exists());
+
+    for copy in &plan.copies {
+        ipod::write(copy).unwrap();
+    }
+    assert_eq!(fs::read(&device_cover).unwrap(), b"new cover");
+
+    let again = ipod::plan(&music, &albums, &device, &Destination::Albums).unwrap();
+    assert_eq!(again.count(Action::Unchanged), 2);
+}
+
+#[test]
+fn artwork_folder_gets_one_file_per_album() {
+    let dir = tempfile::tempdir().unwrap();
+    let music = dir.path().join("music");
+    let artwork = dir.path().join("device").join("Artwork");
+    album(&music.join("Artist").join("Album: Deluxe"), Some(b"cover"));
+
+    let albums = library::scan(&music).unwrap();
+    let destination = Destination::Folder(artwork.clone());
+    let plan = ipod::plan(&music, &albums, &dir.path().join("device"), &destination).unwrap();
+
+    assert_eq!(plan.copies.len(), 1);
+    assert_eq!(
+        plan.copies[0].destination,
+        artwork.join("Artist - Album_ Deluxe.jpg")
+    );
+    ipod::write(&plan.copies[0]).unwrap();
+    assert_eq!(fs::read(&plan.copies[0].destination).unwrap(), b"cover");
+}
@@ -0,0 +1,88 @@
+use artcover_image_conversor::{ArtCoverError, ProcessOptions, pipeline, process_image};
+use image::{Rgb, RgbImage};
+use std::fs;
+
+// Headers of a 24-bit 50000x50000 bitmap, without the pixels
+fn absurd_bmp() -> Vec<u8> {
+    let mut bmp = b"BM".to_vec();
+    bmp.extend_from_slice(&0u32.to_le_bytes());
+    bmp.extend_from_slice(&0u32.to_le_bytes());
+    bmp.extend_from_slice(&54u32.to_le_bytes());
+    bmp.extend_from_slice(&40u32.to_le_bytes());
+    bmp.extend_from_slice(&50_000i32.to_le_bytes());
+    bmp.extend_from_slice(&50_000i32.to_le_bytes());
+    bmp.extend_from_slice(&1u16.to_le_bytes());
+    bmp.extend_from_slice(&24u16.to_le_bytes());
+    bmp.extend_from_slice(&[0; 24]);
+    bmp
+}
+
+// 50000x50000 GIF canvas holding a single 1x1 frame
+fn absurd_gif() -> Vec<u8> {
+    let mut gif = b"GIF89a".to_vec();
+    gif.extend_from_slice(&50_000u16.to_le_bytes());
+    gif.extend_from_slice(&50_000u16.to_le_bytes());
+    gif.extend_from_slice(&[0x00, 0x00, 0x00]);
+    gif.extend_from_slice(&[0x2C, 0, 0, 0, 0, 1, 0, 1, 0, 0x00]);
+    gif.extend_from_slice(&[0x02, 0x02, 0x44, 0x01, 0x00, 0x3B]);
+    gif
+}
+
+fn is_absurd(error: &ArtCoverError) -> bool {
+    matches!(
+        error,
+        ArtCoverError::TooLarge {
+            width: 50_000,
+            height: 50_000,
+            ..
+        }
+    )
+}
+
+#[test]
+fn tiff_scan_is_converted() {
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("sleeve.tif");
+    RgbImage::from_fn(640, 480, |x, y| Rgb([(x % 256) as u8, (y % 256) as u8, 64]))
+        .save(&source)
+        .unwrap();
+
+    let processed = process_image(&source, &ProcessOptions::default()).unwrap();
+
+    assert_ne!(processed.path.extension().unwrap(), "tif");
+    assert_eq!(
+        image::image_dimensions(&processed.path).unwrap(),
+        (300, 300)
+    );
+}
+
+#[test]
+fn absurd_dimensions_are_refused_before_decoding() {
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("broken.bmp");
+    fs::write(&source, absurd_bmp()).unwrap();
+
+    let error = process_image(&source, &ProcessOptions::default()).unwrap_err();
+
+    assert!(is_absurd(&error));
+}
+
+#[test]
+fn absurd_dimensions_in_memory_are_refused_too() {
+    let error = pipeline::convert_bytes(&absurd_bmp(), &ProcessOptions::default()).unwrap_err();
+
+    assert!(is_absurd(&error));
+}
+
+#[test]
+fn absurd_gif_canvas_is_refused() {
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("broken.gif");
+    fs::write(&source, absurd_gif()).unwrap();
+
+    let error = process_image(&source, &ProcessOptions::default()).unwrap_err();
+    assert!(is_absurd(&error), "{:?}", error);
+
+    let error = pipeline::convert_bytes(&absurd_gif(), &ProcessOptions::default()).unwrap_err();
+    assert!(is_absurd(&error), "{:?}", error);
+}
@@ -0,0 +1,49 @@
+use artcover_image_conversor::{CancelToken, ProcessOptions, library};
+use image::RgbImage;
+use std::fs;
+use std::path::Path;
+
+// An album folder with a track and a 600x600 folder.png
+fn album(folder: &Path) {
+    fs::create_dir_all(folder).unwrap();
+    fs::write(folder.join("01.mp3"), b"").unwrap();
+    RgbImage::from_pixel(600, 600, image::Rgb([10, 120, 60]))
+        .save(folder.join("folder.png"))
+        .unwrap();
+}
+
+#[test]
+fn existing_cover_is_kept_unless_replacing() {
+    let dir = tempfile::tempdir().unwrap();
+    let folder = dir.path().join("Album");
+    album(&folder);
+    let cover = folder.join(library::COVER_FILE_NAME);
+    fs::write(&cover, b"picked by hand").unwrap();
+
+    let albums = library::scan(dir.path()).unwrap();
+    assert!(albums[0].has_cover);
+    let options = ProcessOptions::default();
+    let cancel = CancelToken::default();
+
+    let kept = library::convert_album(&albums[0], &options, false, &cancel).unwrap();
+    assert!(kept.is_none());
+    assert_eq!(fs::read(&cover).unwrap(), b"picked by hand");
+
+    let replaced = library::convert_album(&albums[0], &options, true, &cancel).unwrap();
+    assert_eq!(replaced.unwrap().path, cover);
+    assert_eq!(image::image_dimensions(&cover).unwrap(), (300, 300));
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinked_folders_are_not_followed() {
+    let dir = tempfile::tempdir().unwrap();
+    let folder = dir.path().join("Artist").join("Album");
+    album(&folder);
+    std::os::unix::fs::symlink(dir.path(), folder.join("loop")).unwrap();
+
+    let albums = library::scan(dir.path()).unwrap();
+
+    assert_eq!(albums.len(), 1);
+    assert_eq!(albums[0].folder, folder);
+}
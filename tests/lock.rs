@@ -0,0 +1,56 @@
+use artcover_image_conversor::{CancelToken, lock};
+use std::fs::{self, File};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+#[test]
+fn stale_lock_is_taken_over_and_released_on_drop() {
+    let dir = tempfile::tempdir().unwrap();
+    let target = dir.path().join("cover.jpg");
+    let lock_file = dir.path().join(".cover.jpg.artcover-lock");
+    // Left by a process that is still running but stopped refreshing it
+    fs::write(&lock_file, std::process::id().to_string()).unwrap();
+    File::options()
+        .write(true)
+        .open(&lock_file)
+        .unwrap()
+        .set_modified(SystemTime::now() - Duration::from_secs(600))
+        .unwrap();
+
+    let held = lock::acquire(&target, &CancelToken::default()).unwrap();
+    let age = fs::metadata(&lock_file)
+        .unwrap()
+        .modified()
+        .unwrap()
+        .elapsed()
+        .unwrap();
+    assert!(age < Duration::from_secs(60));
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+
+    drop(held);
+    assert!(!lock_file.exists());
+}
+
+#[test]
+fn holders_take_turns() {
+    let dir = tempfile::tempdir().unwrap();
+    let target = Arc::new(dir.path().join("cover.jpg"));
+    let inside = Arc::new(Mutex::new(0));
+
+    let workers: Vec<_> = (0..4)
+        .map(|_| {
+            let (target, inside) = (Arc::clone(&target), Arc::clone(&inside));
+            thread::spawn(move || {
+                let _lock = lock::acquire(&target, &CancelToken::default()).unwrap();
+                *inside.lock().unwrap() += 1;
+                assert_eq!(*inside.lock().unwrap(), 1);
+                thread::sleep(Duration::from_millis(20));
+                *inside.lock().unwrap() -= 1;
+            })
+        })
+        .collect();
+    for worker in workers {
+        worker.join().unwrap();
+    }
+}
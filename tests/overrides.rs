@@ -0,0 +1,108 @@
+use artcover_image_conversor::ProcessOptions;
+use artcover_image_conversor::settings::{Appearance, Bundle, OptionsProfile};
+use artcover_image_conversor::{overrides, preset};
+
+// Saved settings with a "Small" profile using the "iPod (PNG)" preset
+fn saved() -> Bundle {
+    let options = ProcessOptions {
+        preset: preset::find("iPod (PNG)", &[]).unwrap(),
+        jpeg_quality: 50,
+        ..ProcessOptions::default()
+    };
+    Bundle {
+        option_profiles: vec![OptionsProfile {
+            name: "Small".to_string(),
+            options,
+        }],
+        ..Bundle::default()
+    }
+}
+
+fn flags(flags: &[(&str, &str)]) -> Vec<(String, String)> {
+    flags
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+fn env(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+    flags(vars)
+}
+
+#[test]
+fn profile_flag_applies_first_in_any_order() {
+    for order in [
+        flags(&[("preset", "iPod (JPEG)"), ("profile", "small")]),
+        flags(&[("profile", "small"), ("preset", "iPod (JPEG)")]),
+    ] {
+        let mut bundle = saved();
+        overrides::apply_all(&mut bundle, Vec::new(), &order).unwrap();
+
+        assert_eq!(bundle.options.preset.name, "iPod (JPEG)");
+        assert_eq!(bundle.options.jpeg_quality, 50);
+    }
+}
+
+#[test]
+fn variables_apply_over_a_profile_and_flags_over_variables() {
+    let mut bundle = saved();
+    overrides::apply_all(
+        &mut bundle,
+        env(&[
+            ("ARTCOVER_JPEG_QUALITY", "70"),
+            ("ARTCOVER_PROFILE", "Small"),
+            ("ARTCOVER_APPEARANCE", "dark"),
+        ]),
+        &flags(&[("jpeg-quality", "90")]),
+    )
+    .unwrap();
+    assert_eq!(bundle.options.preset.name, "iPod (PNG)");
+    assert_eq!(bundle.options.jpeg_quality, 90);
+    assert_eq!(bundle.appearance, Appearance::Dark);
+
+    let mut bundle = saved();
+    overrides::apply_all(
+        &mut bundle,
+        env(&[("ARTCOVER_JPEG_QUALITY", "70")]),
+        &flags(&[("profile", "Small")]),
+    )
+    .unwrap();
+    assert_eq!(bundle.options.jpeg_quality, 70);
+}
+
+#[test]
+fn errors_name_the_variable_or_flag() {
+    let mut bundle = saved();
+    let error = overrides::apply_all(&mut bundle, env(&[("ARTCOVER_APPEARANCE", "pink")]), &[])
+        .unwrap_err();
+    assert!(error.starts_with("ARTCOVER_APPEARANCE: "), "{}", error);
+
+    let error =
+        overrides::apply_all(&mut bundle, Vec::new(), &flags(&[("profile", "Huge")])).unwrap_err();
+    assert!(error.starts_with("--profile: "), "{}", error);
+}
+
+#[test]
+fn only_values_changed_by_the_user_are_saved() {
+    let saved = saved();
+    let mut overridden = saved.clone();
+    overrides::apply_all(
+        &mut overridden,
+        env(&[
+            ("ARTCOVER_JPEG_QUALITY", "70"),
+            ("ARTCOVER_APPEARANCE", "dark"),
+        ]),
+        &[],
+    )
+    .unwrap();
+
+    let mut current = overridden.clone();
+    current.options.auto_trim = true;
+    current.appearance = Appearance::Light;
+
+    let own = overrides::own_values(&saved, &overridden, &current);
+    assert_eq!(own.options.jpeg_quality, saved.options.jpeg_quality);
+    assert!(own.options.auto_trim);
+    assert_eq!(own.appearance, Appearance::Light);
+    assert_eq!(own.option_profiles.len(), 1);
+}
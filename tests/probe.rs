@@ -0,0 +1,40 @@
+use artcover_image_conversor::i18n::{self, Language};
+use artcover_image_conversor::probe::{self, InputEncoding};
+
+#[test]
+fn malformed_jpeg_segments_are_not_read_past() {
+    // APP0 claiming a length shorter than the length field itself
+    let encoding = probe::probe_bytes(&[0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x00, 0x00, 0x00]);
+    assert_eq!(encoding, InputEncoding::default());
+
+    let encoding = probe::probe_bytes(&[0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x01]);
+    assert_eq!(encoding, InputEncoding::default());
+}
+
+#[test]
+fn truncated_frame_header_is_still_recognized() {
+    // Progressive frame header cut off before the component count
+    let encoding = probe::probe_bytes(&[0xFF, 0xD8, 0xFF, 0xC2, 0x00, 0x11, 0x08, 0x01]);
+    assert!(encoding.progressive_jpeg);
+    assert!(!encoding.cmyk_jpeg);
+
+    // Segment length running past the end of the data
+    let encoding = probe::probe_bytes(&[0xFF, 0xD8, 0xFF, 0xE1, 0xFF, 0xFF, 0x00]);
+    assert_eq!(encoding, InputEncoding::default());
+}
+
+#[test]
+fn warnings_follow_the_interface_language() {
+    let encoding = InputEncoding {
+        cmyk_jpeg: true,
+        ..InputEncoding::default()
+    };
+
+    i18n::set_language(Language::Spanish);
+    let spanish = encoding.warnings();
+    i18n::set_language(Language::English);
+    let english = encoding.warnings();
+
+    assert_eq!(spanish, ["JPEG CMYK convertido a RGB"]);
+    assert_eq!(english, ["CMYK JPEG converted to RGB"]);
+}
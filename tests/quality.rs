@@ -0,0 +1,39 @@
+use artcover_image_conversor::encode::{self, EncodeSettings};
+use artcover_image_conversor::quality;
+use image::{DynamicImage, Rgb, RgbImage};
+use std::path::Path;
+
+// Gradient with a checkered detail, so quality visibly matters
+fn cover() -> DynamicImage {
+    DynamicImage::ImageRgb8(RgbImage::from_fn(96, 96, |x, y| {
+        let detail = if (x / 4 + y / 4) % 2 == 0 { 40 } else { 0 };
+        Rgb([(x * 2 + detail) as u8, (y * 2) as u8, 120])
+    }))
+}
+
+#[test]
+fn chosen_quality_reaches_the_target() {
+    let img = cover();
+    let path = Path::new("cover.jpg");
+    let settings = EncodeSettings::with_quality(90);
+
+    let (loose, _) = quality::find_jpeg_quality(&img, path, 0.9, &settings).unwrap();
+    let (strict, bytes) = quality::find_jpeg_quality(&img, path, 0.99, &settings).unwrap();
+    assert!(loose <= strict);
+
+    // Found and fallback results alike are encoded with the given settings
+    let same = EncodeSettings {
+        jpeg_quality: strict,
+        ..settings
+    };
+    assert_eq!(bytes, encode::encode(&img, path, &same).unwrap());
+
+    let decoded = image::load_from_memory(&bytes).unwrap().to_luma8();
+    assert!(quality::ssim(&img.to_luma8(), &decoded) >= 0.99 || strict == 95);
+}
+
+#[test]
+fn identical_images_score_one() {
+    let luma = cover().to_luma8();
+    assert!((quality::ssim(&luma, &luma) - 1.0).abs() < 1e-9);
+}
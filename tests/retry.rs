@@ -0,0 +1,48 @@
+use artcover_image_conversor::retry;
+use artcover_image_conversor::{ArtCoverError, CancelToken};
+use std::io;
+use std::path::Path;
+
+fn failure(kind: io::ErrorKind) -> ArtCoverError {
+    ArtCoverError::io(Path::new("cover.jpg"), io::Error::from(kind))
+}
+
+#[test]
+fn passing_failures_are_retried() {
+    let mut attempts = 0;
+    let result = retry::with_retries(2, &CancelToken::default(), || {
+        attempts += 1;
+        match attempts {
+            1 => Err(failure(io::ErrorKind::TimedOut)),
+            _ => Ok(attempts),
+        }
+    });
+
+    assert_eq!(result.unwrap(), 2);
+}
+
+#[test]
+fn locked_files_are_retried() {
+    let mut attempts = 0;
+    let result = retry::with_retries(1, &CancelToken::default(), || {
+        attempts += 1;
+        match attempts {
+            1 => Err(ArtCoverError::Locked(Path::new("cover.jpg").to_path_buf())),
+            _ => Ok(attempts),
+        }
+    });
+
+    assert_eq!(result.unwrap(), 2);
+}
+
+#[test]
+fn lasting_failures_are_not_retried() {
+    let mut attempts = 0;
+    let result: Result<(), _> = retry::with_retries(2, &CancelToken::default(), || {
+        attempts += 1;
+        Err(failure(io::ErrorKind::NotFound))
+    });
+
+    assert!(result.is_err());
+    assert_eq!(attempts, 1);
+}
@@ -0,0 +1,165 @@
+use artcover_image_conversor::i18n::{self, Language};
+use artcover_image_conversor::pipeline::CancelToken;
+use artcover_image_conversor::preset::TargetSize;
+use artcover_image_conversor::{ProcessOptions, probe, rockbox};
+use image::{Rgb, RgbImage};
+use std::fs;
+use std::path::Path;
+
+// Theme .cfg under a player root, its WPS holding `album_art`
+fn theme(root: &Path, album_art: &str) -> TargetSize {
+    let wps = root.join(".rockbox/wps");
+    let themes = root.join(".rockbox/themes");
+    fs::create_dir_all(&wps).unwrap();
+    fs::create_dir_all(&themes).unwrap();
+    fs::write(
+        wps.join("art.wps"),
+        format!("%s%al%?it<%it|%fn>\n{}\n", album_art),
+    )
+    .unwrap();
+    let cfg = themes.join("art.cfg");
+    fs::write(
+        &cfg,
+        "# art\nwps: /.rockbox/wps/art.wps\nforeground color: FFFFFF\n",
+    )
+    .unwrap();
+    rockbox::import_theme(&cfg).unwrap().size
+}
+
+#[test]
+fn album_art_bounds_are_read_in_both_syntaxes() {
+    let dir = tempfile::tempdir().unwrap();
+    let cases = [
+        ("%Cl(10,20,150,120)", (150, 120)),
+        ("%Cl(10,20,c150,b120)", (150, 120)),
+        ("%Cl(10,20,-,-)", (100, 100)),
+        // Pre-3.5 themes
+        ("%Cl|10|20|150|120|", (150, 120)),
+        ("%Cl|10|20|c150|b120|", (150, 120)),
+        ("%Cl|10|20|r90|t|", (90, 100)),
+        // The first live tag counts, not one commented out
+        ("# %Cl(0,0,40,40)\n%Cl(10,20,150,120)", (150, 120)),
+    ];
+    for (album_art, (width, height)) in cases {
+        assert_eq!(
+            theme(dir.path(), album_art),
+            TargetSize::Max(width, height),
+            "{}",
+            album_art
+        );
+    }
+}
+
+// Flat gray 8x8 grayscale JPEG in two progressive scans (DC, then AC), each
+// coded with a one-symbol Huffman table
+fn progressive_jpeg() -> Vec<u8> {
+    let mut bytes = vec![0xFF, 0xD8];
+    bytes.extend([0xFF, 0xDB, 0x00, 0x43, 0x00]);
+    bytes.extend([1; 64]);
+    bytes.extend([
+        0xFF, 0xC2, 0x00, 0x0B, 0x08, 0x00, 0x08, 0x00, 0x08, 0x01, 0x01, 0x11, 0x00,
+    ]);
+    for class in [0x00, 0x10] {
+        bytes.extend([0xFF, 0xC4, 0x00, 0x14, class, 0x01]);
+        bytes.extend([0; 16]);
+    }
+    for (start, end) in [(0x00, 0x00), (0x01, 0x3F)] {
+        bytes.extend([
+            0xFF, 0xDA, 0x00, 0x08, 0x01, 0x01, 0x00, start, end, 0x00, 0x7F,
+        ]);
+    }
+    bytes.extend([0xFF, 0xD9]);
+    bytes
+}
+
+#[test]
+fn progressive_sources_are_saved_as_baseline() {
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("front.jpg");
+    fs::write(&source, progressive_jpeg()).unwrap();
+    assert!(probe::probe(&source).unwrap().progressive_jpeg);
+
+    // Copying conforming files and leaving progressive JPEGs alone would
+    // otherwise keep the source as is
+    let options = ProcessOptions {
+        baseline_jpeg: false,
+        ..Default::default()
+    };
+    let target = &rockbox::TARGETS[0];
+    let results = rockbox::export_album_art(
+        &source,
+        target,
+        &options,
+        false,
+        &mut |_| {},
+        &CancelToken::default(),
+    );
+
+    assert_eq!(results.len(), target.art_sizes.len() + 1);
+    for result in results {
+        let image = result.unwrap();
+        assert!(!image.passed_through);
+        assert!(!probe::probe(&image.path).unwrap().progressive_jpeg);
+    }
+}
+
+#[test]
+fn existing_covers_and_the_source_are_never_written_over() {
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("cover.jpg");
+    RgbImage::from_pixel(800, 800, Rgb([30, 90, 160]))
+        .save(&source)
+        .unwrap();
+    let original = fs::read(&source).unwrap();
+    let sized = dir.path().join("cover.100x100.jpg");
+    fs::write(&sized, b"earlier").unwrap();
+
+    let target = &rockbox::TARGETS[0];
+    let export = |replace| {
+        rockbox::export_album_art(
+            &source,
+            target,
+            &ProcessOptions::default(),
+            replace,
+            &mut |_| {},
+            &CancelToken::default(),
+        )
+    };
+
+    let results = export(false);
+    assert!(results[0].is_err());
+    assert!(results[1].is_err());
+    assert_eq!(fs::read(&sized).unwrap(), b"earlier");
+    let made = results[2].as_ref().unwrap();
+    assert_eq!(made.path, dir.path().join("cover.200x200.jpg"));
+    assert_eq!(image::image_dimensions(&made.path).unwrap(), (200, 200));
+
+    // Replacing still keeps the source, the other sizes are made from it
+    let results = export(true);
+    assert!(results[0].is_err());
+    assert_eq!(image::image_dimensions(&sized).unwrap(), (100, 100));
+    assert!(results[2].is_ok());
+    assert_eq!(fs::read(&source).unwrap(), original);
+
+    let left: Vec<_> = fs::read_dir(dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .filter(|name| name.to_string_lossy().starts_with(".artcover-rockbox"))
+        .collect();
+    assert!(left.is_empty());
+}
+
+#[test]
+fn theme_errors_follow_the_interface_language() {
+    let dir = tempfile::tempdir().unwrap();
+    let cfg = dir.path().join("art.cfg");
+    fs::write(&cfg, "wps: /.rockbox/wps/none.wps\n").unwrap();
+
+    i18n::set_language(Language::Spanish);
+    let error = rockbox::import_theme(&cfg).unwrap_err();
+    i18n::set_language(Language::English);
+    assert_eq!(
+        error.user_message(),
+        "No se encontró el archivo WPS: /.rockbox/wps/none.wps"
+    );
+}
@@ -0,0 +1,38 @@
+use artcover_image_conversor::schedule::{self, NIGHTLY_HOUR};
+use chrono::{FixedOffset, TimeZone, Utc};
+
+#[test]
+fn next_run_is_the_coming_night() {
+    let at = |day, hour, minute| Utc.with_ymd_and_hms(2026, 3, day, hour, minute, 0).unwrap();
+
+    assert_eq!(
+        schedule::next_run(&at(10, 1, 30), NIGHTLY_HOUR),
+        at(10, 3, 0)
+    );
+    assert_eq!(
+        schedule::next_run(&at(10, 3, 0), NIGHTLY_HOUR),
+        at(11, 3, 0)
+    );
+    assert_eq!(
+        schedule::next_run(&at(10, 23, 59), NIGHTLY_HOUR),
+        at(11, 3, 0)
+    );
+    // Across the end of the month
+    assert_eq!(
+        schedule::next_run(&at(31, 12, 0), NIGHTLY_HOUR),
+        Utc.with_ymd_and_hms(2026, 4, 1, 3, 0, 0).unwrap()
+    );
+}
+
+#[test]
+fn next_run_follows_the_local_clock() {
+    let madrid = FixedOffset::east_opt(2 * 3600).unwrap();
+    // 02:30 in Madrid is 00:30 UTC; the run is at 03:00 Madrid time
+    let now = madrid.with_ymd_and_hms(2026, 7, 1, 2, 30, 0).unwrap();
+    let run = schedule::next_run(&now, NIGHTLY_HOUR);
+    assert_eq!(run, madrid.with_ymd_and_hms(2026, 7, 1, 3, 0, 0).unwrap());
+    assert_eq!(
+        run.with_timezone(&Utc),
+        Utc.with_ymd_and_hms(2026, 7, 1, 1, 0, 0).unwrap()
+    );
+}
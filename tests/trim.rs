@@ -0,0 +1,31 @@
+use artcover_image_conversor::trim;
+use image::{DynamicImage, GenericImageView, Rgb, RgbImage};
+
+// 100x100 scan: a dark 80x80 artwork inside a paper margin whose outermost
+// line is lighter than the rest, with a speck of dust on the margin
+fn scan() -> DynamicImage {
+    let mut image = RgbImage::from_fn(100, 100, |x, y| {
+        if (10..90).contains(&x) && (10..90).contains(&y) {
+            Rgb([40, 30, 90])
+        } else if x == 0 || y == 0 || x == 99 || y == 99 {
+            Rgb([250, 250, 250])
+        } else {
+            Rgb([235, 235, 235])
+        }
+    });
+    image.put_pixel(50, 4, Rgb([0, 0, 0]));
+    DynamicImage::ImageRgb8(image)
+}
+
+#[test]
+fn tolerance_decides_how_far_the_margin_goes() {
+    assert_eq!(trim::trim_borders(&scan(), 24).dimensions(), (80, 80));
+    // The inner margin is too far from the outer line's color
+    assert_eq!(trim::trim_borders(&scan(), 5).dimensions(), (98, 98));
+}
+
+#[test]
+fn uniform_images_are_kept_whole() {
+    let blank = DynamicImage::ImageRgb8(RgbImage::from_pixel(60, 40, Rgb([255, 255, 255])));
+    assert_eq!(trim::trim_borders(&blank, 24).dimensions(), (60, 40));
+}